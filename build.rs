@@ -1,7 +1,22 @@
-// Simple build script that copies static assets to `dist/` after wasm-pack build.
+// Simple build script that validates the GLSL sources and copies static assets to
+// `dist/` after wasm-pack build.
 use std::process::Command;
 use std::{env, fs, path::Path};
 
+use naga::front::glsl::{Frontend, Options};
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use naga::ShaderStage;
+
+// Same preprocessor the renderer uses, so includes and defines resolve identically.
+#[allow(dead_code)]
+#[path = "src/glsl.rs"]
+mod glsl;
+
+// Same parser and rasterizer the tests check.
+#[allow(dead_code)]
+#[path = "src/font.rs"]
+mod font;
+
 fn main() {
     // ----------------------------------------------------------------------------------
     // 1. Avoid invoking `wasm-pack` from within the build-script
@@ -35,7 +50,20 @@ fn main() {
     }
 
     // ----------------------------------------------------------------------------------
-    // 2. Copy the static assets to the distributable directory
+    // 2. Validate every shader so GLSL mistakes fail the build instead of the page
+    // ----------------------------------------------------------------------------------
+    let shader_dir = Path::new("static/shaders");
+    let errors = validate_shaders(shader_dir);
+    if !errors.is_empty() {
+        for e in &errors {
+            eprintln!("error: {e}");
+        }
+        eprintln!("{} shader error(s) in {}", errors.len(), shader_dir.display());
+        std::process::exit(1);
+    }
+
+    // ----------------------------------------------------------------------------------
+    // 3. Copy the static assets to the distributable directory
     // ----------------------------------------------------------------------------------
     let out_dir = Path::new("dist");
     if out_dir.exists() {
@@ -61,7 +89,215 @@ fn main() {
         copy_dir(static_dir, out_dir);
     }
 
+    // ----------------------------------------------------------------------------------
+    // 4. Turn the lettering font into the SDF atlas the Text scene embeds
+    // ----------------------------------------------------------------------------------
+    let font_path = env::var("VIZ_FONT").unwrap_or_else(|_| "fonts/DejaVuSans-Bold.ttf".into());
+    let atlas = fs::read(&font_path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| font::Font::parse(&data).map(|f| font::Atlas::build(&f, font::CHARSET)));
+    let atlas = match atlas {
+        Ok(atlas) => atlas,
+        Err(e) => {
+            eprintln!("error: {font_path}: {e}");
+            std::process::exit(1);
+        }
+    };
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("font_atlas.bin");
+    fs::write(out, bincode::serialize(&atlas).unwrap()).unwrap();
+
     // Ensure Cargo only re-runs this script when the *inputs* change, not every build.
     println!("cargo:rerun-if-changed=static");
+    println!("cargo:rerun-if-changed=src/glsl.rs");
+    println!("cargo:rerun-if-changed=src/font.rs");
+    println!("cargo:rerun-if-changed=fonts");
+    println!("cargo:rerun-if-env-changed=VIZ_FONT");
     println!("cargo:rerun-if-env-changed=WASM_BUILD");
 }
+
+// Parse and validate each `.vert` / `.frag` under `dir` (snippets are checked
+// through the files that include them). Returns `file:line: message` strings
+// pointing at the original source, not the flattened one.
+fn validate_shaders(dir: &Path) -> Vec<String> {
+    let mut roots = Vec::new();
+    collect_roots(dir, dir, &mut roots);
+    roots.sort();
+
+    let defines = glsl::builtin_defines();
+    let mut frontend = Frontend::default();
+    let mut errors = Vec::new();
+    for root in roots {
+        let stage = if root.ends_with(".vert") { ShaderStage::Vertex } else { ShaderStage::Fragment };
+        let pp = match glsl::preprocess(&root, &defines, glsl::GLOBALS_GLSL, |f| fs::read_to_string(dir.join(f)).ok()) {
+            Ok(pp) => pp,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+        let src = lower_for_naga(&pp.source);
+        let locate = |line: u32| match pp.origin(line) {
+            Some((file, l)) => format!("{file}:{l}"),
+            None => format!("{root}:?"),
+        };
+
+        let mut options = Options::from(stage);
+        for q in ["highp", "mediump", "lowp"] {
+            options.defines.insert(q.to_string(), String::new());
+        }
+        match frontend.parse(&options, &src) {
+            Ok(module) => {
+                if let Err(e) = Validator::new(ValidationFlags::all(), Capabilities::empty()).validate(&module) {
+                    let line = e.spans().next().map(|(span, _)| span.location(&src).line_number).unwrap_or(0);
+                    errors.push(format!("{}: {} (in {root})", locate(line), e.as_inner()));
+                }
+            }
+            Err(parse) => {
+                for e in parse.errors {
+                    let line = e.meta.location(&src).line_number;
+                    errors.push(format!("{}: {} (in {root})", locate(line), e.kind));
+                }
+            }
+        }
+    }
+    errors
+}
+
+fn collect_roots(base: &Path, dir: &Path, out: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_roots(base, &path, out);
+        } else if matches!(path.extension().and_then(|e| e.to_str()), Some("vert" | "frag")) {
+            let rel = path.strip_prefix(base).unwrap_or(&path);
+            out.push(rel.to_string_lossy().replace('\\', "/"));
+        }
+    }
+}
+
+// naga's GLSL frontend speaks Vulkan-flavoured GLSL 4.50, so rewrite the WebGL2
+// (GLSL ES 3.00) idioms we use into their equivalents, keeping every line in
+// place so error locations still map through the preprocessor's line table:
+// - `#version 300 es` becomes `#version 450` and precision statements are
+//   blanked (precision qualifiers are defined away by the caller);
+// - loose uniforms are wrapped in single-member blocks with their own binding;
+// - inputs and outputs without a location are numbered from 0 in order, so
+//   varyings match when both stages declare them in the same order;
+// - combined `sampler2D`s are split into `texture2D` + `sampler`, both for
+//   uniforms and function parameters, and uses are rewritten accordingly;
+// - `gl_VertexID` becomes Vulkan's `gl_VertexIndex`.
+fn lower_for_naga(src: &str) -> String {
+    let samplers = sampler_names(src);
+    let mut binding = 1;
+    let (mut inputs, mut outputs) = (0, 0);
+    let mut out = Vec::new();
+    for line in src.lines() {
+        let t = line.trim_start();
+        if t.starts_with("#version") {
+            out.push("#version 450".to_string());
+            continue;
+        }
+        if t.starts_with("precision ") {
+            out.push(String::new());
+            continue;
+        }
+        if t.starts_with('#') {
+            out.push(line.to_string());
+            continue;
+        }
+        if t.starts_with("layout(std140) uniform") {
+            out.push(line.replacen("layout(std140)", "layout(std140, binding=0)", 1));
+            continue;
+        }
+        let mut lowered = String::new();
+        for stmt in line.split_inclusive(';') {
+            let s = stmt.trim_start();
+            let indent = &stmt[..stmt.len() - s.len()];
+            if let Some(decl) = s.strip_prefix("uniform ") {
+                let decl = decl.trim_start();
+                if let Some(name) = decl.strip_prefix("sampler2D ") {
+                    let name = name.trim_end_matches(';').trim();
+                    lowered.push_str(&format!(
+                        "{indent}layout(binding={binding}) uniform texture2D {name}_t; layout(binding={}) uniform sampler {name}_s;",
+                        binding + 1
+                    ));
+                    binding += 2;
+                } else {
+                    lowered.push_str(&format!("{indent}layout(binding={binding}) uniform U{binding} {{ {decl} }};"));
+                    binding += 1;
+                }
+            } else if s.starts_with("out ") {
+                lowered.push_str(&format!("{indent}layout(location={outputs}) {s}"));
+                outputs += 1;
+            } else if s.starts_with("in ") {
+                lowered.push_str(&format!("{indent}layout(location={inputs}) {s}"));
+                inputs += 1;
+            } else {
+                lowered.push_str(&rewrite_sampler_uses(stmt, &samplers));
+            }
+        }
+        out.push(lowered.replace("gl_VertexID", "gl_VertexIndex"));
+    }
+    out.join("\n")
+}
+
+// Every identifier declared with type `sampler2D` (uniforms and parameters).
+fn sampler_names(src: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = src;
+    while let Some(i) = rest.find("sampler2D") {
+        rest = &rest[i + "sampler2D".len()..];
+        let name: String = rest.trim_start().chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+        if !name.is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+// `sampler2D x` parameters become `texture2D x_t, sampler x_s`; a sampler passed
+// to a builtin (`texture(x, ...)`) becomes `sampler2D(x_t, x_s)`, and one passed
+// to a user function becomes the two arguments `x_t, x_s`.
+fn rewrite_sampler_uses(code: &str, samplers: &[String]) -> String {
+    let mut out = String::with_capacity(code.len());
+    let mut chars = code.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if !(c.is_alphabetic() || c == '_') {
+            out.push(c);
+            continue;
+        }
+        let mut end = i + c.len_utf8();
+        while let Some(&(j, d)) = chars.peek() {
+            if !(d.is_alphanumeric() || d == '_') {
+                break;
+            }
+            end = j + d.len_utf8();
+            chars.next();
+        }
+        let ident = &code[i..end];
+        if ident == "sampler2D" {
+            let rest = code[end..].trim_start();
+            let name: String = rest.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+            if !name.is_empty() {
+                out.push_str(&format!("texture2D {name}_t, sampler {name}_s"));
+                let skip = code.len() - rest.len() + name.len() - end;
+                for _ in 0..code[end..end + skip].chars().count() {
+                    chars.next();
+                }
+                continue;
+            }
+        }
+        if samplers.iter().any(|s| s == ident) {
+            let before = out.trim_end();
+            if before.ends_with("texture(") || before.ends_with("textureLod(") || before.ends_with("texelFetch(") || before.ends_with("textureSize(") {
+                out.push_str(&format!("sampler2D({ident}_t, {ident}_s)"));
+            } else {
+                out.push_str(&format!("{ident}_t, {ident}_s"));
+            }
+            continue;
+        }
+        out.push_str(ident);
+    }
+    out
+}
@@ -0,0 +1,4 @@
+// Cheap sin-based hashes; fine for visual jitter, not for anything statistical.
+float hash11(float n) { return fract(sin(n)*43758.5453123); }
+float hash12(vec2 p) { return fract(sin(dot(p, vec2(127.1, 311.7))) * 43758.5453); }
+vec2  hash22(vec2 p) { return fract(sin(vec2(dot(p,vec2(127.1,311.7)), dot(p,vec2(269.5,183.3))))*43758.5453); }
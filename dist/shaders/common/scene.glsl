@@ -0,0 +1,9 @@
+// Shared by the SDF scenes: transform uniforms, the MRT outputs and the mapping
+// from screen UV into the scene's aspect-correct, scaled, rotated and shifted
+// space. The transform is animated from Rust (see src/anim.rs).
+// SCENE_*_LOCATION are injected from Rust so they match the framebuffer setup.
+uniform float u_scale; uniform float u_rot; uniform vec2 u_offset;
+layout(location=SCENE_COLOR_LOCATION) out vec4 o;
+layout(location=SCENE_MASK_LOCATION) out vec4 o_mask;
+
+vec2 toP(vec2 uv){ vec2 res=u_resolution; vec2 a=vec2(min(res.x,res.y))/res; vec2 p=(uv*2.0-1.0)*a*u_scale; float c=cos(u_rot), s=sin(u_rot); return mat2(c,-s,s,c)*p - u_offset; }
@@ -0,0 +1,27 @@
+#version 300 es
+// Jump flood, one pass: keep the nearest of the seeds `u_step` texels away.
+precision highp float;
+precision highp int;
+out vec4 o;
+uniform sampler2D u_seeds;
+uniform int u_step;
+
+#include "common/jfa.glsl"
+
+void main(){
+    ivec2 p = ivec2(gl_FragCoord.xy);
+    ivec2 size = textureSize(u_seeds, 0);
+    vec4 best = NO_SEED;
+    float best_d = 1e20;
+    for (int dy = -1; dy <= 1; dy++) {
+        for (int dx = -1; dx <= 1; dx++) {
+            ivec2 q = p + ivec2(dx, dy) * u_step;
+            if (!in_bounds(q, size)) continue;
+            vec4 s = texelFetch(u_seeds, q, 0);
+            if (!has_seed(s)) continue;
+            float d = distance(vec2(unpack_seed(s)), vec2(p));
+            if (d < best_d) { best_d = d; best = s; }
+        }
+    }
+    o = best;
+}
@@ -0,0 +1,22 @@
+#version 300 es
+// Particle sprites, placed like the glyph quads of text.vert. They grow in
+// after birth and shrink away before death.
+uniform float u_scale; uniform float u_rot; uniform vec2 u_offset;
+uniform float u_size;        // diameter in scene units
+layout(location=0) in vec2 a_pos;
+layout(location=1) in vec2 a_vel;
+layout(location=2) in float a_age;
+layout(location=3) in float a_life;
+out float v_heat;
+
+void main(){
+    float t = a_age / a_life;
+    float grow = a_age < 0.0 ? 0.0 : smoothstep(0.0, 0.1, t) * (1.0 - smoothstep(0.7, 1.0, t));
+    vec2 a = vec2(min(u_resolution.x, u_resolution.y)) / u_resolution;
+    float c = cos(u_rot), s = sin(u_rot);
+    vec2 q = transpose(mat2(c, -s, s, c)) * (a_pos + u_offset) / (a * u_scale);
+    gl_PointSize = u_size * grow * min(u_resolution.x, u_resolution.y) / (2.0 * u_scale);
+    v_heat = clamp(length(a_vel), 0.0, 1.0);
+    // Unborn and dead particles are clipped rather than drawn at size 0
+    gl_Position = grow > 0.0 ? vec4(q, 0.0, 1.0) : vec4(2.0, 2.0, 2.0, 1.0);
+}
@@ -0,0 +1,5 @@
+#version 300 es
+// Never runs (rasterizer discard), but a program needs a fragment stage.
+precision mediump float;
+out vec4 o;
+void main(){ o = vec4(0.0); }
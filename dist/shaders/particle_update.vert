@@ -0,0 +1,75 @@
+#version 300 es
+// Particle simulation step, run by transform feedback with rasterization off:
+// reads one particle from the current buffer and writes it to the other.
+// Mirrors `step` in src/particles.rs.
+#include "common/hash.glsl"
+
+layout(location=0) in vec2 a_pos;
+layout(location=1) in vec2 a_vel;
+layout(location=2) in float a_age;
+layout(location=3) in float a_life;
+out vec2 tf_pos;
+out vec2 tf_vel;
+out float tf_age;
+out float tf_life;
+
+uniform float u_dt;
+uniform float u_sim_time;    // the scene time the step ends at
+uniform float u_curl; uniform float u_curl_scale; uniform float u_drag;
+uniform vec2 u_life;         // lifetime range in seconds
+uniform int u_emitter_count;
+uniform vec4 u_emitters[PARTICLE_MAX_EMITTERS];     // xy position, z radius, w spread
+uniform vec2 u_emitter_vel[PARTICLE_MAX_EMITTERS];
+uniform int u_attractor_count;
+uniform vec3 u_attractors[PARTICLE_MAX_ATTRACTORS]; // xy position, z strength
+
+float value_noise(vec2 p){
+    vec2 i = floor(p), f = p - i;
+    vec2 u = f * f * (3.0 - 2.0 * f);
+    float a = hash12(i), b = hash12(i + vec2(1.0, 0.0));
+    float c = hash12(i + vec2(0.0, 1.0)), d = hash12(i + vec2(1.0, 1.0));
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+
+float potential(vec2 p){ return value_noise(p * u_curl_scale + vec2(0.0, u_sim_time * 0.2)); }
+
+vec2 curl(vec2 p){
+    float e = 0.01 / u_curl_scale;
+    float dy = potential(p + vec2(0.0, e)) - potential(p - vec2(0.0, e));
+    float dx = potential(p + vec2(e, 0.0)) - potential(p - vec2(e, 0.0));
+    return vec2(dy, -dx) / (2.0 * e);
+}
+
+void born(float i, float time){
+    vec2 r = hash22(vec2(i, time * 13.1));
+    vec2 s = hash22(vec2(time * 7.7, i * 0.37));
+    int k = min(int(r.x * float(u_emitter_count)), u_emitter_count - 1);
+    vec4 e = u_emitters[k];
+    float angle = s.x * 6.2831853;
+    float dist = sqrt(r.y) * e.z;
+    float jitter = s.y * e.w;
+    tf_pos = e.xy + vec2(cos(angle), sin(angle)) * dist;
+    tf_vel = u_emitter_vel[k] + vec2(sin(angle), -cos(angle)) * jitter;
+    tf_age = 0.0;
+    tf_life = u_life.x + (u_life.y - u_life.x) * hash12(vec2(i, 3.7));
+}
+
+void main(){
+    tf_pos = a_pos; tf_vel = a_vel; tf_age = a_age; tf_life = a_life;
+    if (a_age < 0.0) {
+        tf_age = a_age + u_dt;
+    } else if (a_age >= a_life) {
+        born(float(gl_VertexID), u_sim_time);
+    } else {
+        vec2 force = curl(a_pos) * u_curl;
+        for (int k = 0; k < PARTICLE_MAX_ATTRACTORS; k++) {
+            if (k >= u_attractor_count) break;
+            vec2 d = u_attractors[k].xy - a_pos;
+            force += d * u_attractors[k].z / (dot(d, d) + 0.05);
+        }
+        tf_vel = (a_vel + force * u_dt) * exp(-u_drag * u_dt);
+        tf_pos = a_pos + tf_vel * u_dt;
+        tf_age = a_age + u_dt;
+    }
+    gl_Position = vec4(0.0);
+}
@@ -0,0 +1,15 @@
+#version 300 es
+precision mediump float;
+#include "common/scene.glsl"
+#include "common/sdf.glsl"
+
+void main(){
+    vec2 uv=gl_FragCoord.xy/u_resolution; vec2 p=toP(uv);
+    float th=0.25+0.1*abs(sin(u_scene_time*2.0));
+    float d=min(sdBox(p, vec2(0.8, th)), sdBox(p, vec2(th, 0.8)));
+    float clip=1.0 - smoothstep(0.85, 1.0, length(p));
+    float a=smoothstep(0.0,-0.005,d)*clip;
+    float m=step(d,0.0)*clip;
+    o=vec4(1.0,1.0,0.0,a);
+    o_mask=vec4(m,m,m,1.0);
+}
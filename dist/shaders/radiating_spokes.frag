@@ -0,0 +1,15 @@
+#version 300 es
+precision mediump float;
+#include "common/scene.glsl"
+
+void main(){
+    vec2 uv=gl_FragCoord.xy/u_resolution; vec2 p=toP(uv);
+    float th=atan(p.y,p.x); float r=length(p);
+    float n=18.0; float w=0.12;
+    float band = abs(sin(th*n + u_scene_time*0.6));
+    float clip=1.0 - smoothstep(0.85, 1.0, r);
+    float a = smoothstep(w,w-0.01,band) * smoothstep(0.9,0.2,r) * clip;
+    float m = step(band,w) * step(r,0.95) * clip;
+    o=vec4(0.0,0.8,1.0,a);
+    o_mask=vec4(m,m,m,1.0);
+}
@@ -0,0 +1,33 @@
+#version 300 es
+// Image or SVG silhouette: the picture is the colour pass, its alpha (or the
+// jump-flood distance field of it) the shape. Placement matches `Fit::scale`
+// in src/silhouette.rs.
+precision mediump float;
+#include "common/scene.glsl"
+
+uniform sampler2D u_image;
+uniform sampler2D u_sdf;
+uniform vec2 u_fit;          // scene space to picture UV
+uniform float u_has_sdf;     // 1 when u_sdf holds the distance field
+uniform float u_sdf_scale;   // field value - 0.5 to scene-space distance
+
+void main(){
+    vec2 p = toP(gl_FragCoord.xy / u_resolution);
+    vec2 uv = 0.5 + 0.5 * p * u_fit;
+    // Rasterized top row first
+    uv.y = 1.0 - uv.y;
+    float inside = step(0.0, uv.x) * step(uv.x, 1.0) * step(0.0, uv.y) * step(uv.y, 1.0);
+    vec4 img = texture(u_image, uv);
+    float a;
+    float m;
+    if (u_has_sdf > 0.5) {
+        float d = (texture(u_sdf, uv).r - 0.5) * u_sdf_scale;
+        a = smoothstep(0.0, -0.005, d);
+        m = step(d, 0.0);
+    } else {
+        a = img.a;
+        m = step(0.5, img.a);
+    }
+    o = vec4(img.rgb, a * inside);
+    o_mask = vec4(vec3(m * inside), 1.0);
+}
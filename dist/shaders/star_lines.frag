@@ -0,0 +1,19 @@
+#version 300 es
+precision mediump float;
+#include "common/scene.glsl"
+
+// star via angular radius modulation
+void main(){
+    vec2 uv=gl_FragCoord.xy/u_resolution; vec2 p=toP(uv);
+    float th=atan(p.y,p.x); float r=length(p);
+    float k=5.0; float r1=0.75, r2=0.35;
+    float rr = mix(r1, r2, 0.5+0.5*cos(th*k));
+    float clip=1.0 - smoothstep(0.85, 1.0, r);
+    float a = smoothstep(rr, rr-0.01, r)*clip;
+    float m = step(r, rr)*clip;
+    // |sin| peaks twice per period: 5/pi blinks per second unless capped
+    float blink=abs(sin(u_scene_time*min(5.0, 3.14159265*u_max_flash_hz)));
+    vec3 col=vec3(1.0, blink, 0.0);
+    o=vec4(col, a);
+    o_mask=vec4(m,m,m,1.0);
+}
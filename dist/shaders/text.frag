@@ -0,0 +1,18 @@
+#version 300 es
+// Text scene: each glyph quad samples the font atlas distance field built by
+// src/font.rs. highp so the transform uniforms match text.vert's.
+precision highp float;
+#include "common/scene.glsl"
+
+uniform sampler2D u_atlas;
+uniform float u_sdf_scale;   // field value - 0.5 to scene-space distance
+in vec2 v_uv;
+
+void main(){
+    float d = (texture(u_atlas, v_uv).r - 0.5) * u_sdf_scale;
+    // Padding of neighbouring quads overlaps; only ink is written
+    if (d > 0.0) discard;
+    float a = smoothstep(0.0, -0.005, d);
+    o = vec4(vec3(1.0), a);
+    o_mask = vec4(vec3(1.0), 1.0);
+}
@@ -0,0 +1,15 @@
+#version 300 es
+// Glyph quads of the Text scene, given in scene space: the inverse of `toP`
+// in common/scene.glsl places them on screen.
+uniform float u_scale; uniform float u_rot; uniform vec2 u_offset;
+layout(location=0) in vec2 a_pos;
+layout(location=1) in vec2 a_uv;
+out vec2 v_uv;
+
+void main(){
+    vec2 a = vec2(min(u_resolution.x, u_resolution.y)) / u_resolution;
+    float c = cos(u_rot), s = sin(u_rot);
+    vec2 q = transpose(mat2(c, -s, s, c)) * (a_pos + u_offset) / (a * u_scale);
+    v_uv = a_uv;
+    gl_Position = vec4(q, 0.0, 1.0);
+}
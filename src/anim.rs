@@ -0,0 +1,256 @@
+//! Animation curves for scene transforms and parameters.
+//!
+//! An [`Animation`] is a set of tracks, each a [`Curve`] driving one
+//! [`Target`]: the scale, rotation and offset every SDF scene feeds to `toP`,
+//! or any float uniform of the scene's program. Scenes bring a default
+//! animation; playlist entries can override it track by track.
+
+use std::str::FromStr;
+
+/// Easing of a keyframe segment, applied on the way into the later key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ease {
+    /// Hold the earlier value until the key is reached.
+    Step,
+    Linear,
+    In,
+    Out,
+    InOut,
+    /// Overshoot and settle like a damped spring.
+    Spring,
+}
+
+impl Ease {
+    /// Map segment progress `x` in `0..=1` to interpolation weight.
+    pub fn apply(self, x: f32) -> f32 {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            Ease::Step => if x < 1.0 { 0.0 } else { 1.0 },
+            Ease::Linear => x,
+            Ease::In => x * x * x,
+            Ease::Out => 1.0 - (1.0 - x).powi(3),
+            Ease::InOut => x * x * (3.0 - 2.0 * x),
+            Ease::Spring => {
+                let f = |x: f32| 1.0 - (-6.0 * x).exp() * (3.0 * std::f32::consts::PI * x).cos();
+                // Normalised so the segment still ends exactly on the key
+                f(x) / f(1.0)
+            }
+        }
+    }
+}
+
+impl FromStr for Ease {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "step" => Ease::Step,
+            "linear" => Ease::Linear,
+            "ease-in" => Ease::In,
+            "ease-out" => Ease::Out,
+            "ease" | "ease-in-out" => Ease::InOut,
+            "spring" => Ease::Spring,
+            _ => return Err(format!("unknown easing \"{s}\"")),
+        })
+    }
+}
+
+/// Low-frequency oscillator shapes. All swing between -1 and 1 before `amp`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wave {
+    Sine,
+    Triangle,
+    /// Smooth value noise: a new random target every cycle.
+    Random,
+}
+
+impl FromStr for Wave {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "sine" => Wave::Sine,
+            "triangle" => Wave::Triangle,
+            "random" => Wave::Random,
+            _ => return Err(format!("unknown LFO wave \"{s}\"")),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Key {
+    /// Seconds into the playlist entry.
+    pub t: f32,
+    pub value: f32,
+    pub ease: Ease,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Curve {
+    Const(f32),
+    /// `rate * t`, e.g. a steady spin in radians per second.
+    Ramp(f32),
+    /// Keys sorted by time. Before the first and after the last key the value
+    /// is held, unless `looped`, in which case time wraps at the last key.
+    Keyframes { keys: Vec<Key>, looped: bool },
+    /// `offset + amp * wave(hz * t + phase)`; `phase` is in cycles.
+    Lfo { wave: Wave, hz: f32, amp: f32, offset: f32, phase: f32 },
+}
+
+impl Curve {
+    /// Keyframe curve; `keys` may come in any order.
+    pub fn keyframes(mut keys: Vec<Key>, looped: bool) -> Self {
+        keys.sort_by(|a, b| a.t.total_cmp(&b.t));
+        Curve::Keyframes { keys, looped }
+    }
+
+    /// Value at `t` seconds into the entry.
+    pub fn sample(&self, t: f32) -> f32 {
+        match self {
+            Curve::Const(v) => *v,
+            Curve::Ramp(rate) => rate * t,
+            Curve::Keyframes { keys, looped } => sample_keys(keys, *looped, t),
+            Curve::Lfo { wave, hz, amp, offset, phase } => {
+                let x = hz * t + phase;
+                let w = match wave {
+                    Wave::Sine => (x * std::f32::consts::TAU).sin(),
+                    Wave::Triangle => 1.0 - 4.0 * (x + 0.25 - (x + 0.25).floor() - 0.5).abs(),
+                    Wave::Random => {
+                        let i = x.floor();
+                        let f = Ease::InOut.apply(x - i);
+                        let (a, b) = (noise(i as i64), noise(i as i64 + 1));
+                        a + (b - a) * f
+                    }
+                };
+                offset + amp * w
+            }
+        }
+    }
+}
+
+fn sample_keys(keys: &[Key], looped: bool, t: f32) -> f32 {
+    let (Some(first), Some(last)) = (keys.first(), keys.last()) else {
+        return 0.0;
+    };
+    let span = last.t - first.t;
+    let t = if looped && span > 0.0 { first.t + (t - first.t).rem_euclid(span) } else { t };
+    if t <= first.t {
+        return first.value;
+    }
+    // First key strictly after `t`; the segment runs from the key before it
+    match keys.iter().position(|k| k.t > t) {
+        None => last.value,
+        Some(i) => {
+            let (a, b) = (&keys[i - 1], &keys[i]);
+            let w = b.ease.apply((t - a.t) / (b.t - a.t));
+            a.value + (b.value - a.value) * w
+        }
+    }
+}
+
+// Deterministic value in -1..1 for lattice point `i`.
+fn noise(i: i64) -> f32 {
+    let mut x = (i as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    x ^= x >> 31;
+    x = x.wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x ^= x >> 29;
+    (x >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+}
+
+/// What a track animates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Multiplies the scene's zoom-out factor (`u_scale`); 2 shows the shape at half size.
+    Scale,
+    /// Radians, counter-clockwise (`u_rot`).
+    Rotation,
+    /// Shape offset in scene units (`u_offset`).
+    X,
+    Y,
+    /// Any float uniform of the scene's program, e.g. `u_thickness`.
+    Param(String),
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "scale" => Target::Scale,
+            "rotation" => Target::Rotation,
+            "x" => Target::X,
+            "y" => Target::Y,
+            u if u.starts_with("u_") => Target::Param(u.to_string()),
+            _ => return Err(format!("unknown animation target \"{s}\" (scale, rotation, x, y or a u_* uniform)")),
+        })
+    }
+}
+
+/// Transform and parameter values for one frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pose {
+    pub scale: f32,
+    pub rotation: f32,
+    pub offset: [f32; 2],
+    pub params: Vec<(String, f32)>,
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Self { scale: 1.0, rotation: 0.0, offset: [0.0; 2], params: Vec::new() }
+    }
+}
+
+/// One curve per target; targets without a track keep their [`Pose`] default.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Animation {
+    tracks: Vec<(Target, Curve)>,
+}
+
+impl Animation {
+    /// Builder form of [`Animation::set`].
+    pub fn with(mut self, target: Target, curve: Curve) -> Self {
+        self.set(target, curve);
+        self
+    }
+
+    /// Animate `target` with `curve`, replacing any existing track for it.
+    pub fn set(&mut self, target: Target, curve: Curve) {
+        match self.tracks.iter_mut().find(|(t, _)| *t == target) {
+            Some(track) => track.1 = curve,
+            None => self.tracks.push((target, curve)),
+        }
+    }
+
+    pub fn tracks(&self) -> &[(Target, Curve)] {
+        &self.tracks
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    /// `self`'s tracks layered over `base`: same target, `self` wins.
+    pub fn over(&self, base: &Animation) -> Animation {
+        let mut out = base.clone();
+        for (target, curve) in &self.tracks {
+            out.set(target.clone(), curve.clone());
+        }
+        out
+    }
+
+    pub fn sample(&self, t: f32) -> Pose {
+        let mut pose = Pose::default();
+        for (target, curve) in &self.tracks {
+            let v = curve.sample(t);
+            match target {
+                Target::Scale => pose.scale = v,
+                Target::Rotation => pose.rotation = v,
+                Target::X => pose.offset[0] = v,
+                Target::Y => pose.offset[1] = v,
+                Target::Param(name) => pose.params.push((name.clone(), v)),
+            }
+        }
+        pose
+    }
+}
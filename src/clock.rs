@@ -0,0 +1,73 @@
+//! Playback clock: wall time in, animation time out. Pausing, stepping,
+//! seeking and speed changes all act on this clock, so every time the shaders
+//! see (scene time and the global `time`) follows them consistently.
+
+/// Length of one [`Clock::step`]: a frame at 60 Hz.
+pub const STEP_MS: f64 = 1000.0 / 60.0;
+/// Playback speeds are clamped to `0..=MAX_SPEED`.
+pub const MAX_SPEED: f64 = 8.0;
+
+#[derive(Clone, Debug)]
+pub struct Clock {
+    t_ms: f64,
+    last_wall_ms: Option<f64>,
+    speed: f64,
+    paused: bool,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self { t_ms: 0.0, last_wall_ms: None, speed: 1.0, paused: false }
+    }
+}
+
+impl Clock {
+    /// Advance by the wall time elapsed since the previous tick, scaled by the
+    /// speed, and return the playback time. The first tick only sets the reference.
+    pub fn tick(&mut self, wall_ms: f64) -> f64 {
+        if let Some(last) = self.last_wall_ms {
+            if !self.paused {
+                self.t_ms += (wall_ms - last).max(0.0) * self.speed;
+            }
+        }
+        self.last_wall_ms = Some(wall_ms);
+        self.t_ms
+    }
+
+    /// Playback time in milliseconds as of the last tick or adjustment.
+    pub fn now(&self) -> f64 {
+        self.t_ms
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Non-finite values are ignored.
+    pub fn set_speed(&mut self, speed: f64) {
+        if speed.is_finite() {
+            self.speed = speed.clamp(0.0, MAX_SPEED);
+        }
+    }
+
+    /// Pause and move by `frames` steps of [`STEP_MS`], backwards when negative.
+    pub fn step(&mut self, frames: i32) {
+        self.paused = true;
+        self.t_ms += frames as f64 * STEP_MS;
+    }
+
+    /// Jump to playback time `t_ms`; the running/paused state is kept.
+    pub fn seek(&mut self, t_ms: f64) {
+        if t_ms.is_finite() {
+            self.t_ms = t_ms;
+        }
+    }
+}
@@ -0,0 +1,450 @@
+//! TrueType outlines to a signed-distance-field glyph atlas. build.rs runs this
+//! on the host over `fonts/` so the bundle carries its lettering and never
+//! fetches a web font; [`crate::text`] lays strings out from the result.
+//!
+//! Only what the atlas needs is read: `cmap` format 4, `hmtx`, `hhea` and
+//! simple or composite `glyf` outlines. No hinting and no kerning.
+
+use serde::{Deserialize, Serialize};
+
+/// Characters in the atlas: printable ASCII and common typographic marks.
+pub const CHARSET: &str = concat!(
+    " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~",
+    "°·–—‘’“”…€£"
+);
+/// Atlas pixels per em.
+pub const EM_PX: f32 = 48.0;
+/// Distance at either end of the stored field, in atlas pixels; glyphs are
+/// padded by as much.
+pub const RANGE_PX: f32 = 6.0;
+/// Width of the atlas; its height is what the glyphs need.
+pub const ATLAS_WIDTH: u32 = 512;
+// Line segments per quadratic curve.
+const CURVE_STEPS: usize = 6;
+
+/// A closed outline in font units: the last point repeats the first.
+pub type Contour = Vec<[f32; 2]>;
+
+/// A parsed TrueType font borrowing its file.
+pub struct Font<'a> {
+    data: &'a [u8],
+    glyf: usize,
+    loca: usize,
+    long_loca: bool,
+    hmtx: usize,
+    h_metrics: u16,
+    glyphs: u16,
+    cmap: usize,
+    pub units_per_em: f32,
+    pub ascender: f32,
+    pub descender: f32,
+    pub line_gap: f32,
+}
+
+impl<'a> Font<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self, String> {
+        let table = |tag: &[u8; 4]| -> Result<usize, String> {
+            let n = u16_at(data, 4).ok_or("not a TrueType font")? as usize;
+            (0..n)
+                .map(|i| 12 + 16 * i)
+                .find(|&rec| data.get(rec..rec + 4) == Some(tag))
+                .and_then(|rec| u32_at(data, rec + 8))
+                .map(|off| off as usize)
+                .ok_or_else(|| format!("no {} table", String::from_utf8_lossy(tag)))
+        };
+        let bad = |what: &str| format!("truncated {what} table");
+        let (head, hhea, maxp) = (table(b"head")?, table(b"hhea")?, table(b"maxp")?);
+        let cmap = table(b"cmap")?;
+        let units_per_em = u16_at(data, head + 18).filter(|&u| u > 0).ok_or_else(|| bad("head"))? as f32;
+        let long_loca = i16_at(data, head + 50).ok_or_else(|| bad("head"))? == 1;
+        let metric = |off: usize| i16_at(data, hhea + off).map(|v| v as f32).ok_or_else(|| bad("hhea"));
+        let subtable = (0..u16_at(data, cmap + 2).ok_or_else(|| bad("cmap"))? as usize)
+            .map(|i| cmap + 4 + 8 * i)
+            .filter(|&rec| matches!((u16_at(data, rec), u16_at(data, rec + 2)), (Some(3), Some(1)) | (Some(0), Some(3))))
+            .filter_map(|rec| u32_at(data, rec + 4).map(|off| cmap + off as usize))
+            .find(|&sub| u16_at(data, sub) == Some(4))
+            .ok_or("no Unicode BMP (format 4) cmap")?;
+        Ok(Self {
+            data,
+            glyf: table(b"glyf")?,
+            loca: table(b"loca")?,
+            long_loca,
+            hmtx: table(b"hmtx")?,
+            h_metrics: u16_at(data, hhea + 34).filter(|&n| n > 0).ok_or_else(|| bad("hhea"))?,
+            glyphs: u16_at(data, maxp + 4).ok_or_else(|| bad("maxp"))?,
+            cmap: subtable,
+            units_per_em,
+            ascender: metric(4)?,
+            descender: metric(6)?,
+            line_gap: metric(8)?,
+        })
+    }
+
+    /// Glyph for `c`, `None` when the font lacks it.
+    pub fn glyph_index(&self, c: char) -> Option<u16> {
+        let c = u16::try_from(c as u32).ok()?;
+        let d = self.data;
+        let segs = u16_at(d, self.cmap + 6)? as usize / 2;
+        let ends = self.cmap + 14;
+        let starts = ends + 2 * segs + 2;
+        let deltas = starts + 2 * segs;
+        let ranges = deltas + 2 * segs;
+        let i = (0..segs).find(|&i| u16_at(d, ends + 2 * i).is_some_and(|end| end >= c))?;
+        let start = u16_at(d, starts + 2 * i)?;
+        if start > c {
+            return None;
+        }
+        let delta = u16_at(d, deltas + 2 * i)?;
+        let range = u16_at(d, ranges + 2 * i)? as usize;
+        let gid = if range == 0 {
+            c.wrapping_add(delta)
+        } else {
+            match u16_at(d, ranges + 2 * i + range + 2 * (c - start) as usize)? {
+                0 => 0,
+                g => g.wrapping_add(delta),
+            }
+        };
+        (gid != 0 && gid < self.glyphs).then_some(gid)
+    }
+
+    /// Advance width of `gid` in font units.
+    pub fn advance(&self, gid: u16) -> f32 {
+        let i = gid.min(self.h_metrics - 1) as usize;
+        u16_at(self.data, self.hmtx + 4 * i).unwrap_or(0) as f32
+    }
+
+    /// Outline of `gid` in font units, curves flattened. Empty for blank glyphs.
+    pub fn outline(&self, gid: u16) -> Vec<Contour> {
+        let mut out = Vec::new();
+        self.outline_into(gid, [1.0, 0.0, 0.0, 1.0, 0.0, 0.0], 0, &mut out);
+        out
+    }
+
+    fn glyph_range(&self, gid: u16) -> Option<(usize, usize)> {
+        let d = self.data;
+        let (start, end) = if self.long_loca {
+            (u32_at(d, self.loca + 4 * gid as usize)? as usize, u32_at(d, self.loca + 4 * gid as usize + 4)? as usize)
+        } else {
+            (2 * u16_at(d, self.loca + 2 * gid as usize)? as usize, 2 * u16_at(d, self.loca + 2 * gid as usize + 2)? as usize)
+        };
+        (end > start).then_some((self.glyf + start, self.glyf + end))
+    }
+
+    // `m` maps glyph units: `[a, b, c, d, dx, dy]` as `x' = a x + c y + dx`,
+    // `y' = b x + d y + dy`.
+    fn outline_into(&self, gid: u16, m: [f32; 6], depth: u32, out: &mut Vec<Contour>) -> Option<()> {
+        let (g, _) = self.glyph_range(gid)?;
+        let d = self.data;
+        let contours = i16_at(d, g)?;
+        if contours < 0 {
+            return self.composite_into(g + 10, m, depth, out);
+        }
+        let ends: Vec<usize> = (0..contours as usize).map(|i| u16_at(d, g + 10 + 2 * i).map(|e| e as usize)).collect::<Option<_>>()?;
+        let n = ends.last().map_or(0, |e| e + 1);
+        let mut p = g + 10 + 2 * contours as usize;
+        p += 2 + u16_at(d, p)? as usize;
+        let mut flags = Vec::with_capacity(n);
+        while flags.len() < n {
+            let f = *d.get(p)?;
+            p += 1;
+            flags.push(f);
+            if f & 8 != 0 {
+                let repeat = *d.get(p)?;
+                p += 1;
+                flags.extend(std::iter::repeat_n(f, repeat as usize));
+            }
+        }
+        flags.truncate(n);
+        let mut coords = |short: u8, same: u8| -> Option<Vec<f32>> {
+            let mut v = 0i32;
+            flags
+                .iter()
+                .map(|&f| {
+                    if f & short != 0 {
+                        let b = *d.get(p)? as i32;
+                        p += 1;
+                        v += if f & same != 0 { b } else { -b };
+                    } else if f & same == 0 {
+                        v += i16_at(d, p)? as i32;
+                        p += 2;
+                    }
+                    Some(v as f32)
+                })
+                .collect()
+        };
+        let xs = coords(2, 0x10)?;
+        let ys = coords(4, 0x20)?;
+        let mut first = 0;
+        for end in ends {
+            let points: Vec<([f32; 2], bool)> = (first..=end.min(n - 1))
+                .map(|i| ([m[0] * xs[i] + m[2] * ys[i] + m[4], m[1] * xs[i] + m[3] * ys[i] + m[5]], flags[i] & 1 != 0))
+                .collect();
+            first = end + 1;
+            if points.len() > 1 {
+                out.push(flatten(&points));
+            }
+        }
+        Some(())
+    }
+
+    fn composite_into(&self, mut p: usize, m: [f32; 6], depth: u32, out: &mut Vec<Contour>) -> Option<()> {
+        if depth > 8 {
+            return None;
+        }
+        let d = self.data;
+        loop {
+            let flags = u16_at(d, p)?;
+            let gid = u16_at(d, p + 2)?;
+            p += 4;
+            let (dx, dy) = if flags & 1 != 0 {
+                p += 4;
+                (i16_at(d, p - 4)? as f32, i16_at(d, p - 2)? as f32)
+            } else {
+                p += 2;
+                (*d.get(p - 2)? as i8 as f32, *d.get(p - 1)? as i8 as f32)
+            };
+            // Point-matched placement (flag 2 clear) is rare; place at the origin
+            let (dx, dy) = if flags & 2 != 0 { (dx, dy) } else { (0.0, 0.0) };
+            let f2 = |p: usize| i16_at(d, p).map(|v| v as f32 / 16384.0);
+            let [a, b, c, e] = if flags & 8 != 0 {
+                p += 2;
+                let s = f2(p - 2)?;
+                [s, 0.0, 0.0, s]
+            } else if flags & 0x40 != 0 {
+                p += 4;
+                [f2(p - 4)?, 0.0, 0.0, f2(p - 2)?]
+            } else if flags & 0x80 != 0 {
+                p += 8;
+                [f2(p - 8)?, f2(p - 6)?, f2(p - 4)?, f2(p - 2)?]
+            } else {
+                [1.0, 0.0, 0.0, 1.0]
+            };
+            // Component transform first, then ours
+            let local = [a, b, c, e, dx, dy];
+            let combined = [
+                m[0] * local[0] + m[2] * local[1],
+                m[1] * local[0] + m[3] * local[1],
+                m[0] * local[2] + m[2] * local[3],
+                m[1] * local[2] + m[3] * local[3],
+                m[0] * local[4] + m[2] * local[5] + m[4],
+                m[1] * local[4] + m[3] * local[5] + m[5],
+            ];
+            self.outline_into(gid, combined, depth + 1, out);
+            if flags & 0x20 == 0 {
+                return Some(());
+            }
+        }
+    }
+}
+
+fn u16_at(d: &[u8], off: usize) -> Option<u16> {
+    d.get(off..off + 2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+fn i16_at(d: &[u8], off: usize) -> Option<i16> {
+    u16_at(d, off).map(|v| v as i16)
+}
+
+fn u32_at(d: &[u8], off: usize) -> Option<u32> {
+    d.get(off..off + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+// Quadratic B-spline contour (on-curve flags) to a closed polyline. Two
+// off-curve points in a row imply an on-curve point halfway between them.
+fn flatten(points: &[([f32; 2], bool)]) -> Contour {
+    let mid = |a: [f32; 2], b: [f32; 2]| [(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0];
+    let n = points.len();
+    let (start, from) = match points.iter().position(|p| p.1) {
+        Some(i) => (points[i].0, i + 1),
+        None => (mid(points[n - 1].0, points[0].0), 0),
+    };
+    let mut out = vec![start];
+    let mut cur = start;
+    let mut ctrl: Option<[f32; 2]> = None;
+    let curve = |from: [f32; 2], c: [f32; 2], to: [f32; 2], out: &mut Contour| {
+        for i in 1..=CURVE_STEPS {
+            let t = i as f32 / CURVE_STEPS as f32;
+            let u = 1.0 - t;
+            out.push([
+                u * u * from[0] + 2.0 * u * t * c[0] + t * t * to[0],
+                u * u * from[1] + 2.0 * u * t * c[1] + t * t * to[1],
+            ]);
+        }
+    };
+    for k in 0..n {
+        let (p, on) = points[(from + k) % n];
+        match (on, ctrl) {
+            (true, Some(c)) => {
+                curve(cur, c, p, &mut out);
+                ctrl = None;
+                cur = p;
+            }
+            (true, None) => {
+                out.push(p);
+                cur = p;
+            }
+            (false, Some(c)) => {
+                let m = mid(c, p);
+                curve(cur, c, m, &mut out);
+                cur = m;
+                ctrl = Some(p);
+            }
+            (false, None) => ctrl = Some(p),
+        }
+    }
+    match ctrl {
+        Some(c) => curve(cur, c, start, &mut out),
+        None if cur != start => out.push(start),
+        None => {}
+    }
+    out
+}
+
+/// Whether `p` is inside the outlines, by the nonzero winding rule.
+pub fn winding_inside(contours: &[Contour], p: [f32; 2]) -> bool {
+    let mut winding = 0;
+    for c in contours {
+        for e in c.windows(2) {
+            let (a, b) = (e[0], e[1]);
+            let side = (b[0] - a[0]) * (p[1] - a[1]) - (p[0] - a[0]) * (b[1] - a[1]);
+            if a[1] <= p[1] && b[1] > p[1] && side > 0.0 {
+                winding += 1;
+            } else if a[1] > p[1] && b[1] <= p[1] && side < 0.0 {
+                winding -= 1;
+            }
+        }
+    }
+    winding != 0
+}
+
+// Distance from `p` to the nearest outline edge.
+fn edge_distance(contours: &[Contour], p: [f32; 2]) -> f32 {
+    let mut best = f32::MAX;
+    for c in contours {
+        for e in c.windows(2) {
+            let (a, b) = (e[0], e[1]);
+            let ab = [b[0] - a[0], b[1] - a[1]];
+            let ap = [p[0] - a[0], p[1] - a[1]];
+            let len2 = ab[0] * ab[0] + ab[1] * ab[1];
+            let t = if len2 > 0.0 { ((ap[0] * ab[0] + ap[1] * ab[1]) / len2).clamp(0.0, 1.0) } else { 0.0 };
+            let (dx, dy) = (ap[0] - ab[0] * t, ap[1] - ab[1] * t);
+            best = best.min(dx * dx + dy * dy);
+        }
+    }
+    best.sqrt()
+}
+
+/// One character's place in the atlas.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Glyph {
+    pub ch: char,
+    /// Pen advance, in ems.
+    pub advance: f32,
+    /// Quad around the baseline origin, padding included, in ems:
+    /// `[left, bottom, right, top]`.
+    pub bounds: [f32; 4],
+    /// `[x, y, width, height]` in atlas pixels, rows counted from the bottom.
+    /// Zero-sized for blank glyphs.
+    pub rect: [u32; 4],
+}
+
+/// Single-channel distance field of every glyph: `0.5 + d / (2 * range)`
+/// where `d` is the distance in atlas pixels to the outline, negative inside.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Atlas {
+    pub width: u32,
+    pub height: u32,
+    pub em_px: f32,
+    pub range: f32,
+    /// Line metrics, in ems; `descender` is negative.
+    pub ascender: f32,
+    pub descender: f32,
+    pub line_gap: f32,
+    pub glyphs: Vec<Glyph>,
+    /// Bottom row first, as WebGL uploads it.
+    pub pixels: Vec<u8>,
+}
+
+impl Atlas {
+    /// Rasterize `chars` (those the font has) at [`EM_PX`] with [`RANGE_PX`]
+    /// of field around each, shelf-packed [`ATLAS_WIDTH`] wide.
+    pub fn build(font: &Font, chars: &str) -> Atlas {
+        let upm = font.units_per_em;
+        let scale = EM_PX / upm;
+        let pad = RANGE_PX / scale;
+        struct Cell {
+            glyph: Glyph,
+            pixels: Vec<u8>,
+        }
+        let mut cells: Vec<Cell> = Vec::new();
+        for ch in chars.chars() {
+            let Some(gid) = font.glyph_index(ch) else { continue };
+            let contours = font.outline(gid);
+            let advance = font.advance(gid) / upm;
+            let points = contours.iter().flatten();
+            let (x0, y0) = points.clone().fold((f32::MAX, f32::MAX), |(x, y), p| (x.min(p[0]), y.min(p[1])));
+            let (x1, y1) = points.fold((f32::MIN, f32::MIN), |(x, y), p| (x.max(p[0]), y.max(p[1])));
+            if contours.is_empty() || x1 <= x0 || y1 <= y0 {
+                cells.push(Cell { glyph: Glyph { ch, advance, bounds: [0.0; 4], rect: [0; 4] }, pixels: Vec::new() });
+                continue;
+            }
+            let (ox, oy) = (x0 - pad, y0 - pad);
+            let w = ((x1 - x0) * scale + 2.0 * RANGE_PX).ceil() as u32;
+            let h = ((y1 - y0) * scale + 2.0 * RANGE_PX).ceil() as u32;
+            let mut pixels = Vec::with_capacity((w * h) as usize);
+            for y in 0..h {
+                for x in 0..w {
+                    let p = [ox + (x as f32 + 0.5) / scale, oy + (y as f32 + 0.5) / scale];
+                    let d = edge_distance(&contours, p) * scale;
+                    let d = if winding_inside(&contours, p) { -d } else { d };
+                    pixels.push(((0.5 + d / (2.0 * RANGE_PX)).clamp(0.0, 1.0) * 255.0).round() as u8);
+                }
+            }
+            let bounds = [ox / upm, oy / upm, (ox + w as f32 / scale) / upm, (oy + h as f32 / scale) / upm];
+            cells.push(Cell { glyph: Glyph { ch, advance, bounds, rect: [0, 0, w, h] }, pixels });
+        }
+
+        // Shelves of the tallest glyphs first, one pixel apart
+        let mut order: Vec<usize> = (0..cells.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(cells[i].glyph.rect[3]));
+        let (mut x, mut y, mut shelf) = (0, 0, 0);
+        for &i in &order {
+            let [_, _, w, h] = cells[i].glyph.rect;
+            if w == 0 {
+                continue;
+            }
+            if x + w > ATLAS_WIDTH {
+                (x, y, shelf) = (0, y + shelf + 1, 0);
+            }
+            cells[i].glyph.rect[0] = x;
+            cells[i].glyph.rect[1] = y;
+            x += w + 1;
+            shelf = shelf.max(h);
+        }
+        let height = y + shelf;
+        let mut pixels = vec![255u8; (ATLAS_WIDTH * height) as usize];
+        for cell in &cells {
+            let [gx, gy, w, h] = cell.glyph.rect;
+            for row in 0..h {
+                let dst = ((gy + row) * ATLAS_WIDTH + gx) as usize;
+                let src = (row * w) as usize;
+                pixels[dst..dst + w as usize].copy_from_slice(&cell.pixels[src..src + w as usize]);
+            }
+        }
+        Atlas {
+            width: ATLAS_WIDTH,
+            height,
+            em_px: EM_PX,
+            range: RANGE_PX,
+            ascender: font.ascender / upm,
+            descender: font.descender / upm,
+            line_gap: font.line_gap / upm,
+            glyphs: cells.into_iter().map(|c| c.glyph).collect(),
+            pixels,
+        }
+    }
+
+    pub fn glyph(&self, ch: char) -> Option<&Glyph> {
+        self.glyphs.iter().find(|g| g.ch == ch)
+    }
+}
@@ -0,0 +1,132 @@
+//! Touch/pointer gesture recognition, fed with raw pointer events.
+//!
+//! Coordinates are CSS pixels and times milliseconds, as in `PointerEvent`.
+//! Kept free of web APIs so it can be tested on the host.
+
+/// Minimum horizontal travel for a swipe.
+pub const SWIPE_MIN_PX: f32 = 60.0;
+/// A swipe must finish within this time.
+pub const SWIPE_MAX_MS: f64 = 600.0;
+/// Two-finger taps must finish within this time.
+pub const TAP_MAX_MS: f64 = 300.0;
+/// Holding one finger still this long is a long press.
+pub const LONG_PRESS_MS: f64 = 600.0;
+/// Movement below this does not count as moving (taps, long press).
+pub const SLOP_PX: f32 = 10.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+    SwipeLeft,
+    SwipeRight,
+    TwoFingerTap,
+    LongPress,
+    /// Two-finger pinch/rotate since the previous report: `scale` is the
+    /// factor the finger distance changed by, `rotate` the angle in radians
+    /// (counter-clockwise on screen positive).
+    Transform { scale: f32, rotate: f32 },
+}
+
+#[derive(Clone, Debug)]
+struct Pointer {
+    id: i32,
+    start: (f32, f32),
+    pos: (f32, f32),
+}
+
+/// State of the current touch sequence: from the first pointer down until the
+/// last one is lifted.
+#[derive(Clone, Debug, Default)]
+pub struct GestureRecognizer {
+    pointers: Vec<Pointer>,
+    // Sequence-wide facts
+    started_ms: f64,
+    max_pointers: usize,
+    moved: bool,
+    long_press_fired: bool,
+    // Distance and angle between the two fingers at the last `Transform`
+    span: Option<(f32, f32)>,
+}
+
+impl GestureRecognizer {
+    pub fn down(&mut self, id: i32, x: f32, y: f32, t_ms: f64) {
+        if self.pointers.is_empty() {
+            *self = Self { started_ms: t_ms, ..Self::default() };
+        }
+        self.pointers.retain(|p| p.id != id);
+        self.pointers.push(Pointer { id, start: (x, y), pos: (x, y) });
+        self.max_pointers = self.max_pointers.max(self.pointers.len());
+        self.span = self.two_finger_span();
+    }
+
+    pub fn moved(&mut self, id: i32, x: f32, y: f32) -> Option<Gesture> {
+        let p = self.pointers.iter_mut().find(|p| p.id == id)?;
+        p.pos = (x, y);
+        if dist(p.start, p.pos) > SLOP_PX {
+            self.moved = true;
+        }
+        let (d1, a1) = self.two_finger_span()?;
+        let (d0, a0) = self.span.replace((d1, a1))?;
+        if d0 <= 0.0 {
+            return None;
+        }
+        let mut rotate = a1 - a0;
+        if rotate > std::f32::consts::PI {
+            rotate -= std::f32::consts::TAU;
+        } else if rotate < -std::f32::consts::PI {
+            rotate += std::f32::consts::TAU;
+        }
+        // Screen y points down; report counter-clockwise as positive
+        Some(Gesture::Transform { scale: d1 / d0, rotate: -rotate })
+    }
+
+    pub fn up(&mut self, id: i32, x: f32, y: f32, t_ms: f64) -> Option<Gesture> {
+        let i = self.pointers.iter().position(|p| p.id == id)?;
+        let p = self.pointers.remove(i);
+        self.span = None;
+        if !self.pointers.is_empty() {
+            return None;
+        }
+        let elapsed = t_ms - self.started_ms;
+        if self.max_pointers == 2 && !self.moved && elapsed <= TAP_MAX_MS {
+            return Some(Gesture::TwoFingerTap);
+        }
+        let (dx, dy) = (x - p.start.0, y - p.start.1);
+        if self.max_pointers == 1 && !self.long_press_fired && elapsed <= SWIPE_MAX_MS
+            && dx.abs() >= SWIPE_MIN_PX && dx.abs() > 2.0 * dy.abs()
+        {
+            return Some(if dx < 0.0 { Gesture::SwipeLeft } else { Gesture::SwipeRight });
+        }
+        None
+    }
+
+    /// The pointer left without a normal `up` (e.g. the browser took over).
+    pub fn cancel(&mut self, id: i32) {
+        self.pointers.retain(|p| p.id != id);
+        self.span = None;
+        // Whatever was in progress no longer counts
+        self.moved = true;
+    }
+
+    /// Time-based gestures; call once per frame.
+    pub fn poll(&mut self, t_ms: f64) -> Option<Gesture> {
+        if self.pointers.len() == 1 && self.max_pointers == 1 && !self.moved && !self.long_press_fired
+            && t_ms - self.started_ms >= LONG_PRESS_MS
+        {
+            self.long_press_fired = true;
+            return Some(Gesture::LongPress);
+        }
+        None
+    }
+
+    fn two_finger_span(&self) -> Option<(f32, f32)> {
+        let [a, b] = self.pointers.as_slice() else {
+            return None;
+        };
+        let (dx, dy) = (b.pos.0 - a.pos.0, b.pos.1 - a.pos.1);
+        Some((dx.hypot(dy), dy.atan2(dx)))
+    }
+}
+
+fn dist(a: (f32, f32), b: (f32, f32)) -> f32 {
+    (b.0 - a.0).hypot(b.1 - a.1)
+}
@@ -0,0 +1,171 @@
+//! Minimal GLSL preprocessor for the files under `static/shaders/`.
+//!
+//! Supports `#include "path"` (relative to the shader root, each file pulled in
+//! at most once), `#define`s injected from Rust and a prelude spliced in after
+//! `#version`. Every output line remembers where it came from so driver info
+//! logs, which only know the flattened source, can be pointed back at the
+//! snippet that actually contains the error.
+//!
+//! Free of web APIs so the build script and host tests can use it too.
+
+/// GLSL declaration of the per-frame globals. Every shader that needs the
+/// resolution or time splices this in; the layout must match
+/// `Globals::pack` in the wasm renderer.
+pub const GLOBALS_GLSL: &str = r#"
+layout(std140) uniform Globals {
+    highp vec2 u_resolution;  // size of the target being drawn, in pixels
+    highp float u_time;       // seconds since page load
+    highp float u_scene_time; // seconds since the current scene began
+    highp float u_beat;       // beat phase, reserved for audio input (0 for now)
+    highp float u_max_flash_hz; // cap on periodic flicker/blink rates (safety mode)
+    highp vec4 u_bands;       // low/mid/high/level bands, reserved for audio input
+};
+"#;
+
+/// Fragment output locations of the scene pass (color and mask, written via
+/// MRT). Injected as `SCENE_COLOR_LOCATION` / `SCENE_MASK_LOCATION`.
+pub const SCENE_COLOR_LOCATION: u32 = 0;
+pub const SCENE_MASK_LOCATION: u32 = 1;
+
+/// Uniform array sizes of the particle update pass. Injected as
+/// `PARTICLE_MAX_EMITTERS` / `PARTICLE_MAX_ATTRACTORS`.
+pub const PARTICLE_MAX_EMITTERS: u32 = 4;
+pub const PARTICLE_MAX_ATTRACTORS: u32 = 4;
+
+/// `#define`s every shader is built with, shared between the renderer and the
+/// build-time validation.
+pub fn builtin_defines() -> Vec<(String, String)> {
+    [
+        ("SCENE_COLOR_LOCATION", SCENE_COLOR_LOCATION),
+        ("SCENE_MASK_LOCATION", SCENE_MASK_LOCATION),
+        ("PARTICLE_MAX_EMITTERS", PARTICLE_MAX_EMITTERS),
+        ("PARTICLE_MAX_ATTRACTORS", PARTICLE_MAX_ATTRACTORS),
+    ]
+    .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+/// Name reported for lines of the prelude.
+pub const PRELUDE_FILE: &str = "<prelude>";
+/// Name reported for injected `#define` lines.
+pub const DEFINES_FILE: &str = "<defines>";
+
+/// Flattened shader source plus a line table back to the original files.
+#[derive(Debug)]
+pub struct Preprocessed {
+    pub source: String,
+    /// `(file, 1-based line)` for each line of `source`.
+    lines: Vec<(String, u32)>,
+    /// Every file that went into `source`, the root first.
+    files: Vec<String>,
+}
+
+impl Preprocessed {
+    /// Origin of 1-based `line` in the flattened source.
+    pub fn origin(&self, line: u32) -> Option<(&str, u32)> {
+        let (file, l) = self.lines.get((line as usize).checked_sub(1)?)?;
+        Some((file.as_str(), *l))
+    }
+
+    /// Files this source was built from; an edit to any of them needs a rebuild.
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    /// Rewrite `0:<line>:` locations in a compiler info log (the format used by
+    /// ANGLE and the major drivers) to `<file>:<line>:`.
+    pub fn map_log(&self, log: &str) -> String {
+        let mut out = String::with_capacity(log.len());
+        let mut rest = log;
+        while let Some(i) = rest.find("0:") {
+            let (before, after) = rest.split_at(i);
+            out.push_str(before);
+            let at_word_start = before.is_empty() || before.ends_with(|c: char| c.is_whitespace());
+            let digits = after[2..].find(|c: char| !c.is_ascii_digit()).unwrap_or(after.len() - 2);
+            let mapped = at_word_start && digits > 0 && after[2 + digits..].starts_with(':');
+            match after[2..2 + digits].parse().ok().filter(|_| mapped).and_then(|l| self.origin(l)) {
+                Some((file, line)) => {
+                    out.push_str(&format!("{file}:{line}"));
+                    rest = &after[2 + digits..];
+                }
+                None => {
+                    out.push_str("0:");
+                    rest = &after[2..];
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    fn push(&mut self, line: &str, file: &str, n: u32) {
+        self.source.push_str(line);
+        self.source.push('\n');
+        self.lines.push((file.to_string(), n));
+    }
+}
+
+/// Expand `root`, resolving includes through `load` (path below the shader root
+/// to file text). `defines` and `prelude` are inserted after the `#version` line,
+/// or at the top when there is none.
+pub fn preprocess<F>(root: &str, defines: &[(String, String)], prelude: &str, load: F) -> Result<Preprocessed, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let text = load(root).ok_or_else(|| format!("{root}: no such shader"))?;
+    let mut out = Preprocessed { source: String::new(), lines: Vec::new(), files: vec![root.to_string()] };
+
+    let (body, first_line) = match text.split_once('\n') {
+        Some((version, rest)) if version.trim_start().starts_with("#version") => {
+            out.push(version, root, 1);
+            (rest, 2)
+        }
+        _ => (text.as_str(), 1),
+    };
+    for (i, (name, value)) in defines.iter().enumerate() {
+        out.push(&format!("#define {name} {value}"), DEFINES_FILE, i as u32 + 1);
+    }
+    for (i, line) in prelude.lines().enumerate() {
+        out.push(line, PRELUDE_FILE, i as u32 + 1);
+    }
+    expand(&mut out, root, body, first_line, &load, &mut vec![root.to_string()])?;
+    Ok(out)
+}
+
+fn expand<F>(out: &mut Preprocessed, file: &str, text: &str, first_line: u32, load: &F, stack: &mut Vec<String>) -> Result<(), String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    for (i, line) in text.lines().enumerate() {
+        let n = first_line + i as u32;
+        let Some(path) = parse_include(line).transpose().map_err(|e| format!("{file}:{n}: {e}"))? else {
+            out.push(line, file, n);
+            continue;
+        };
+        if stack.iter().any(|f| f == path) {
+            return Err(format!("{file}:{n}: include cycle through \"{path}\""));
+        }
+        if out.files.iter().any(|f| f == path) {
+            continue;
+        }
+        let text = load(path).ok_or_else(|| format!("{file}:{n}: cannot find include \"{path}\""))?;
+        out.files.push(path.to_string());
+        stack.push(path.to_string());
+        expand(out, path, &text, 1, load, stack)?;
+        stack.pop();
+    }
+    Ok(())
+}
+
+// `Some(Ok(path))` for a well-formed `#include "path"`, `None` for other lines.
+fn parse_include(line: &str) -> Option<Result<&str, &'static str>> {
+    let rest = line.trim_start().strip_prefix('#')?.trim_start().strip_prefix("include")?;
+    let path = rest
+        .trim()
+        .strip_prefix('"')
+        .and_then(|r| r.split_once('"'))
+        .filter(|(path, tail)| !path.is_empty() && (tail.trim().is_empty() || tail.trim().starts_with("//")))
+        .map(|(path, _)| path);
+    Some(path.ok_or("expected #include \"path\""))
+}
@@ -0,0 +1,232 @@
+//! Keyboard bindings: `KeyboardEvent.key` values mapped to renderer commands.
+//!
+//! Commands have stable names (`"next"`, `"scene-3"`, ...) so bindings can be
+//! configured from JS and listed in the `?` help.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Next,
+    Prev,
+    /// Playlist entry by 1-based number.
+    Scene(usize),
+    /// Switch the fill between stripes and polka dots.
+    ToggleFill,
+    /// New pattern parameters, same scene.
+    Randomize,
+    /// Keep the pattern parameters across scene changes.
+    LockParams,
+    Blackout,
+    /// Hold the last drawn frame while time runs on.
+    Freeze,
+    Pause,
+    StepBack,
+    StepForward,
+    ScrubBack,
+    ScrubForward,
+    Slower,
+    Faster,
+    /// Undo pinch zoom and rotation.
+    ResetView,
+    ToggleHud,
+    ToggleOverlay,
+    /// Recall the preset in a 1-based slot.
+    Preset(usize),
+    /// Save the current look into a 1-based preset slot.
+    SavePreset(usize),
+    /// Download the presets as a JSON file.
+    ExportPresets,
+    /// Pick a JSON file of presets to add.
+    ImportPresets,
+    /// Photosensitivity flash limiter on / off.
+    ToggleSafety,
+    Help,
+}
+
+impl Command {
+    const SIMPLE: &'static [(Command, &'static str, &'static str)] = &[
+        (Command::Next, "next", "next scene"),
+        (Command::Prev, "prev", "previous scene"),
+        (Command::ToggleFill, "toggle-fill", "stripes / polka dots"),
+        (Command::Randomize, "randomize", "new pattern, same scene"),
+        (Command::LockParams, "lock-params", "keep pattern across scenes"),
+        (Command::Blackout, "blackout", "blackout"),
+        (Command::Freeze, "freeze", "freeze frame"),
+        (Command::Pause, "pause", "pause / resume"),
+        (Command::StepBack, "step-back", "step one frame back"),
+        (Command::StepForward, "step-forward", "step one frame forward"),
+        (Command::ScrubBack, "scrub-back", "scrub back 1 s"),
+        (Command::ScrubForward, "scrub-forward", "scrub forward 1 s"),
+        (Command::Slower, "slower", "half speed"),
+        (Command::Faster, "faster", "double speed"),
+        (Command::ResetView, "reset-view", "reset zoom / rotation"),
+        (Command::ToggleHud, "toggle-hud", "performance HUD"),
+        (Command::ToggleOverlay, "toggle-overlay", "scene label"),
+        (Command::ExportPresets, "export-presets", "save presets to a file"),
+        (Command::ImportPresets, "import-presets", "load presets from a file"),
+        (Command::ToggleSafety, "toggle-safety", "flash limiter"),
+        (Command::Help, "help", "this help"),
+    ];
+
+    /// Short description for the help listing.
+    pub fn description(&self) -> String {
+        match self {
+            Command::Scene(n) => format!("scene {n}"),
+            Command::Preset(n) => format!("recall preset {n}"),
+            Command::SavePreset(n) => format!("save preset {n}"),
+            c => Self::SIMPLE.iter().find(|(k, ..)| k == c).map(|(_, _, d)| d.to_string()).unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Scene(n) => write!(f, "scene-{n}"),
+            Command::Preset(n) => write!(f, "preset-{n}"),
+            Command::SavePreset(n) => write!(f, "save-preset-{n}"),
+            c => f.write_str(Self::SIMPLE.iter().find(|(k, ..)| k == c).map_or("", |(_, name, _)| name)),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let numbered = [
+            ("scene-", Command::Scene as fn(usize) -> Command),
+            ("preset-", Command::Preset),
+            ("save-preset-", Command::SavePreset),
+        ];
+        for (prefix, cmd) in numbered {
+            if let Some(n) = s.strip_prefix(prefix) {
+                return match n.parse::<usize>() {
+                    Ok(n) if n > 0 => Ok(cmd(n)),
+                    _ => Err(format!("bad number in \"{s}\"")),
+                };
+            }
+        }
+        Self::SIMPLE
+            .iter()
+            .find(|(_, name, _)| *name == s)
+            .map(|(c, ..)| *c)
+            .ok_or_else(|| format!("unknown command \"{s}\""))
+    }
+}
+
+/// Ordered key → command bindings. A key maps to at most one command; a
+/// command may have several keys.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keymap {
+    bindings: Vec<(String, Command)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use Command::*;
+        let mut bindings: Vec<(String, Command)> = [
+            (" ", Next),
+            ("Backspace", Prev),
+            ("m", ToggleFill),
+            ("r", Randomize),
+            ("l", LockParams),
+            ("b", Blackout),
+            ("f", Freeze),
+            ("p", Pause),
+            (",", StepBack),
+            (".", StepForward),
+            ("ArrowLeft", ScrubBack),
+            ("ArrowRight", ScrubForward),
+            ("[", Slower),
+            ("]", Faster),
+            ("0", ResetView),
+            ("h", ToggleHud),
+            ("o", ToggleOverlay),
+            ("e", ExportPresets),
+            ("i", ImportPresets),
+            ("s", ToggleSafety),
+            ("?", Help),
+        ]
+        .into_iter()
+        .map(|(k, c)| (k.to_string(), c))
+        .collect();
+        bindings.extend((1..=9).map(|n| (n.to_string(), Scene(n))));
+        bindings.extend((1..=8).map(|n| (format!("F{n}"), Preset(n))));
+        bindings.extend((1..=8).map(|n| (format!("Shift+F{n}"), SavePreset(n))));
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// Command bound to `key`. Single letters match regardless of case, so
+    /// Caps Lock or Shift do not disable the shortcuts. Other keys can be bound
+    /// with Shift held as `"Shift+F1"`.
+    pub fn lookup(&self, key: &str) -> Option<Command> {
+        self.find(key).map(|i| self.bindings[i].1)
+    }
+
+    /// Bind `key` to `cmd`, replacing whatever it was bound to.
+    pub fn bind(&mut self, key: &str, cmd: Command) {
+        match self.find(key) {
+            Some(i) => self.bindings[i].1 = cmd,
+            None => self.bindings.push((key.to_string(), cmd)),
+        }
+    }
+
+    pub fn unbind(&mut self, key: &str) {
+        if let Some(i) = self.find(key) {
+            self.bindings.remove(i);
+        }
+    }
+
+    pub fn bindings(&self) -> &[(String, Command)] {
+        &self.bindings
+    }
+
+    /// One line per command, keys joined with " / ", in binding order. The
+    /// scene and preset number bindings share one line each.
+    pub fn help(&self) -> Vec<String> {
+        let mut rows: Vec<(Vec<String>, String)> = Vec::new();
+        for (key, cmd) in &self.bindings {
+            let desc = match cmd {
+                Command::Scene(_) => "scene by number".to_string(),
+                Command::Preset(_) => "recall preset".to_string(),
+                Command::SavePreset(_) => "save preset".to_string(),
+                c => c.description(),
+            };
+            match rows.iter_mut().find(|(_, d)| *d == desc) {
+                Some((keys, _)) => keys.push(key_label(key)),
+                None => rows.push((vec![key_label(key)], desc)),
+            }
+        }
+        let keys = |k: &[String]| match k {
+            [first, .., last] if k.len() > 3 => format!("{first}-{last}"),
+            _ => k.join(" / "),
+        };
+        let width = rows.iter().map(|(k, _)| keys(k).chars().count()).max().unwrap_or(0);
+        rows.into_iter()
+            .map(|(k, desc)| format!("{:<width$}  {desc}", keys(&k)))
+            .collect()
+    }
+
+    fn find(&self, key: &str) -> Option<usize> {
+        let folded = |k: &str| if k.chars().count() == 1 { k.to_lowercase() } else { k.to_string() };
+        let key = folded(key);
+        self.bindings.iter().position(|(k, _)| folded(k) == key)
+    }
+}
+
+// How a `KeyboardEvent.key` value is shown in the help.
+fn key_label(key: &str) -> String {
+    match key {
+        " " => "Space".into(),
+        "ArrowLeft" => "←".into(),
+        "ArrowRight" => "→".into(),
+        "ArrowUp" => "↑".into(),
+        "ArrowDown" => "↓".into(),
+        k => k.to_string(),
+    }
+}
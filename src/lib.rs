@@ -1,10 +1,32 @@
 #![cfg_attr(target_arch = "wasm32", allow(dead_code))]
 
+pub mod anim;
+pub mod clock;
+pub mod font;
+pub mod gesture;
+pub mod glsl;
+pub mod keymap;
+pub mod motion;
+pub mod particles;
+pub mod pattern;
+pub mod perf;
+pub mod query;
+pub mod safety;
+pub mod schedule;
+pub mod settings;
+pub mod silhouette;
+pub mod show;
+pub mod sync;
+pub mod text;
+pub mod video;
+
 // Only compile wasm-specific code when targeting wasm32.
 
 #[cfg(target_arch = "wasm32")]
 mod wasm {
     use wasm_bindgen::prelude::*;
+
+    use error::VizError;
     // Only enable wasm-bindgen-test when we are running tests. The macro and
     // crate are unavailable in a normal (non-test) build, which is what `wasm-pack`
     // invokes.
@@ -14,31 +36,390 @@ mod wasm {
     #[cfg(test)]
     wasm_bindgen_test_configure!(run_in_browser);
 
+    mod error;
+    mod glutil;
+    mod hud;
+    mod options;
+    mod particles;
+    mod post;
+    mod readback;
     mod render;
+    mod scenes;
+    mod shaders;
+    mod silhouette;
+    mod store;
+    mod sync;
+    mod text;
+    mod video;
+
+    use crate::show::ShowState;
+    use options::Options;
+
+    thread_local! {
+        // Instance started by `main` for the standalone page, if any.
+        static PAGE: std::cell::RefCell<Option<render::Handle>> = const { std::cell::RefCell::new(None) };
+    }
+
+    /// A visualizer running on a canvas. Several can share a page; each has its
+    /// own playlist and stops drawing after `destroy()`.
+    ///
+    /// ```js
+    /// const viz = new Viz(canvas, { scenes: ["Pulsing Plus"], autoplay: false });
+    /// ```
+    #[wasm_bindgen]
+    pub struct Viz {
+        handle: render::Handle,
+    }
+
+    #[wasm_bindgen]
+    impl Viz {
+        /// Options (all optional): `scenes` (names of built-in scenes to load),
+        /// `playlist` (scene names or `{ scene, duration }`), `duration` (seconds
+        /// per entry), `overlay` (element for labels and errors), `stats`
+        /// (element for the FPS line), `hud` (show the performance HUD), `autoplay`,
+        /// `keyboard` (listen for shortcuts), `keys` (`{ key: command | null }`
+        /// overriding the default bindings), `gestures` (touch/pointer input on
+        /// the canvas), `fillWindow`, `scene` (start scene), `seed` (reproducible
+        /// patterns), `fill` (`"stripes"` or `"polka"`), `storage` (a
+        /// `localStorage` key to restore settings and presets from and save them
+        /// to), `safety` (limit flashing for photosensitive viewers),
+        /// `reducedMotion` (`true`, `false` or `"auto"`, the default, following
+        /// `prefers-reduced-motion`), `sync` (`"leader"` or `"follower"`, to play
+        /// in lockstep with other displays), `syncUrl` (the relay, by default
+        /// the page's host on port 8001), `video` (`"webcam"`, a video URL or
+        /// `{ src, mask, threshold, key, mirror }`, adding a keyed video scene)
+        /// `image` (a PNG/SVG URL or `{ src | path, viewBox, fit, sdf }`,
+        /// adding a scene shaped like the picture) `text` (a message, or
+        /// `{ text, countdownTo }` where `{countdown}` in the text counts down
+        /// to a date, adding a scene of lettering) and `particles` (`{ count,
+        /// emitters, attractors, curl, curlScale, drag, life, size }` for the
+        /// Particles scene).
+        #[wasm_bindgen(constructor)]
+        pub fn new(canvas: web_sys::HtmlCanvasElement, options: JsValue) -> Result<Viz, VizError> {
+            let mut opts = Options::embedded();
+            if let Some(key) = options::storage_key(&options)? {
+                store::restore(&mut opts, &key);
+            }
+            opts.apply_js(&options)?;
+            Ok(Viz { handle: render::start(canvas, opts)? })
+        }
+
+        /// Advance to the next playlist entry.
+        pub fn next(&self) {
+            self.handle.renderer().borrow_mut().next();
+        }
+
+        /// Go back to the previous playlist entry.
+        pub fn prev(&self) {
+            self.handle.renderer().borrow_mut().prev();
+        }
+
+        /// Jump to the scene called `name`.
+        pub fn goto(&self, name: &str) -> Result<(), VizError> {
+            self.handle.renderer().borrow_mut().goto(name)
+        }
+
+        /// Freeze the animation and the playlist.
+        pub fn pause(&self) {
+            self.handle.renderer().borrow_mut().pause();
+        }
+
+        pub fn resume(&self) {
+            self.handle.renderer().borrow_mut().resume();
+        }
+
+        #[wasm_bindgen(getter)]
+        pub fn paused(&self) -> bool {
+            self.handle.renderer().borrow().paused()
+        }
+
+        /// Pause and advance `frames` frames (1/60 s each); negative steps back.
+        pub fn step(&self, frames: i32) {
+            self.handle.renderer().borrow_mut().step(frames);
+        }
+
+        /// Scrub to `seconds` into the current scene.
+        pub fn seek(&self, seconds: f64) {
+            self.handle.renderer().borrow_mut().seek(seconds * 1000.0);
+        }
+
+        /// Seconds into the current scene.
+        #[wasm_bindgen(getter, js_name = sceneTime)]
+        pub fn scene_time(&self) -> f64 {
+            self.handle.renderer().borrow().scene_time_ms() / 1000.0
+        }
+
+        /// Playback speed factor, 1 = real time (clamped to 0..8).
+        #[wasm_bindgen(getter)]
+        pub fn speed(&self) -> f64 {
+            self.handle.renderer().borrow().speed()
+        }
+
+        #[wasm_bindgen(setter)]
+        pub fn set_speed(&self, speed: f64) {
+            self.handle.renderer().borrow_mut().set_speed(speed);
+        }
+
+        /// Name of the scene on screen, `undefined` if none loaded.
+        #[wasm_bindgen(getter)]
+        pub fn scene(&self) -> Option<String> {
+            self.handle.renderer().borrow().scene_name().map(String::from)
+        }
+
+        /// Bind `key` (a `KeyboardEvent.key` value) to a command such as
+        /// `"next"`, `"blackout"` or `"scene-2"`; `null` removes the binding.
+        #[wasm_bindgen(js_name = bindKey)]
+        pub fn bind_key(&self, key: &str, command: Option<String>) -> Result<(), VizError> {
+            let renderer = self.handle.renderer();
+            let mut r = renderer.borrow_mut();
+            options::bind_key(r.keymap_mut(), key, command.as_deref())
+        }
+
+        /// Run a command by name, as if its key had been pressed.
+        pub fn command(&self, name: &str) -> Result<(), VizError> {
+            let cmd = name.parse().map_err(VizError::InvalidOption)?;
+            self.handle.renderer().borrow_mut().run(cmd);
+            Ok(())
+        }
+
+        /// Save the current scene, pattern, effects, view and safety / motion
+        /// settings as preset `name`.
+        #[wasm_bindgen(js_name = savePreset)]
+        pub fn save_preset(&self, name: &str) {
+            self.handle.renderer().borrow_mut().save_preset(name);
+        }
+
+        #[wasm_bindgen(js_name = recallPreset)]
+        pub fn recall_preset(&self, name: &str) -> Result<(), VizError> {
+            self.handle.renderer().borrow_mut().recall_preset(name)
+        }
+
+        #[wasm_bindgen(js_name = deletePreset)]
+        pub fn delete_preset(&self, name: &str) -> Result<(), VizError> {
+            self.handle.renderer().borrow_mut().delete_preset(name)
+        }
+
+        /// Preset names by slot (F1, F2, ...), `null` for an empty slot.
+        #[wasm_bindgen(getter)]
+        pub fn presets(&self) -> Vec<JsValue> {
+            self.handle.renderer().borrow().presets().slots().map(|name| name.map_or(JsValue::NULL, JsValue::from)).collect()
+        }
+
+        /// The presets as JSON, in the format of an exported file.
+        #[wasm_bindgen(js_name = exportPresets)]
+        pub fn export_presets(&self) -> String {
+            self.handle.renderer().borrow().presets().to_json()
+        }
+
+        /// Add presets from exported JSON, replacing those with the same name.
+        /// Returns how many were read.
+        #[wasm_bindgen(js_name = importPresets)]
+        pub fn import_presets(&self, json: &str) -> Result<usize, VizError> {
+            self.handle.renderer().borrow_mut().import_presets(json)
+        }
+
+        /// Current scene, clock, seed, pattern and effects as JSON; pass it to
+        /// `restore` to get back to this exact frame.
+        pub fn snapshot(&self) -> String {
+            self.handle.renderer().borrow().snapshot().to_json()
+        }
+
+        /// `snapshot()` in a compact binary form.
+        #[wasm_bindgen(js_name = snapshotBytes)]
+        pub fn snapshot_bytes(&self) -> Vec<u8> {
+            self.handle.renderer().borrow().snapshot().to_bytes()
+        }
+
+        pub fn restore(&self, state: &str) -> Result<(), VizError> {
+            let state = ShowState::from_json(state).map_err(VizError::InvalidOption)?;
+            self.handle.renderer().borrow_mut().restore(&state)
+        }
+
+        #[wasm_bindgen(js_name = restoreBytes)]
+        pub fn restore_bytes(&self, state: &[u8]) -> Result<(), VizError> {
+            let state = ShowState::from_bytes(state).map_err(VizError::InvalidOption)?;
+            self.handle.renderer().borrow_mut().restore(&state)
+        }
+
+        /// Whether the photosensitivity flash limiter is on.
+        #[wasm_bindgen(getter)]
+        pub fn safety(&self) -> bool {
+            self.handle.renderer().borrow().safety()
+        }
+
+        #[wasm_bindgen(setter)]
+        pub fn set_safety(&self, on: bool) -> Result<(), VizError> {
+            self.handle.renderer().borrow_mut().set_safety(on)
+        }
+
+        /// Whether the calm reduced-motion profile is in use.
+        #[wasm_bindgen(getter, js_name = reducedMotion)]
+        pub fn reduced_motion(&self) -> bool {
+            self.handle.renderer().borrow().reduced_motion()
+        }
+
+        /// `true` / `false` force the calm profile on or off; `"auto"` follows
+        /// `prefers-reduced-motion`.
+        #[wasm_bindgen(setter, js_name = reducedMotion)]
+        pub fn set_reduced_motion(&self, value: JsValue) -> Result<(), VizError> {
+            let pref = options::motion_pref(&value)?;
+            self.handle.renderer().borrow_mut().set_motion(pref);
+            Ok(())
+        }
+
+        /// Limits of this instance's adaptive render scale. Pass equal
+        /// `min_scale` and `max_scale` to pin a fixed scale.
+        #[wasm_bindgen(js_name = setRenderScale)]
+        pub fn set_render_scale(&self, min_scale: f32, max_scale: f32, target_fps: f32) {
+            let cfg = crate::perf::ScaleConfig::new(min_scale, max_scale, target_fps);
+            self.handle.renderer().borrow_mut().set_scale_config(cfg);
+        }
+
+        /// Write the FPS / render-scale line into the `stats` element.
+        #[wasm_bindgen(setter, js_name = statsOverlay)]
+        pub fn set_stats_overlay(&self, visible: bool) {
+            self.handle.renderer().borrow_mut().set_stats_visible(visible);
+        }
+
+        /// Whether this instance's performance HUD is shown.
+        #[wasm_bindgen(getter, js_name = hudVisible)]
+        pub fn hud_visible(&self) -> bool {
+            self.handle.renderer().borrow().hud_visible()
+        }
+
+        #[wasm_bindgen(setter, js_name = hudVisible)]
+        pub fn set_hud_visible(&self, visible: bool) {
+            self.handle.renderer().borrow_mut().set_hud_visible(visible);
+        }
+
+        /// This instance's latest frame statistics: `{ fps, frameMs, p99Ms,
+        /// scale, sceneMs, postMs, gpuTiming, history }`.
+        #[wasm_bindgen(js_name = frameStats)]
+        pub fn frame_stats(&self) -> JsValue {
+            self.handle.renderer().borrow().frame_stats()
+        }
+
+        /// Stop rendering and remove the event listeners.
+        pub fn destroy(&mut self) {
+            self.handle.destroy();
+        }
+    }
 
     /// Update the upper-left overlay text from Rust.
     #[wasm_bindgen]
-    pub fn set_overlay_text(text: &str) -> Result<(), JsValue> {
-        let window = web_sys::window().ok_or("no window")?;
-        let document = window.document().ok_or("no document")?;
+    pub fn set_overlay_text(text: &str) -> Result<(), VizError> {
+        let document = document()?;
         if let Some(elem) = document.get_element_by_id("overlay") {
             elem.set_text_content(Some(text));
         }
         Ok(())
     }
 
+    // The standalone page's instance, if it has started.
+    fn with_page<T>(f: impl FnOnce(&mut render::Renderer) -> T) -> Option<T> {
+        PAGE.with(|p| p.borrow().as_ref().map(|h| f(&mut h.renderer().borrow_mut())))
+    }
+
+    /// Configure the dynamic resolution of the standalone page's offscreen
+    /// scene/mask targets. Pass equal `min_scale` and `max_scale` to pin a
+    /// fixed scale. Embedded instances have `Viz.setRenderScale`.
+    #[wasm_bindgen]
+    pub fn set_render_scale(min_scale: f32, max_scale: f32, target_fps: f32) {
+        with_page(|r| r.set_scale_config(crate::perf::ScaleConfig::new(min_scale, max_scale, target_fps)));
+    }
+
+    /// Show or hide the standalone page's FPS / render-scale stats line.
+    #[wasm_bindgen]
+    pub fn set_stats_overlay(visible: bool) {
+        with_page(|r| r.set_stats_visible(visible));
+    }
+
+    /// Show or hide the standalone page's performance HUD (FPS, frame-time
+    /// graph, per-pass timings).
+    #[wasm_bindgen]
+    pub fn set_hud_visible(visible: bool) {
+        with_page(|r| r.set_hud_visible(visible));
+    }
+
+    /// The standalone page's latest frame statistics: `{ fps, frameMs, p99Ms,
+    /// scale, sceneMs, postMs, gpuTiming, history }`, or `undefined` before it
+    /// has started. Intended for automated runs that log performance.
+    #[wasm_bindgen]
+    pub fn frame_stats() -> JsValue {
+        with_page(|r| r.frame_stats()).unwrap_or(JsValue::UNDEFINED)
+    }
+
+    fn document() -> Result<web_sys::Document, VizError> {
+        web_sys::window()
+            .ok_or_else(|| VizError::MissingElement("window".into()))?
+            .document()
+            .ok_or_else(|| VizError::MissingElement("document".into()))
+    }
+
+    // Route panics to the console and the overlay; otherwise a panic only shows
+    // up as an opaque `unreachable` trap with the page frozen on its last frame.
+    fn install_panic_hook() {
+        std::panic::set_hook(Box::new(|info| {
+            let msg = format!("panic: {info}");
+            web_sys::console::error_1(&JsValue::from_str(&msg));
+            let _ = set_overlay_text(&msg);
+        }));
+    }
+
+    /// Starts the standalone page on canvas `#c`, configured from the URL query
+    /// (see `crate::query`). Pages without one (embedders) create their own
+    /// instances with `new Viz(...)`.
     #[wasm_bindgen(start)]
-    pub fn main() -> Result<(), JsValue> {
-        let window = web_sys::window().ok_or("no window")?;
-        let document = window.document().ok_or("no document")?;
-        let canvas = document
-            .get_element_by_id("c")
-            .ok_or("canvas not found")?
-            .dyn_into::<web_sys::HtmlCanvasElement>()?;
-
-        render::start(canvas)?;
+    pub fn main() -> Result<(), VizError> {
+        install_panic_hook();
+        let Some(elem) = document()?.get_element_by_id("c") else {
+            return Ok(());
+        };
+        let canvas = elem
+            .dyn_into::<web_sys::HtmlCanvasElement>()
+            .map_err(|_| VizError::MissingElement("canvas #c (element is not a canvas)".into()))?;
+
+        let search = web_sys::window().map(|w| w.location().search()).transpose()?.unwrap_or_default();
+        let query = crate::query::parse(&search)
+            .map_err(|errors| VizError::InvalidOption(format!("URL: {}", errors.join("\n"))))
+            .inspect_err(report)?;
+        // The URL wins over what was stored by the last session
+        let mut opts = Options::page();
+        store::restore(&mut opts, store::PAGE_KEY);
+        opts.apply_query(&query);
+        match query.playlist_url {
+            None => start_page(canvas, opts).inspect_err(report),
+            // Kiosk shows keep their playlist next to the page; start once it is in
+            Some(url) => {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let started = async {
+                        let text = shaders::fetch_text(&url)
+                            .await
+                            .ok_or_else(|| VizError::InvalidOption(format!("playlist: cannot load {url}")))?;
+                        let json = js_sys::JSON::parse(&text)
+                            .map_err(|_| VizError::InvalidOption(format!("playlist: {url} is not valid JSON")))?;
+                        opts.playlist = Some(options::parse_playlist(&json)?);
+                        start_page(canvas, opts)
+                    };
+                    let _ = started.await.inspect_err(report);
+                });
+                Ok(())
+            }
+        }
+    }
+
+    fn start_page(canvas: web_sys::HtmlCanvasElement, opts: Options) -> Result<(), VizError> {
+        let handle = render::start(canvas, opts)?;
+        PAGE.with(|p| *p.borrow_mut() = Some(handle));
         Ok(())
     }
+
+    // Start-up failures go to the overlay as well as the console, so a kiosk
+    // screen says what is wrong.
+    fn report(e: &VizError) {
+        web_sys::console::error_1(&JsValue::from_str(&e.to_string()));
+        let _ = set_overlay_text(&e.to_string());
+    }
 }
 
 // When compiling for non-wasm targets (e.g., `cargo test` on host),
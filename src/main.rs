@@ -1,8 +1,16 @@
 //! Host-side helper: `cargo run` builds the WASM site, starts a local HTTP
-//! server for `dist/`, and (if available) exposes it via ngrok.
+//! server for `dist/`, relays multi-display sync, and (if available) exposes
+//! the site via ngrok.
 
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
 use std::process::{Command, Stdio};
-use std::{env, thread, time::Duration};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, UNIX_EPOCH};
+use std::{env, fs, thread, time::Duration};
+
+use viz_wasm::sync::{Relay, SYNC_PORT};
 
 fn main() {
     // Only meaningful on non-wasm targets.
@@ -37,12 +45,11 @@ fn main() {
 
     // Ensure wasm32 target is added
     // Ensure wasm32 target present; if missing instruct user and exit
-    if Command::new("rustup")
+    if !Command::new("rustup")
         .args(["target", "list", "--installed"])
         .output()
         .map(|o| String::from_utf8_lossy(&o.stdout).contains("wasm32-unknown-unknown"))
         .unwrap_or(false)
-        == false
     {
         eprintln!("Rust target wasm32-unknown-unknown not installed. Run `rustup target add wasm32-unknown-unknown` and retry. See README.md.");
         std::process::exit(1);
@@ -50,7 +57,7 @@ fn main() {
 
     // Build wasm bundle
     println!("Building WASM pkg …");
-    match Command::new(&wasm_pack_exe)
+    match Command::new(wasm_pack_exe)
         .args([
             "build",
             "--release",
@@ -83,6 +90,8 @@ fn main() {
     // Bind explicitly to 0.0.0.0 so the service is reachable from outside
     // the host machine (e.g. mobile devices on the same network) without
     // requiring ngrok or a similar tunnel.
+    // The server lives until this process is killed, so it is never waited on.
+    #[allow(clippy::zombie_processes)]
     let _server = Command::new("python3")
         .args([
             "-m",
@@ -98,7 +107,13 @@ fn main() {
         .spawn()
         .expect("failed to start http server");
 
-    // 3. Try to start ngrok if installed
+    // 3. Watch shader sources so the page can hot-reload them (see src/wasm/shaders.rs)
+    thread::spawn(|| watch_shaders(Path::new("static/shaders")));
+
+    // 4. Relay clock and show state between sync leader and followers (see src/sync.rs)
+    thread::spawn(|| serve_sync(SYNC_PORT));
+
+    // 5. Try to start ngrok if installed
     let ngrok = Command::new("ngrok")
         .args(["http", "8000"])
         .stdout(Stdio::inherit())
@@ -115,3 +130,136 @@ fn main() {
         thread::sleep(Duration::from_secs(60));
     }
 }
+
+/// Poll `dir` for shader edits and rewrite `manifest.json` (relative path ->
+/// mtime in ms) whenever something changes. The page polls the manifest and
+/// re-fetches only the files whose timestamp moved.
+fn watch_shaders(dir: &Path) {
+    let mut last = String::new();
+    loop {
+        let manifest = shader_manifest(dir);
+        if manifest != last {
+            if !last.is_empty() {
+                println!("shaders changed, reloading in browser …");
+            }
+            // Write then rename, so a poll never reads a half-written manifest
+            let tmp = dir.join("manifest.json.tmp");
+            if let Err(e) = fs::write(&tmp, &manifest).and_then(|()| fs::rename(&tmp, dir.join("manifest.json"))) {
+                eprintln!("failed to write shader manifest: {e}");
+            }
+            last = manifest;
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+}
+
+/// Answer the sync protocol on `port`, one short-lived connection per request.
+/// Host time is milliseconds since the relay started.
+fn serve_sync(port: u16) {
+    let listener = match TcpListener::bind(("0.0.0.0", port)) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("sync relay unavailable on port {port}: {e}");
+            return;
+        }
+    };
+    println!("Sync relay at http://0.0.0.0:{port} …");
+    let relay = Arc::new(Mutex::new(Relay::default()));
+    let start = Instant::now();
+    for stream in listener.incoming().flatten() {
+        let relay = relay.clone();
+        thread::spawn(move || {
+            if let Err(e) = sync_request(stream, &relay, start) {
+                eprintln!("sync request failed: {e}");
+            }
+        });
+    }
+}
+
+fn sync_request(mut stream: TcpStream, relay: &Mutex<Relay>, start: Instant) -> std::io::Result<()> {
+    const MAX_BODY: usize = 64 * 1024;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or("").to_string(), parts.next().unwrap_or("/").to_string());
+    let mut len = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                len = value.trim().parse().unwrap_or(0).min(MAX_BODY);
+            }
+        }
+    }
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body)?;
+
+    // Browsers ask before cross-origin posts with a JSON content type
+    let (status, body) = if method == "OPTIONS" {
+        (204, String::new())
+    } else {
+        let host_ms = start.elapsed().as_secs_f64() * 1000.0;
+        let reply = relay.lock().unwrap_or_else(|e| e.into_inner()).handle(
+            &method,
+            &path,
+            &String::from_utf8_lossy(&body),
+            host_ms,
+        );
+        (reply.status, reply.body)
+    };
+    let reason = match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        _ => "Not Found",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+         Access-Control-Allow-Headers: Content-Type\r\n\
+         Cache-Control: no-store\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+fn shader_manifest(dir: &Path) -> String {
+    fn walk(base: &Path, dir: &Path, out: &mut Vec<(String, u128)>) {
+        let Ok(entries) = fs::read_dir(dir) else { return };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(base, &path, out);
+                continue;
+            }
+            let rel = path.strip_prefix(base).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+            if rel == "manifest.json" || rel == "manifest.json.tmp" {
+                continue;
+            }
+            let mtime = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis())
+                .unwrap_or(0);
+            out.push((rel, mtime));
+        }
+    }
+    let mut files = Vec::new();
+    walk(dir, dir, &mut files);
+    files.sort();
+    let body: Vec<String> = files
+        .iter()
+        .map(|(name, t)| format!("  \"{}\": {}", name.replace('"', "\\\""), t))
+        .collect();
+    format!("{{\n{}\n}}\n", body.join(",\n"))
+}
@@ -0,0 +1,64 @@
+//! Reduced-motion profile for viewers who ask for less movement, e.g. through
+//! the `prefers-reduced-motion` media query.
+//!
+//! The calm profile keeps the scene rotation but slows the pattern, holds each
+//! scene for longer and (in the renderer) turns off displacement and flicker.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::pattern::PatternParams;
+
+/// Factor applied to pattern rotation, drift and hue-cycling speeds.
+pub const CALM_SPEED: f32 = 0.25;
+/// Factor applied to playlist entry durations.
+pub const CALM_DURATION: f64 = 2.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MotionPref {
+    /// Follow the system setting.
+    #[default]
+    Auto,
+    Reduce,
+    Full,
+}
+
+impl MotionPref {
+    /// Whether to use the calm profile, given the system preference.
+    pub fn reduced(self, system_prefers_reduced: bool) -> bool {
+        match self {
+            MotionPref::Auto => system_prefers_reduced,
+            MotionPref::Reduce => true,
+            MotionPref::Full => false,
+        }
+    }
+}
+
+impl FromStr for MotionPref {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "auto" => MotionPref::Auto,
+            "reduce" | "reduced" => MotionPref::Reduce,
+            "full" => MotionPref::Full,
+            _ => return Err(format!("unknown motion setting \"{s}\" (auto, reduce or full)")),
+        })
+    }
+}
+
+/// `p` with every speed scaled by [`CALM_SPEED`]; the look is unchanged.
+pub fn calm(p: &PatternParams) -> PatternParams {
+    PatternParams {
+        theta_speed: p.theta_speed * CALM_SPEED,
+        drift_x: p.drift_x * CALM_SPEED,
+        drift_y: p.drift_y * CALM_SPEED,
+        dot_theta_speed: p.dot_theta_speed * CALM_SPEED,
+        dot_drift_x: p.dot_drift_x * CALM_SPEED,
+        dot_drift_y: p.dot_drift_y * CALM_SPEED,
+        color_speed: p.color_speed * CALM_SPEED,
+        ..*p
+    }
+}
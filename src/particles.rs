@@ -0,0 +1,217 @@
+//! Particle clouds as a scene shape. The simulation runs on the GPU by
+//! transform feedback (`static/shaders/particle_update.vert`); each particle
+//! is born at an emitter, pushed around by curl noise and attractors, and dies
+//! after its lifetime to be born again.
+//!
+//! [`step`] does on the CPU what the update shader does per particle, with the
+//! same hashes and noise, so the forces can be checked without a GPU.
+
+use serde::{Deserialize, Serialize};
+
+pub use crate::glsl::{PARTICLE_MAX_ATTRACTORS as MAX_ATTRACTORS, PARTICLE_MAX_EMITTERS as MAX_EMITTERS};
+
+/// Most particles a scene may simulate.
+pub const MAX_COUNT: u32 = 100_000;
+/// Floats per particle in the feedback buffers: position, velocity, age, life.
+pub const PARTICLE_FLOATS: usize = 6;
+// Finite-difference step of the curl, in noise cells.
+const CURL_EPS: f32 = 0.01;
+// Keeps attraction finite at the attractor.
+const SOFTENING: f32 = 0.05;
+
+/// Where particles are born, in scene space.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Emitter {
+    pub pos: [f32; 2],
+    /// Particles start anywhere in this disc.
+    pub radius: f32,
+    /// Initial velocity, per second.
+    pub velocity: [f32; 2],
+    /// Random speed added in a random direction.
+    pub spread: f32,
+}
+
+impl Default for Emitter {
+    fn default() -> Self {
+        Self { pos: [0.0, -0.7], radius: 0.1, velocity: [0.0, 0.6], spread: 0.2 }
+    }
+}
+
+/// Pulls particles towards `pos`; a negative `strength` pushes them away.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Attractor {
+    pub pos: [f32; 2],
+    pub strength: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ParticleConfig {
+    pub count: u32,
+    pub emitters: Vec<Emitter>,
+    pub attractors: Vec<Attractor>,
+    /// Strength of the curl-noise flow.
+    pub curl: f32,
+    /// Noise cells per scene unit; larger gives smaller eddies.
+    pub curl_scale: f32,
+    /// Fraction of velocity lost per second, roughly.
+    pub drag: f32,
+    /// Lifetime range in seconds, `[min, max]`.
+    pub life: [f32; 2],
+    /// Sprite diameter in scene units.
+    pub size: f32,
+}
+
+impl Default for ParticleConfig {
+    fn default() -> Self {
+        Self {
+            count: 6000,
+            emitters: vec![
+                Emitter { pos: [-0.55, -0.7], velocity: [0.25, 0.6], ..Emitter::default() },
+                Emitter { pos: [0.55, -0.7], velocity: [-0.25, 0.6], ..Emitter::default() },
+            ],
+            attractors: vec![Attractor { pos: [0.0, 0.1], strength: 0.3 }],
+            curl: 0.8,
+            curl_scale: 1.5,
+            drag: 0.5,
+            life: [2.0, 4.5],
+            size: 0.05,
+        }
+    }
+}
+
+impl ParticleConfig {
+    /// Parse and [`validate`](Self::validate); missing fields take their defaults.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let config: Self = serde_json::from_str(json).map_err(|e| format!("particles: {e}"))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_COUNT).contains(&self.count) {
+            return Err(format!("particles: count must be 1 to {MAX_COUNT}"));
+        }
+        if self.emitters.is_empty() || self.emitters.len() > MAX_EMITTERS as usize {
+            return Err(format!("particles: 1 to {MAX_EMITTERS} emitters"));
+        }
+        if self.attractors.len() > MAX_ATTRACTORS as usize {
+            return Err(format!("particles: at most {MAX_ATTRACTORS} attractors"));
+        }
+        if !(self.life[0] > 0.0 && self.life[1] >= self.life[0]) {
+            return Err("particles: life must be [min, max] seconds with 0 < min <= max".into());
+        }
+        if !(self.size > 0.0 && self.size.is_finite()) {
+            return Err("particles: size must be positive".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Particle {
+    pub pos: [f32; 2],
+    pub vel: [f32; 2],
+    /// Seconds since birth; negative until the first birth.
+    pub age: f32,
+    /// Seconds it lives.
+    pub life: f32,
+}
+
+impl Particle {
+    /// As laid out in the feedback buffers.
+    pub fn to_floats(self) -> [f32; PARTICLE_FLOATS] {
+        [self.pos[0], self.pos[1], self.vel[0], self.vel[1], self.age, self.life]
+    }
+}
+
+/// Starting state of particle `i` of `count`: unborn, with births spread
+/// evenly over the longest lifetime so the cloud fills in steadily.
+pub fn seed(config: &ParticleConfig, i: u32) -> Particle {
+    let stagger = i as f32 / config.count as f32;
+    Particle { age: -config.life[1] * stagger, ..born(config, i, stagger * 97.0) }
+}
+
+/// A fresh particle `i` at one of the emitters; `time` varies the draw.
+pub fn born(config: &ParticleConfig, i: u32, time: f32) -> Particle {
+    let r = hash22([i as f32, time * 13.1]);
+    let s = hash22([time * 7.7, i as f32 * 0.37]);
+    let e = &config.emitters[((r[0] * config.emitters.len() as f32) as usize).min(config.emitters.len() - 1)];
+    let (angle, dist) = (s[0] * std::f32::consts::TAU, r[1].sqrt() * e.radius);
+    let jitter = s[1] * e.spread;
+    Particle {
+        pos: [e.pos[0] + angle.cos() * dist, e.pos[1] + angle.sin() * dist],
+        vel: [e.velocity[0] + angle.sin() * jitter, e.velocity[1] - angle.cos() * jitter],
+        age: 0.0,
+        life: config.life[0] + (config.life[1] - config.life[0]) * hash12([i as f32, 3.7]),
+    }
+}
+
+/// Advance particle `i` by `dt` seconds at scene time `time`.
+pub fn step(config: &ParticleConfig, p: &mut Particle, i: u32, time: f32, dt: f32) {
+    if p.age < 0.0 {
+        p.age += dt;
+        return;
+    }
+    if p.age >= p.life {
+        *p = born(config, i, time);
+        return;
+    }
+    let c = curl(p.pos, time, config.curl_scale);
+    let a = attraction(p.pos, &config.attractors);
+    let keep = (-config.drag * dt).exp();
+    for k in 0..2 {
+        p.vel[k] = (p.vel[k] + (c[k] * config.curl + a[k]) * dt) * keep;
+        p.pos[k] += p.vel[k] * dt;
+    }
+    p.age += dt;
+}
+
+/// Divergence-free flow at `p`: the curl of a drifting value-noise potential.
+pub fn curl(p: [f32; 2], time: f32, scale: f32) -> [f32; 2] {
+    let psi = |x: f32, y: f32| value_noise([x * scale, y * scale + time * 0.2]);
+    let e = CURL_EPS / scale.max(f32::EPSILON);
+    let dy = psi(p[0], p[1] + e) - psi(p[0], p[1] - e);
+    let dx = psi(p[0] + e, p[1]) - psi(p[0] - e, p[1]);
+    [dy / (2.0 * e), -dx / (2.0 * e)]
+}
+
+/// Summed pull of the attractors at `p`.
+pub fn attraction(p: [f32; 2], attractors: &[Attractor]) -> [f32; 2] {
+    attractors.iter().fold([0.0, 0.0], |acc, a| {
+        let d = [a.pos[0] - p[0], a.pos[1] - p[1]];
+        let k = a.strength / (d[0] * d[0] + d[1] * d[1] + SOFTENING);
+        [acc[0] + d[0] * k, acc[1] + d[1] * k]
+    })
+}
+
+// `hash12` / `hash22` of `common/hash.glsl` (43758.5453 there; f32 holds no more).
+const HASH_SCALE: f32 = 43_758.547;
+
+fn hash12(p: [f32; 2]) -> f32 {
+    fract((p[0] * 127.1 + p[1] * 311.7).sin() * HASH_SCALE)
+}
+
+fn hash22(p: [f32; 2]) -> [f32; 2] {
+    [fract((p[0] * 127.1 + p[1] * 311.7).sin() * HASH_SCALE), fract((p[0] * 269.5 + p[1] * 183.3).sin() * HASH_SCALE)]
+}
+
+fn fract(x: f32) -> f32 {
+    x - x.floor()
+}
+
+// Smoothly interpolated hash on the integer lattice, as `value_noise` in
+// particle_update.vert.
+fn value_noise(p: [f32; 2]) -> f32 {
+    let (ix, iy) = (p[0].floor(), p[1].floor());
+    let (fx, fy) = (p[0] - ix, p[1] - iy);
+    let (ux, uy) = (fx * fx * (3.0 - 2.0 * fx), fy * fy * (3.0 - 2.0 * fy));
+    let mix = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let a = hash12([ix, iy]);
+    let b = hash12([ix + 1.0, iy]);
+    let c = hash12([ix, iy + 1.0]);
+    let d = hash12([ix + 1.0, iy + 1.0]);
+    mix(mix(a, b, ux), mix(c, d, ux), uy)
+}
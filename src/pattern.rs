@@ -0,0 +1,95 @@
+//! Parameters of the stripe / polka-dot fill the post pass draws inside the mask.
+//!
+//! Re-rolled whenever the scene changes. Kept free of web APIs; callers supply
+//! the random source.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PatternParams {
+    // stripes
+    pub theta0: f32,
+    pub theta_speed: f32,
+    pub density: f32,
+    pub thickness: f32,
+    pub drift_x: f32,
+    pub drift_y: f32,
+    // polka
+    pub mode_polka: bool,
+    pub dot_theta0: f32,
+    pub dot_theta_speed: f32,
+    pub dot_drift_x: f32,
+    pub dot_drift_y: f32,
+    pub dot_density: f32,
+    pub dot_rmin: f32,
+    pub dot_rmax: f32,
+    // shared
+    pub color_speed: f32,
+}
+
+impl Default for PatternParams {
+    fn default() -> Self {
+        Self {
+            theta0: 0.0, theta_speed: 0.1, density: 16.0, thickness: 0.5, drift_x: 0.05, drift_y: 0.03,
+            mode_polka: false,
+            dot_theta0: 0.0, dot_theta_speed: 0.08, dot_drift_x: 0.03, dot_drift_y: -0.02,
+            dot_density: 10.0, dot_rmin: 0.05, dot_rmax: 0.18,
+            color_speed: 0.1,
+        }
+    }
+}
+
+impl PatternParams {
+    /// Draw a fresh set of parameters; `frand` yields uniform values in `0..1`.
+    pub fn random(mut frand: impl FnMut() -> f32) -> Self {
+        // Fields in draw order, so a given random sequence always yields the same set.
+        let mut s = Self {
+            theta0: frand() * std::f32::consts::PI,
+            theta_speed: 0.05 + frand() * 0.3, // rad/s
+            density: 8.0 + frand() * 24.0,     // lines per unit
+            thickness: 0.15 + frand() * 0.7,   // 0..1 fraction
+            drift_x: (frand() * 2.0 - 1.0) * 0.15, // units/s
+            drift_y: (frand() * 2.0 - 1.0) * 0.15,
+            color_speed: 0.05 + frand() * 0.4, // hue cycles/s
+            // switch mode randomly
+            mode_polka: frand() > 0.5,
+            // polka params
+            dot_theta0: frand() * std::f32::consts::TAU,
+            dot_theta_speed: 0.02 + frand() * 0.2,
+            dot_drift_x: (frand()*2.0 - 1.0) * 0.2,
+            dot_drift_y: (frand()*2.0 - 1.0) * 0.2,
+            dot_density: 6.0 + frand() * 20.0,
+            dot_rmin: 0.03 + frand() * 0.12,
+            dot_rmax: 0.0,
+        };
+        s.dot_rmax = s.dot_rmin + 0.03 + frand() * 0.2;
+        s
+    }
+}
+
+/// Small deterministic generator (SplitMix64) so a seed reproduces the same
+/// sequence of patterns.
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Current position in the sequence; `Rng::new(rng.state())` continues it.
+    pub fn state(&self) -> u64 {
+        self.0
+    }
+
+    /// Uniform in `0..1`.
+    pub fn next_f32(&mut self) -> f32 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
@@ -0,0 +1,197 @@
+//! Frame-time tracking and adaptive render scaling.
+//!
+//! Kept free of any web APIs so the controller logic can be exercised from host
+//! tests; the wasm renderer feeds it `performance.now()` timestamps.
+
+use serde::{Deserialize, Serialize};
+
+/// Limits for the dynamic render scale applied to the offscreen scene/mask targets.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ScaleConfig {
+    /// Lowest allowed fraction of the canvas resolution.
+    pub min_scale: f32,
+    /// Highest allowed fraction of the canvas resolution.
+    pub max_scale: f32,
+    /// Frame rate the controller tries to hold.
+    pub target_fps: f32,
+}
+
+impl Default for ScaleConfig {
+    fn default() -> Self {
+        Self { min_scale: 0.5, max_scale: 1.0, target_fps: 60.0 }
+    }
+}
+
+impl ScaleConfig {
+    /// Build a config, clamping to sane ranges. Setting `min == max` pins the scale.
+    /// Non-finite arguments (e.g. `undefined` from JS) fall back to the defaults.
+    pub fn new(min_scale: f32, max_scale: f32, target_fps: f32) -> Self {
+        let d = Self::default();
+        let or = |v: f32, fallback: f32| if v.is_finite() { v } else { fallback };
+        let lo = or(min_scale, d.min_scale).clamp(0.1, 2.0);
+        let hi = or(max_scale, d.max_scale).clamp(0.1, 2.0);
+        Self {
+            min_scale: lo.min(hi),
+            max_scale: lo.max(hi),
+            target_fps: or(target_fps, d.target_fps).clamp(10.0, 240.0),
+        }
+    }
+
+    /// Re-check a config that did not come through [`ScaleConfig::new`], such as
+    /// one deserialized from storage.
+    pub fn validated(self) -> Self {
+        Self::new(self.min_scale, self.max_scale, self.target_fps)
+    }
+}
+
+/// Exponentially smoothed frame time.
+pub struct FrameMonitor {
+    last_ms: Option<f64>,
+    avg_ms: f64,
+}
+
+impl Default for FrameMonitor {
+    fn default() -> Self {
+        Self { last_ms: None, avg_ms: 1000.0 / 60.0 }
+    }
+}
+
+impl FrameMonitor {
+    /// Smoothing factor for the moving average.
+    const ALPHA: f64 = 0.1;
+    /// Gaps longer than this (hidden tab, debugger pause) are not frame cost.
+    const MAX_GAP_MS: f64 = 250.0;
+
+    /// Record a frame timestamp and return the raw delta since the previous one.
+    pub fn tick(&mut self, now_ms: f64) -> Option<f64> {
+        let dt = self.last_ms.map(|last| now_ms - last);
+        self.last_ms = Some(now_ms);
+        match dt {
+            Some(dt) if dt > 0.0 && dt < Self::MAX_GAP_MS => {
+                self.avg_ms += (dt - self.avg_ms) * Self::ALPHA;
+                Some(dt)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn avg_ms(&self) -> f64 {
+        self.avg_ms
+    }
+
+    pub fn fps(&self) -> f64 {
+        1000.0 / self.avg_ms.max(1e-3)
+    }
+}
+
+/// Steps the render scale down quickly when over budget and back up slowly
+/// once frames have stayed comfortably within it.
+pub struct AdaptiveScale {
+    scale: f32,
+    since_change_ms: f64,
+}
+
+impl Default for AdaptiveScale {
+    fn default() -> Self {
+        Self { scale: 1.0, since_change_ms: 0.0 }
+    }
+}
+
+impl AdaptiveScale {
+    const DOWN_HOLD_MS: f64 = 500.0;
+    const UP_HOLD_MS: f64 = 3000.0;
+    const DOWN_FACTOR: f32 = 0.85;
+    const UP_STEP: f32 = 0.05;
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Advance by one frame of `dt_ms` given the smoothed frame time, returning the new scale.
+    pub fn update(&mut self, cfg: &ScaleConfig, avg_ms: f64, dt_ms: f64) -> f32 {
+        // Not `clamp`: that panics on inverted or NaN bounds.
+        let clamped = self.scale.max(cfg.min_scale).min(cfg.max_scale);
+        if clamped != self.scale {
+            self.scale = clamped;
+            self.since_change_ms = 0.0;
+            return self.scale;
+        }
+        self.since_change_ms += dt_ms;
+        let budget = 1000.0 / cfg.target_fps as f64;
+        if avg_ms > budget * 1.15 && self.since_change_ms >= Self::DOWN_HOLD_MS {
+            self.scale = (self.scale * Self::DOWN_FACTOR).max(cfg.min_scale);
+            self.since_change_ms = 0.0;
+        } else if avg_ms < budget * 1.05 && self.since_change_ms >= Self::UP_HOLD_MS {
+            self.scale = (self.scale + Self::UP_STEP).min(cfg.max_scale);
+            self.since_change_ms = 0.0;
+        }
+        self.scale
+    }
+}
+
+/// Rolling window of recent frame times, drawn as the HUD's frame-time graph.
+pub struct FrameHistory {
+    samples: std::collections::VecDeque<f32>,
+    cap: usize,
+}
+
+impl FrameHistory {
+    pub fn new(cap: usize) -> Self {
+        Self { samples: std::collections::VecDeque::with_capacity(cap), cap: cap.max(1) }
+    }
+
+    pub fn push(&mut self, ms: f64) {
+        if self.samples.len() == self.cap {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(ms as f32);
+    }
+
+    /// Oldest-first frame times in milliseconds.
+    pub fn iter(&self) -> impl Iterator<Item = f32> + '_ {
+        self.samples.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn max_ms(&self) -> f32 {
+        self.iter().fold(0.0, f32::max)
+    }
+
+    /// Frame time at percentile `p` (0..=1), e.g. `0.99` for the 1% worst frames.
+    pub fn percentile(&self, p: f32) -> f32 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let mut v: Vec<f32> = self.iter().collect();
+        v.sort_by(f32::total_cmp);
+        let i = ((v.len() - 1) as f32 * p.clamp(0.0, 1.0)).round() as usize;
+        v[i]
+    }
+}
+
+/// Snapshot of the numbers shown in the HUD and returned by `frame_stats()`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameStats {
+    pub fps: f64,
+    pub frame_ms: f64,
+    pub scale: f32,
+    /// Scene pass (color + mask via MRT), smoothed.
+    pub scene_ms: f64,
+    /// Final post-process pass, smoothed.
+    pub post_ms: f64,
+    /// Pass timings come from GPU timer queries rather than CPU submit time.
+    pub gpu_timing: bool,
+}
+
+/// Exponential smoothing used for per-pass timings.
+pub fn smooth(avg: f64, sample: f64) -> f64 {
+    if avg == 0.0 { sample } else { avg + (sample - avg) * 0.1 }
+}
@@ -0,0 +1,272 @@
+//! Start-up settings from the page URL's query string, for kiosk players that
+//! can only be given a URL:
+//!
+//! `?scene=Pulsing%20Plus&seed=42&duration=30&fill=polka&overlay=off&autoplay=1&hud=on&playlist=show.json`
+//!
+//! Unknown keys and malformed values are errors, so a typo shows up on screen
+//! instead of being silently ignored.
+
+use serde::{Deserialize, Serialize};
+
+use crate::motion::MotionPref;
+use crate::silhouette::Fit;
+use crate::sync::Role;
+use crate::video::{MaskMode, VideoSource};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Fill {
+    Stripes,
+    Polka,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Query {
+    /// Scene to start on.
+    pub scene: Option<String>,
+    /// Seed for the pattern parameters, for reproducible runs.
+    pub seed: Option<u64>,
+    /// Seconds per playlist entry.
+    pub duration_s: Option<f64>,
+    /// Always use this fill instead of picking one at random.
+    pub fill: Option<Fill>,
+    pub overlay: Option<bool>,
+    pub autoplay: Option<bool>,
+    pub hud: Option<bool>,
+    /// Flash limiter for photosensitive viewers.
+    pub safety: Option<bool>,
+    /// `auto` follows `prefers-reduced-motion`; `reduce` / `full` override it.
+    pub motion: Option<MotionPref>,
+    /// Play in lockstep with other displays through the host relay.
+    pub sync: Option<Role>,
+    /// Relay address; the page's host on the sync port when `None`.
+    pub relay: Option<String>,
+    /// Adds a video or webcam scene.
+    pub video: Option<VideoSource>,
+    /// How the video is keyed into the mask.
+    pub mask: Option<MaskMode>,
+    /// Adds an image scene from this PNG or SVG URL.
+    pub image: Option<String>,
+    /// Placement of the image.
+    pub fit: Option<Fit>,
+    /// Adds a text scene with this message; a newline (`%0A`) or a literal
+    /// `\n` separates lines.
+    pub text: Option<String>,
+    /// Date `{countdown}` in the text counts down to.
+    pub countdown: Option<DateTime>,
+    /// JSON playlist to fetch, in the format of the `playlist` option.
+    pub playlist_url: Option<String>,
+}
+
+/// Parse `search` (with or without the leading `?`). All problems are
+/// reported, one message each.
+pub fn parse(search: &str) -> Result<Query, Vec<String>> {
+    let mut q = Query::default();
+    let mut errors = Vec::new();
+    let mut saw_video = false;
+    for pair in search.trim_start_matches('?').split('&').filter(|p| !p.is_empty()) {
+        let (key, raw) = pair.split_once('=').unwrap_or((pair, ""));
+        let key = decode(key);
+        saw_video |= key == "video";
+        let Some(value) = Some(decode(raw)).filter(|v| !v.is_empty()) else {
+            errors.push(format!("{key}: missing value"));
+            continue;
+        };
+        let bad = |expected: &str| format!("{key}={value}: expected {expected}");
+        let result = match key.as_str() {
+            "scene" => {
+                q.scene = Some(value.clone());
+                Ok(())
+            }
+            "seed" => value.parse().map(|s| q.seed = Some(s)).map_err(|_| bad("a whole number")),
+            "duration" => match value.parse::<f64>() {
+                Ok(s) if s.is_finite() && s > 0.0 => {
+                    q.duration_s = Some(s);
+                    Ok(())
+                }
+                _ => Err(bad("a positive number of seconds")),
+            },
+            "fill" => match value.as_str() {
+                "stripes" => Ok(Fill::Stripes),
+                "polka" => Ok(Fill::Polka),
+                _ => Err(bad("stripes or polka")),
+            }
+            .map(|f| q.fill = Some(f)),
+            "overlay" => flag(&value).map(|b| q.overlay = Some(b)).ok_or_else(|| bad("on or off")),
+            "autoplay" => flag(&value).map(|b| q.autoplay = Some(b)).ok_or_else(|| bad("on or off")),
+            "hud" => flag(&value).map(|b| q.hud = Some(b)).ok_or_else(|| bad("on or off")),
+            "safety" => flag(&value).map(|b| q.safety = Some(b)).ok_or_else(|| bad("on or off")),
+            "motion" => value.parse().map(|m| q.motion = Some(m)).map_err(|_| bad("auto, reduce or full")),
+            "sync" => value.parse().map(|r| q.sync = Some(r)).map_err(|_| bad("leader or follower")),
+            "relay" => {
+                q.relay = Some(value.clone());
+                Ok(())
+            }
+            "video" => value.parse().map(|v| q.video = Some(v)),
+            "mask" => value.parse().map(|m| q.mask = Some(m)).map_err(|_| bad("luminance, chroma or difference")),
+            "image" => {
+                q.image = Some(value.clone());
+                Ok(())
+            }
+            "fit" => value.parse().map(|f| q.fit = Some(f)).map_err(|_| bad("fill, contain or cover")),
+            "text" => {
+                q.text = Some(value.replace("\\n", "\n"));
+                Ok(())
+            }
+            "countdown" => value
+                .parse()
+                .map(|d| q.countdown = Some(d))
+                .map_err(|_| bad("a date like 2026-12-31 or 2026-12-31T23:00")),
+            "playlist" => {
+                q.playlist_url = Some(value.clone());
+                Ok(())
+            }
+            _ => Err(format!("unknown parameter \"{key}\"")),
+        };
+        errors.extend(result.err());
+    }
+    // A bad `video=` is already reported; only flag a mask with no video at all
+    if q.mask.is_some() && !saw_video {
+        errors.push("mask: needs video= as well".to_string());
+    }
+    if errors.is_empty() { Ok(q) } else { Err(errors) }
+}
+
+/// An ISO 8601 date with optional time and offset: `2026-12-31`,
+/// `2026-12-31T23:00`, `2026-12-31T23:00:30Z` or `2026-12-31T23:00+01:00`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: i32,
+    /// 1 to 12.
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    /// Minutes east of UTC; `None` means the viewer's local time.
+    pub utc_offset_min: Option<i32>,
+}
+
+impl DateTime {
+    /// Milliseconds since the Unix epoch, when the offset is known.
+    pub fn utc_ms(&self) -> Option<f64> {
+        let offset = self.utc_offset_min?;
+        let days = days_from_civil(self.year, self.month, self.day);
+        let secs = days * 86_400 + (self.hour * 3600 + self.minute * 60 + self.second) as i64 - offset as i64 * 60;
+        Some(secs as f64 * 1000.0)
+    }
+}
+
+impl std::str::FromStr for DateTime {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let (date, time) = match s.split_once('T') {
+            Some((d, t)) => (d, Some(t)),
+            None => (s, None),
+        };
+        let [year, month, day] = fields(date, '-', [4, 2, 2])?;
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year as i32, month) {
+            return Err(());
+        }
+        let mut dt = Self { year: year as i32, month, day, hour: 0, minute: 0, second: 0, utc_offset_min: None };
+        let Some(time) = time else { return Ok(dt) };
+        let (clock, zone) = match time.find(['Z', '+', '-']) {
+            Some(i) => time.split_at(i),
+            None => (time, ""),
+        };
+        let [hour, minute, second] = match clock.len() {
+            5 => fields(&format!("{clock}:00"), ':', [2, 2, 2])?,
+            _ => fields(clock, ':', [2, 2, 2])?,
+        };
+        if hour > 23 || minute > 59 || second > 59 {
+            return Err(());
+        }
+        (dt.hour, dt.minute, dt.second) = (hour, minute, second);
+        dt.utc_offset_min = match zone.split_at(zone.len().min(1)) {
+            ("", _) => None,
+            ("Z", "") => Some(0),
+            (sign @ ("+" | "-"), hhmm) => {
+                let [h, m] = fields(hhmm, ':', [2, 2])?;
+                if h > 23 || m > 59 {
+                    return Err(());
+                }
+                let minutes = (h * 60 + m) as i32;
+                Some(if sign == "-" { -minutes } else { minutes })
+            }
+            _ => return Err(()),
+        };
+        Ok(dt)
+    }
+}
+
+// `N` numbers of exactly the given digit counts separated by `sep`.
+fn fields<const N: usize>(s: &str, sep: char, digits: [usize; N]) -> Result<[u32; N], ()> {
+    let mut out = [0; N];
+    let mut parts = s.split(sep);
+    for (slot, len) in out.iter_mut().zip(digits) {
+        let part = parts.next().ok_or(())?;
+        if part.len() != len || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(());
+        }
+        *slot = part.parse().map_err(|_| ())?;
+    }
+    if parts.next().is_some() {
+        return Err(());
+    }
+    Ok(out)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year } as i64;
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn flag(v: &str) -> Option<bool> {
+    match v {
+        "1" | "on" | "true" | "yes" => Some(true),
+        "0" | "off" | "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+// Percent-decoding as for `application/x-www-form-urlencoded` (`+` is a space).
+// Invalid escapes are kept literally.
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                (Some(hi), Some(lo)) => {
+                    out.push(hi << 4 | lo);
+                    i += 2;
+                }
+                _ => out.push(b'%'),
+            },
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn hex(c: u8) -> Option<u8> {
+    (c as char).to_digit(16).map(|d| d as u8)
+}
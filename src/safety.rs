@@ -0,0 +1,201 @@
+//! Photosensitive-epilepsy safety: flash detection on small frame readbacks and
+//! a limiter that tones the effects down until the output stays within the
+//! WCAG 2.3.1 thresholds.
+//!
+//! A *flash* is a pair of opposing changes in relative luminance of at least
+//! [`LUMA_DELTA`] where the darker state is below [`DARK_LIMIT`], or a pair of
+//! opposing changes of at least [`RED_DELTA`] in saturated red. More than
+//! [`MAX_FLASHES_PER_S`] in any one second fail. WCAG only counts flashes
+//! covering a quarter of a 10° visual field; the analyzer instead counts each
+//! tile of a coarse grid on its own, which errs on the safe side for large
+//! projections.
+//!
+//! Free of web APIs; the renderer feeds it RGBA readbacks.
+
+use std::collections::VecDeque;
+
+pub const MAX_FLASHES_PER_S: f32 = 3.0;
+/// Relative-luminance change of one flash transition.
+pub const LUMA_DELTA: f32 = 0.1;
+/// A luminance flash only counts when its darker state is below this.
+pub const DARK_LIMIT: f32 = 0.8;
+/// Change in [`red_level`] of one red flash transition.
+pub const RED_DELTA: f32 = 20.0;
+/// Flash rate allowed to effects when no limit applies.
+pub const UNLIMITED_FLASH_HZ: f32 = 1000.0;
+
+/// WCAG relative luminance of an sRGB colour, `0..=1`.
+pub fn relative_luminance(r: u8, g: u8, b: u8) -> f32 {
+    let lin = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.039_28 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    0.2126 * lin(r) + 0.7152 * lin(g) + 0.0722 * lin(b)
+}
+
+/// Saturated-red level, `(R - G - B) * 320` on `0..=1` channels for pixels
+/// whose red share is at least 80 %, else 0.
+pub fn red_level(r: u8, g: u8, b: u8) -> f32 {
+    let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+    if r + g + b > 0.0 && r / (r + g + b) >= 0.8 { ((r - g - b) * 320.0).max(0.0) } else { 0.0 }
+}
+
+/// Flash rates over the last second, in flashes per second, of the worst tile.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FlashReport {
+    pub flashes_per_s: f32,
+    pub red_flashes_per_s: f32,
+}
+
+impl FlashReport {
+    pub fn worst(&self) -> f32 {
+        self.flashes_per_s.max(self.red_flashes_per_s)
+    }
+
+    pub fn is_safe(&self) -> bool {
+        self.worst() <= MAX_FLASHES_PER_S
+    }
+}
+
+// Opposing transitions of one signal, with hysteresis: a transition needs a
+// change of `delta` from the last extreme in the other direction.
+#[derive(Clone, Debug, Default)]
+struct Transitions {
+    extreme: Option<f32>,
+    rising: Option<bool>,
+    times: VecDeque<f64>,
+}
+
+impl Transitions {
+    fn push(&mut self, t_ms: f64, v: f32, delta: f32, dark_limit: f32) {
+        let Some(extreme) = self.extreme else {
+            self.extreme = Some(v);
+            return;
+        };
+        match self.rising {
+            // Still moving the same way: the swing continues from further out
+            Some(true) if v > extreme => self.extreme = Some(v),
+            Some(false) if v < extreme => self.extreme = Some(v),
+            _ if (v - extreme).abs() >= delta => {
+                if extreme.min(v) < dark_limit {
+                    self.times.push_back(t_ms);
+                }
+                self.rising = Some(v > extreme);
+                self.extreme = Some(v);
+            }
+            _ => {}
+        }
+        while self.times.front().is_some_and(|&t0| t_ms - t0 >= 1000.0) {
+            self.times.pop_front();
+        }
+    }
+
+    // Two transitions make a flash.
+    fn flashes(&self) -> f32 {
+        self.times.len() as f32 / 2.0
+    }
+}
+
+/// Counts flashes per tile over a sliding one-second window.
+#[derive(Clone, Debug)]
+pub struct FlashAnalyzer {
+    grid: usize,
+    luma: Vec<Transitions>,
+    red: Vec<Transitions>,
+}
+
+impl Default for FlashAnalyzer {
+    fn default() -> Self {
+        Self::new(4)
+    }
+}
+
+impl FlashAnalyzer {
+    /// Analyse frames as `grid` × `grid` tiles.
+    pub fn new(grid: usize) -> Self {
+        let grid = grid.max(1);
+        Self { grid, luma: vec![Transitions::default(); grid * grid], red: vec![Transitions::default(); grid * grid] }
+    }
+
+    /// Add a `width` × `height` RGBA frame shown at `t_ms` (wall time).
+    pub fn push(&mut self, t_ms: f64, rgba: &[u8], width: usize, height: usize) -> FlashReport {
+        let n = self.grid * self.grid;
+        let mut luma = vec![0.0f32; n];
+        let mut red = vec![0.0f32; n];
+        let mut count = vec![0u32; n];
+        for (i, px) in rgba.chunks_exact(4).take(width * height).enumerate() {
+            let (x, y) = (i % width, i / width);
+            let tile = (y * self.grid / height) * self.grid + x * self.grid / width;
+            luma[tile] += relative_luminance(px[0], px[1], px[2]);
+            red[tile] += red_level(px[0], px[1], px[2]);
+            count[tile] += 1;
+        }
+        for tile in 0..n {
+            let c = count[tile].max(1) as f32;
+            self.luma[tile].push(t_ms, luma[tile] / c, LUMA_DELTA, DARK_LIMIT);
+            // Red flashes count whatever the luminance
+            self.red[tile].push(t_ms, red[tile] / c, RED_DELTA, f32::INFINITY);
+        }
+        self.report()
+    }
+
+    pub fn report(&self) -> FlashReport {
+        let worst = |tracks: &[Transitions]| tracks.iter().map(Transitions::flashes).fold(0.0, f32::max);
+        FlashReport { flashes_per_s: worst(&self.luma), red_flashes_per_s: worst(&self.red) }
+    }
+}
+
+/// Caps the renderer applies while safety mode is on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits {
+    /// Highest rate of any periodic flicker or blink, in Hz.
+    pub max_flash_hz: f32,
+    /// Highest hue-cycling speed of the pattern fill, in cycles per second.
+    pub max_color_speed: f32,
+    /// Output contrast, 1 = unchanged.
+    pub contrast: f32,
+}
+
+/// Tightens [`Limits`] while the measured flash rate is close to the threshold
+/// and relaxes them slowly once it has been low for a while.
+#[derive(Clone, Debug)]
+pub struct SafetyLimiter {
+    // 1 = the base limits, smaller = stricter
+    level: f32,
+}
+
+impl Default for SafetyLimiter {
+    fn default() -> Self {
+        Self { level: 1.0 }
+    }
+}
+
+impl SafetyLimiter {
+    /// Strictest level reached when flashes persist.
+    pub const MIN_LEVEL: f32 = 0.1;
+
+    /// Limits at the current level. Even the loosest keep effects below two
+    /// thirds of the WCAG rate.
+    pub fn limits(&self) -> Limits {
+        Limits {
+            max_flash_hz: MAX_FLASHES_PER_S * 2.0 / 3.0 * self.level,
+            max_color_speed: 0.25 * self.level,
+            contrast: 0.4 + 0.6 * self.level,
+        }
+    }
+
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    /// Feed the latest report; returns the limits to apply.
+    pub fn update(&mut self, report: &FlashReport) -> Limits {
+        let load = report.worst() / MAX_FLASHES_PER_S;
+        if load > 2.0 / 3.0 {
+            self.level = (self.level * 0.8).max(Self::MIN_LEVEL);
+        } else if load < 1.0 / 3.0 {
+            self.level = (self.level + 0.002).min(1.0);
+        }
+        self.limits()
+    }
+}
@@ -0,0 +1,91 @@
+//! Scene playlist: which scene plays in which order and for how long.
+//!
+//! Scenes are referred to by index into the renderer's list of initialised
+//! visualizers; names are resolved by the caller.
+
+use crate::anim::Animation;
+
+/// Default time each playlist entry stays on screen.
+pub const DEFAULT_DURATION_MS: f64 = 20_000.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub scene: usize,
+    pub duration_ms: f64,
+    /// Layered over the scene's own animation while this entry plays.
+    pub anim: Animation,
+}
+
+impl Entry {
+    pub fn new(scene: usize, duration_ms: f64) -> Self {
+        Self { scene, duration_ms, anim: Animation::default() }
+    }
+}
+
+/// Ordered, looping list of entries with a cursor.
+#[derive(Clone, Debug, Default)]
+pub struct Playlist {
+    entries: Vec<Entry>,
+    pos: usize,
+}
+
+impl Playlist {
+    pub fn new(entries: Vec<Entry>) -> Self {
+        Self { entries, pos: 0 }
+    }
+
+    /// Every scene `0..scenes` once, in order.
+    pub fn sequential(scenes: usize, duration_ms: f64) -> Self {
+        Self::new((0..scenes).map(|scene| Entry::new(scene, duration_ms)).collect())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Index of the current entry.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn current(&self) -> Option<&Entry> {
+        self.entries.get(self.pos)
+    }
+
+    /// Advance, wrapping to the first entry after the last.
+    pub fn next_entry(&mut self) -> Option<&Entry> {
+        if !self.is_empty() {
+            self.pos = (self.pos + 1) % self.len();
+        }
+        self.current()
+    }
+
+    /// Step back, wrapping to the last entry before the first.
+    pub fn prev_entry(&mut self) -> Option<&Entry> {
+        if !self.is_empty() {
+            self.pos = (self.pos + self.len() - 1) % self.len();
+        }
+        self.current()
+    }
+
+    /// Jump to entry `pos`; out-of-range positions are ignored.
+    pub fn goto(&mut self, pos: usize) -> Option<&Entry> {
+        if pos < self.len() {
+            self.pos = pos;
+        }
+        self.current()
+    }
+
+    /// First entry showing `scene`, if any.
+    pub fn find_scene(&self, scene: usize) -> Option<usize> {
+        self.entries.iter().position(|e| e.scene == scene)
+    }
+}
@@ -0,0 +1,213 @@
+//! Settings that survive a reload, and named presets.
+//!
+//! Both are plain JSON: the web build keeps [`Settings`] in `localStorage` and
+//! exports or imports [`Presets`] as files. Fields missing from stored JSON take
+//! their defaults, so settings written by an older build still load.
+
+use serde::{Deserialize, Serialize};
+
+use crate::motion::MotionPref;
+use crate::pattern::PatternParams;
+use crate::perf::ScaleConfig;
+use crate::query::Fill;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Settings {
+    /// Scene on screen when last saved.
+    pub scene: Option<String>,
+    /// Pattern kept across scene changes; `None` when not locked.
+    pub locked_params: Option<PatternParams>,
+    /// Scene label visible.
+    pub overlay: bool,
+    /// Performance HUD visible.
+    pub hud: bool,
+    pub calibration: Calibration,
+    pub presets: Presets,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            scene: None,
+            locked_params: None,
+            overlay: true,
+            hud: false,
+            calibration: Calibration::default(),
+            presets: Presets::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("settings: {e}"))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// How the picture is fitted to a particular screen or projector.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Calibration {
+    /// Pinch zoom; 2 shows shapes twice as large.
+    pub zoom: f32,
+    /// Rotation in radians, counter-clockwise.
+    pub rotation: f32,
+    /// Render-scale limits; the built-in defaults when `None`.
+    pub render_scale: Option<ScaleConfig>,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self { zoom: 1.0, rotation: 0.0, render_scale: None }
+    }
+}
+
+/// A saved look: scene, pattern and effects, recalled by name or slot number.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Preset {
+    pub name: String,
+    pub scene: Option<String>,
+    pub params: PatternParams,
+    /// Keep `params` when the scene changes after recalling.
+    pub locked: bool,
+    /// Fill the next scenes use; picked at random when `None`.
+    pub fill: Option<Fill>,
+    pub blackout: bool,
+    pub speed: f64,
+    /// Pinch zoom and rotation (radians).
+    pub zoom: f32,
+    pub rotation: f32,
+    /// Flash limiter on.
+    pub safety: bool,
+    pub motion: MotionPref,
+}
+
+impl Default for Preset {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            scene: None,
+            params: PatternParams::default(),
+            locked: false,
+            fill: None,
+            blackout: false,
+            speed: 1.0,
+            zoom: 1.0,
+            rotation: 0.0,
+            safety: false,
+            motion: MotionPref::Auto,
+        }
+    }
+}
+
+/// Highest preset slot number.
+pub const MAX_SLOTS: usize = 99;
+
+/// Presets by 1-based slot, with gaps for empty slots (`null` in JSON); names
+/// are unique.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Presets(Vec<Option<Preset>>);
+
+impl Presets {
+    /// Parse an exported preset file: an array of presets.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("presets: {e}"))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Preset> {
+        self.iter().find(|p| p.name == name)
+    }
+
+    /// Preset in 1-based slot `n`.
+    pub fn slot(&self, n: usize) -> Option<&Preset> {
+        n.checked_sub(1).and_then(|i| self.0.get(i)).and_then(Option::as_ref)
+    }
+
+    /// Store `preset`, replacing the one with the same name; a new name takes
+    /// the first empty slot.
+    pub fn save(&mut self, preset: Preset) {
+        let i = self
+            .position(&preset.name)
+            .or_else(|| self.0.iter().position(Option::is_none))
+            .unwrap_or(self.0.len());
+        self.put(i, preset);
+    }
+
+    /// Store `preset` in 1-based slot `n` (up to [`MAX_SLOTS`]), keeping the
+    /// name of the preset it replaces. An empty slot gets the name "Preset n".
+    /// Returns `false`, storing nothing, for a slot out of range.
+    pub fn save_slot(&mut self, n: usize, mut preset: Preset) -> bool {
+        if !(1..=MAX_SLOTS).contains(&n) {
+            return false;
+        }
+        preset.name = match self.slot(n) {
+            Some(old) => old.name.clone(),
+            None => format!("Preset {n}"),
+        };
+        // A preset elsewhere with that name is replaced, as by `save`
+        if let Some(i) = self.position(&preset.name).filter(|&i| i != n - 1) {
+            self.0[i] = None;
+        }
+        self.put(n - 1, preset);
+        true
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let Some(i) = self.position(name) else { return false };
+        self.0[i] = None;
+        while self.0.last().is_some_and(Option::is_none) {
+            self.0.pop();
+        }
+        true
+    }
+
+    /// Add imported presets; those named like an existing one replace it.
+    pub fn merge(&mut self, other: Presets) {
+        for preset in other.0.into_iter().flatten() {
+            self.save(preset);
+        }
+    }
+
+    /// Names by slot, `None` for an empty one.
+    pub fn slots(&self) -> impl Iterator<Item = Option<&str>> {
+        self.0.iter().map(|p| p.as_ref().map(|p| p.name.as_str()))
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.iter().map(|p| p.name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(Option::is_none)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Preset> {
+        self.0.iter().flatten()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.0.iter().position(|p| p.as_ref().is_some_and(|p| p.name == name))
+    }
+
+    fn put(&mut self, i: usize, preset: Preset) {
+        if i >= self.0.len() {
+            self.0.resize(i + 1, None);
+        }
+        self.0[i] = Some(preset);
+    }
+}
@@ -0,0 +1,78 @@
+//! Snapshot of everything that decides what is on screen, in one value.
+//!
+//! A [`ShowState`] restored into a renderer with the same scenes and playlist
+//! reproduces the exact frame it was taken at, and continues the same sequence
+//! of random patterns when the show is seeded. It serializes to JSON for people
+//! and to a compact binary form for links and remote control.
+
+use serde::{Deserialize, Serialize};
+
+use crate::pattern::PatternParams;
+use crate::query::Fill;
+
+/// First byte of [`ShowState::to_bytes`]; bumped when the layout changes.
+pub const BINARY_VERSION: u8 = 1;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShowState {
+    /// Scene on screen; `None` when nothing is loaded.
+    pub scene: Option<String>,
+    /// Playlist position, for playlists showing a scene more than once.
+    pub entry: usize,
+    pub clock: ClockState,
+    /// State of the seeded pattern generator; `Math.random` when `None`.
+    pub seed: Option<u64>,
+    /// Fill forced on every pattern; random per scene when `None`.
+    pub fill: Option<Fill>,
+    pub params: PatternParams,
+    pub params_locked: bool,
+    pub effects: Effects,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClockState {
+    /// Playback time, the shaders' global `time`, in milliseconds.
+    pub time_ms: f64,
+    /// Time into the current playlist entry.
+    pub scene_ms: f64,
+    pub speed: f64,
+    pub paused: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Effects {
+    pub blackout: bool,
+    pub frozen: bool,
+    /// Pinch zoom and rotation (radians).
+    pub zoom: f32,
+    pub rotation: f32,
+}
+
+impl ShowState {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("show state: {e}"))
+    }
+
+    /// [`BINARY_VERSION`] followed by the fields in declaration order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![BINARY_VERSION];
+        // Only fails for types serde cannot describe, which this is not
+        out.extend(bincode::serialize(self).unwrap_or_default());
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        match bytes.split_first() {
+            Some((&BINARY_VERSION, rest)) => bincode::deserialize(rest).map_err(|e| format!("show state: {e}")),
+            Some((v, _)) => Err(format!("show state: unsupported version {v}")),
+            None => Err("show state: empty".into()),
+        }
+    }
+}
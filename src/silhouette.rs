@@ -0,0 +1,149 @@
+//! Images and SVG paths as scene shapes: where the picture sits in the centered
+//! square `Post` uses, and the jump-flood distance field that gives it the
+//! smooth and flame edges of the SDF scenes.
+//!
+//! [`jump_flood`] does on the CPU what `static/shaders/jfa_*.frag` do on the
+//! GPU, pixel for pixel, so the passes can be tested against brute force.
+
+use std::str::FromStr;
+
+/// Longest side of the rasterized picture, in pixels.
+pub const RASTER_SIZE: u32 = 512;
+/// Distance at either end of the stored field, in texels; farther saturates.
+pub const SDF_RANGE: f32 = 64.0;
+
+/// Placement of the picture in the centered square.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Fit {
+    /// Stretched to the square.
+    Fill,
+    /// Whole picture visible, letterboxed.
+    #[default]
+    Contain,
+    /// Square covered, the longer side cropped.
+    Cover,
+}
+
+impl FromStr for Fit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fill" => Ok(Fit::Fill),
+            "contain" => Ok(Fit::Contain),
+            "cover" => Ok(Fit::Cover),
+            _ => Err(format!("unknown fit \"{s}\" (fill, contain or cover)")),
+        }
+    }
+}
+
+impl Fit {
+    /// Per-axis factor from scene space (`-1..=1` across the square) to
+    /// picture UV: `uv = 0.5 + 0.5 * p * scale`, for a picture `aspect` wide.
+    pub fn scale(self, aspect: f32) -> [f32; 2] {
+        let a = aspect.max(f32::EPSILON);
+        match self {
+            Fit::Fill => [1.0, 1.0],
+            Fit::Contain => [(1.0 / a).max(1.0), a.max(1.0)],
+            Fit::Cover => [(1.0 / a).min(1.0), a.min(1.0)],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SilhouetteSource {
+    /// A PNG (alpha is the shape), SVG file or any other image the browser loads.
+    Image(String),
+    /// SVG path data filled in white, in the coordinates of `view_box`
+    /// (`[x, y, width, height]`).
+    Path { d: String, view_box: [f32; 4] },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SilhouetteConfig {
+    pub source: SilhouetteSource,
+    pub fit: Fit,
+    /// Build a distance field for smooth edges; otherwise alpha is used as is.
+    pub sdf: bool,
+}
+
+impl SilhouetteConfig {
+    pub fn new(source: SilhouetteSource) -> Self {
+        Self { source, fit: Fit::default(), sdf: true }
+    }
+
+    /// Name of the scene in playlists.
+    pub fn scene_name(&self) -> &'static str {
+        "Image"
+    }
+}
+
+/// Size to rasterize a `w` × `h` picture at: [`RASTER_SIZE`] on the long
+/// side. Pictures without a size (some SVGs) are drawn square.
+pub fn raster_size(w: f32, h: f32) -> (u32, u32) {
+    if !(w > 0.0 && h > 0.0) {
+        return (RASTER_SIZE, RASTER_SIZE);
+    }
+    let s = RASTER_SIZE as f32 / w.max(h);
+    (((w * s).round() as u32).max(1), ((h * s).round() as u32).max(1))
+}
+
+/// Step widths of the flood passes over a `w` × `h` field: half the larger
+/// side rounded up to a power of two, down to 1.
+pub fn jump_flood_steps(w: u32, h: u32) -> Vec<u32> {
+    let mut step = (w.max(h).next_power_of_two() / 2).max(1);
+    let mut steps = Vec::new();
+    while step >= 1 {
+        steps.push(step);
+        step /= 2;
+    }
+    steps
+}
+
+/// Signed distance in texels from each pixel to the shape's outline (where
+/// `inside` changes), negative inside, clamped to [`SDF_RANGE`]. Rows in
+/// `inside` are `w` long.
+pub fn jump_flood(inside: &[bool], w: usize, h: usize) -> Vec<f32> {
+    let at = |x: i64, y: i64| x >= 0 && y >= 0 && (x as usize) < w && (y as usize) < h && inside[y as usize * w + x as usize];
+    // Seed pass: outline pixels are their own nearest seed
+    let mut seeds: Vec<Option<(i64, i64)>> = (0..w * h)
+        .map(|i| {
+            let (x, y) = ((i % w) as i64, (i / w) as i64);
+            let here = at(x, y);
+            let edge = [(1, 0), (-1, 0), (0, 1), (0, -1)].iter().any(|(dx, dy)| at(x + dx, y + dy) != here);
+            edge.then_some((x, y))
+        })
+        .collect();
+    let dist = |s: (i64, i64), x: i64, y: i64| (((s.0 - x).pow(2) + (s.1 - y).pow(2)) as f32).sqrt();
+    for step in jump_flood_steps(w as u32, h as u32) {
+        let k = step as i64;
+        let prev = seeds.clone();
+        for (i, seed) in seeds.iter_mut().enumerate() {
+            let (x, y) = ((i % w) as i64, (i / w) as i64);
+            let mut best: Option<((i64, i64), f32)> = None;
+            for dy in [-k, 0, k] {
+                for dx in [-k, 0, k] {
+                    let (qx, qy) = (x + dx, y + dy);
+                    if qx < 0 || qy < 0 || qx >= w as i64 || qy >= h as i64 {
+                        continue;
+                    }
+                    if let Some(s) = prev[qy as usize * w + qx as usize] {
+                        let d = dist(s, x, y);
+                        if best.is_none_or(|(_, bd)| d < bd) {
+                            best = Some((s, d));
+                        }
+                    }
+                }
+            }
+            *seed = best.map(|(s, _)| s);
+        }
+    }
+    // The outline runs half a texel beyond the seed pixels
+    (0..w * h)
+        .map(|i| {
+            let (x, y) = ((i % w) as i64, (i / w) as i64);
+            let d = seeds[i].map_or(SDF_RANGE, |s| (dist(s, x, y) + 0.5).min(SDF_RANGE));
+            if at(x, y) { -d } else { d }
+        })
+        .collect()
+}
@@ -0,0 +1,182 @@
+//! Lockstep playback across several browsers, relayed by the host binary.
+//!
+//! Every page measures its offset to the host clock NTP-style (`GET /time`
+//! round trips, keeping the fastest). The leader posts its [`ShowState`]
+//! stamped with host time whenever the show changes (`POST /state`);
+//! followers poll `GET /state?after=<seq>`, restore the newest state and then
+//! derive their playback time from it and the host clock every frame. Scene
+//! changes and pattern rolls follow from the shared clock, playlist and seed,
+//! so displays switch on the same frame without waiting for the network.
+//!
+//! Transport-free: the wasm side does the fetching, `main.rs` the serving.
+
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::show::{ClockState, ShowState};
+
+/// Port the host binary relays on.
+pub const SYNC_PORT: u16 = 8001;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Leader,
+    Follower,
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "leader" => Ok(Role::Leader),
+            "follower" => Ok(Role::Follower),
+            _ => Err(format!("unknown sync role \"{s}\" (leader or follower)")),
+        }
+    }
+}
+
+/// Offset between the local clock and the host clock from request round trips.
+#[derive(Clone, Debug, Default)]
+pub struct ClockSync {
+    // (round trip, offset) of the latest samples
+    samples: VecDeque<(f64, f64)>,
+}
+
+impl ClockSync {
+    /// Samples kept; the one with the shortest round trip wins.
+    pub const KEEP: usize = 8;
+
+    /// A request sent at local `sent_ms` was answered with `host_ms` and
+    /// arrived at local `received_ms`.
+    pub fn sample(&mut self, sent_ms: f64, host_ms: f64, received_ms: f64) {
+        let rtt = received_ms - sent_ms;
+        if !(rtt >= 0.0 && host_ms.is_finite()) {
+            return;
+        }
+        // The host stamped its reply halfway through the round trip
+        self.samples.push_back((rtt, host_ms - (sent_ms + received_ms) / 2.0));
+        if self.samples.len() > Self::KEEP {
+            self.samples.pop_front();
+        }
+    }
+
+    fn best(&self) -> Option<(f64, f64)> {
+        self.samples.iter().copied().min_by(|a, b| a.0.total_cmp(&b.0))
+    }
+
+    /// Host time minus local time; `None` before the first sample.
+    pub fn offset(&self) -> Option<f64> {
+        self.best().map(|(_, offset)| offset)
+    }
+
+    /// Round trip of the sample in use; the offset is good to half of it.
+    pub fn rtt(&self) -> Option<f64> {
+        self.best().map(|(rtt, _)| rtt)
+    }
+
+    pub fn host_now(&self, local_ms: f64) -> Option<f64> {
+        self.offset().map(|o| local_ms + o)
+    }
+}
+
+/// The leader's show as of host time `host_ms`, numbered by the relay.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncState {
+    /// Assigned by the relay; increases with every post.
+    #[serde(default)]
+    pub seq: u64,
+    pub host_ms: f64,
+    pub show: ShowState,
+}
+
+impl SyncState {
+    /// The leader's playback time at host time `host_now`.
+    pub fn playback_ms(&self, host_now: f64) -> f64 {
+        let c = &self.show.clock;
+        if c.paused { c.time_ms } else { c.time_ms + (host_now - self.host_ms).max(0.0) * c.speed }
+    }
+
+    /// The show with its clock moved on to host time `host_now`.
+    pub fn at(&self, host_now: f64) -> ShowState {
+        let time_ms = self.playback_ms(host_now);
+        let c = &self.show.clock;
+        ShowState {
+            clock: ClockState { time_ms, scene_ms: c.scene_ms + (time_ms - c.time_ms), ..*c },
+            ..self.show.clone()
+        }
+    }
+}
+
+/// Whether `a` and `b` differ only in how far their clocks have run.
+pub fn same_show(a: &ShowState, b: &ShowState) -> bool {
+    let still = |s: &ShowState| ShowState {
+        clock: ClockState { time_ms: 0.0, scene_ms: 0.0, ..s.clock },
+        ..s.clone()
+    };
+    still(a) == still(b)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeReply {
+    pub host_ms: f64,
+}
+
+/// An HTTP response of the relay; the body is JSON.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reply {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Reply {
+    fn json(body: String) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, msg: &str) -> Self {
+        Self { status, body: serde_json::json!({ "error": msg }).to_string() }
+    }
+}
+
+/// The host's side of the protocol: hands out its clock and keeps the latest
+/// leader state.
+#[derive(Clone, Debug, Default)]
+pub struct Relay {
+    latest: Option<SyncState>,
+}
+
+impl Relay {
+    /// Answer one request at host time `host_ms`.
+    pub fn handle(&mut self, method: &str, path: &str, body: &str, host_ms: f64) -> Reply {
+        let (route, query) = path.split_once('?').unwrap_or((path, ""));
+        match (method, route) {
+            ("GET", "/time") => Reply::json(serde_json::to_string(&TimeReply { host_ms }).unwrap_or_default()),
+            ("GET", "/state") => {
+                let after = query
+                    .split('&')
+                    .find_map(|kv| kv.strip_prefix("after="))
+                    .and_then(|n| n.parse::<u64>().ok())
+                    .unwrap_or(0);
+                match &self.latest {
+                    Some(s) if s.seq > after => Reply::json(serde_json::to_string(s).unwrap_or_default()),
+                    _ => Reply { status: 204, body: String::new() },
+                }
+            }
+            ("POST", "/state") => match serde_json::from_str::<SyncState>(body) {
+                Ok(mut state) => {
+                    state.seq = self.latest.as_ref().map_or(1, |s| s.seq + 1);
+                    let seq = state.seq;
+                    self.latest = Some(state);
+                    Reply::json(serde_json::json!({ "seq": seq }).to_string())
+                }
+                Err(e) => Reply::error(400, &e.to_string()),
+            },
+            _ => Reply::error(404, "not found"),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Lettering as a scene shape: a message (event name, countdown, social
+//! handle, one per line) laid out in glyph quads over the font atlas build.rs
+//! generates (see [`crate::font`]), so the fill and flame edges apply to it
+//! like to any other shape.
+
+use crate::font::Atlas;
+
+/// Replaced by the time left until [`TextConfig::countdown_to`].
+pub const COUNTDOWN: &str = "{countdown}";
+/// Fraction of the centered square the text block may fill.
+pub const MARGIN: f32 = 0.9;
+
+/// What the "Text" scene spells out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextConfig {
+    /// Lines separated by `\n`; [`COUNTDOWN`] is filled in every frame.
+    pub text: String,
+    /// Target of the countdown, in ms since the Unix epoch.
+    pub countdown_to: Option<f64>,
+}
+
+impl TextConfig {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into(), countdown_to: None }
+    }
+
+    /// Name of the scene in playlists.
+    pub fn scene_name(&self) -> &'static str {
+        "Text"
+    }
+
+    /// The message at wall-clock time `now_ms`. Without a target the
+    /// countdown reads zero.
+    pub fn message(&self, now_ms: f64) -> String {
+        if !self.text.contains(COUNTDOWN) {
+            return self.text.clone();
+        }
+        let left = self.countdown_to.map_or(0.0, |t| t - now_ms);
+        self.text.replace(COUNTDOWN, &format_countdown(left))
+    }
+}
+
+/// `ms` left as `mm:ss`, `h:mm:ss` from an hour and `Nd hh:mm:ss` from a
+/// day, rounded up to the second and stopping at `00:00`.
+pub fn format_countdown(ms: f64) -> String {
+    let total = if ms > 0.0 { (ms / 1000.0).ceil() as u64 } else { 0 };
+    let (days, h, m, s) = (total / 86_400, total / 3600 % 24, total / 60 % 60, total % 60);
+    match (days, h) {
+        (0, 0) => format!("{m:02}:{s:02}"),
+        (0, h) => format!("{h}:{m:02}:{s:02}"),
+        (d, h) => format!("{d}d {h:02}:{m:02}:{s:02}"),
+    }
+}
+
+/// One glyph: corners in scene space (`[x0, y0, x1, y1]`, y up) and the
+/// matching atlas UVs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quad {
+    pub pos: [f32; 4],
+    pub uv: [f32; 4],
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Layout {
+    pub quads: Vec<Quad>,
+    /// Scene units per atlas pixel, to turn field distances into scene ones.
+    pub px: f32,
+}
+
+/// Lay `text` out centered in the square, every line centered and the block
+/// scaled to fit [`MARGIN`] of it. Characters missing from the atlas show as
+/// `?`, other whitespace as spaces.
+pub fn layout(atlas: &Atlas, text: &str) -> Layout {
+    let glyph = |c: char| {
+        let c = if c.is_whitespace() { ' ' } else { c };
+        atlas.glyph(c).or_else(|| atlas.glyph('?'))
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.is_empty() {
+        return Layout::default();
+    }
+    let widths: Vec<f32> = lines.iter().map(|l| l.chars().filter_map(glyph).map(|g| g.advance).sum()).collect();
+    let line_height = atlas.ascender - atlas.descender;
+    let block = lines.len() as f32 * line_height + (lines.len() - 1) as f32 * atlas.line_gap;
+    let widest = widths.iter().cloned().fold(0.0, f32::max);
+    // Scene units per em
+    let s = (2.0 * MARGIN / widest.max(f32::EPSILON)).min(2.0 * MARGIN / block);
+
+    let (aw, ah) = (atlas.width as f32, atlas.height as f32);
+    let mut quads = Vec::new();
+    let mut baseline = s * (block / 2.0 - atlas.ascender);
+    for (line, width) in lines.iter().zip(&widths) {
+        let mut x = -s * width / 2.0;
+        for g in line.chars().filter_map(glyph) {
+            let [l, b, r, t] = g.bounds;
+            let [gx, gy, gw, gh] = g.rect;
+            if gw > 0 {
+                quads.push(Quad {
+                    pos: [x + l * s, baseline + b * s, x + r * s, baseline + t * s],
+                    uv: [gx as f32 / aw, gy as f32 / ah, (gx + gw) as f32 / aw, (gy + gh) as f32 / ah],
+                });
+            }
+            x += g.advance * s;
+        }
+        baseline -= s * (line_height + atlas.line_gap);
+    }
+    Layout { quads, px: s / atlas.em_px }
+}
+
+/// The atlas build.rs generated from `fonts/` (or `$VIZ_FONT`).
+pub fn embedded_atlas() -> Atlas {
+    bincode::deserialize(include_bytes!(concat!(env!("OUT_DIR"), "/font_atlas.bin")))
+        .expect("font atlas written by build.rs")
+}
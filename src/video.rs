@@ -0,0 +1,155 @@
+//! Video files and webcams as a scene: each frame is keyed into the mask the
+//! post pass fills with the pattern, while the frame itself is the source its
+//! flame edges are traced on.
+//!
+//! [`MaskMode::mask`] computes what `static/shaders/video.frag` does per pixel,
+//! so the keying can be checked without a GPU.
+
+use std::str::FromStr;
+
+/// Width of the soft band around every threshold, on `0..=1` values.
+pub const SOFTNESS: f32 = 0.05;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum VideoSource {
+    /// The first camera `getUserMedia` offers.
+    Webcam,
+    /// A video file, played muted in a loop.
+    Url(String),
+}
+
+impl FromStr for VideoSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => Err("empty video source".into()),
+            "webcam" => Ok(VideoSource::Webcam),
+            url => Ok(VideoSource::Url(url.to_string())),
+        }
+    }
+}
+
+/// How a frame becomes the mask; everything is on `0..=1` values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MaskMode {
+    /// Pixels brighter than `threshold`.
+    Luminance { threshold: f32 },
+    /// Everything except colours within `tolerance` of `key` (green screen).
+    ChromaKey { key: [f32; 3], tolerance: f32 },
+    /// Pixels whose brightness changed by more than `threshold` since the
+    /// previous frame.
+    Difference { threshold: f32 },
+}
+
+impl Default for MaskMode {
+    fn default() -> Self {
+        MaskMode::Luminance { threshold: 0.5 }
+    }
+}
+
+impl FromStr for MaskMode {
+    type Err = String;
+
+    /// Mode names with their default settings.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "luminance" | "luma" => Ok(MaskMode::default()),
+            "chroma" | "chroma-key" => Ok(MaskMode::ChromaKey { key: [0.0, 1.0, 0.0], tolerance: 0.25 }),
+            "difference" | "motion" => Ok(MaskMode::Difference { threshold: 0.08 }),
+            _ => Err(format!("unknown mask mode \"{s}\" (luminance, chroma or difference)")),
+        }
+    }
+}
+
+impl MaskMode {
+    /// Selector passed to the shader as `u_mask_mode`.
+    pub fn id(&self) -> i32 {
+        match self {
+            MaskMode::Luminance { .. } => 0,
+            MaskMode::ChromaKey { .. } => 1,
+            MaskMode::Difference { .. } => 2,
+        }
+    }
+
+    /// The threshold or tolerance.
+    pub fn threshold(&self) -> f32 {
+        match *self {
+            MaskMode::Luminance { threshold } | MaskMode::Difference { threshold } => threshold,
+            MaskMode::ChromaKey { tolerance, .. } => tolerance,
+        }
+    }
+
+    /// Same mode with another threshold or tolerance.
+    pub fn with_threshold(self, t: f32) -> Self {
+        match self {
+            MaskMode::Luminance { .. } => MaskMode::Luminance { threshold: t },
+            MaskMode::ChromaKey { key, .. } => MaskMode::ChromaKey { key, tolerance: t },
+            MaskMode::Difference { .. } => MaskMode::Difference { threshold: t },
+        }
+    }
+
+    /// Mask value of a pixel, given the same pixel in the previous frame.
+    pub fn mask(&self, rgb: [f32; 3], prev: [f32; 3]) -> f32 {
+        match *self {
+            MaskMode::Luminance { threshold } => smoothstep(threshold - SOFTNESS, threshold + SOFTNESS, luma(rgb)),
+            MaskMode::ChromaKey { key, tolerance } => {
+                let ([cb, cr], [kb, kr]) = (chroma(rgb), chroma(key));
+                smoothstep(tolerance, tolerance + SOFTNESS, ((cb - kb).powi(2) + (cr - kr).powi(2)).sqrt())
+            }
+            MaskMode::Difference { threshold } => {
+                smoothstep(threshold - SOFTNESS, threshold + SOFTNESS, (luma(rgb) - luma(prev)).abs())
+            }
+        }
+    }
+}
+
+/// Which video and how to key it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VideoConfig {
+    pub source: VideoSource,
+    pub mask: MaskMode,
+    /// Flip horizontally, as a mirror; on by default for webcams.
+    pub mirror: bool,
+}
+
+impl VideoConfig {
+    pub fn new(source: VideoSource) -> Self {
+        let mirror = source == VideoSource::Webcam;
+        Self { source, mask: MaskMode::default(), mirror }
+    }
+
+    /// Name of the scene in playlists.
+    pub fn scene_name(&self) -> &'static str {
+        match self.source {
+            VideoSource::Webcam => "Webcam",
+            VideoSource::Url(_) => "Video",
+        }
+    }
+}
+
+/// `#rrggbb` (the `#` is optional) as `0..=1` channels.
+pub fn parse_hex_color(s: &str) -> Option<[f32; 3]> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok().map(|c| c as f32 / 255.0);
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+// Rec. 709 luminance, as `luma` in `common/color.glsl`.
+fn luma([r, g, b]: [f32; 3]) -> f32 {
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+// Rec. 709 blue- and red-difference chroma.
+fn chroma(rgb: [f32; 3]) -> [f32; 2] {
+    let y = luma(rgb);
+    [(rgb[2] - y) / 1.8556, (rgb[0] - y) / 1.5748]
+}
+
+fn smoothstep(e0: f32, e1: f32, x: f32) -> f32 {
+    let t = ((x - e0) / (e1 - e0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
@@ -0,0 +1,89 @@
+#![cfg(target_arch = "wasm32")]
+
+//! Error type for everything the module can fail at. Crossing into JS it becomes
+//! an `Error` whose `code` property tells embedding pages what went wrong
+//! without matching on message text.
+
+use std::fmt;
+
+use wasm_bindgen::{JsCast, JsValue};
+
+#[derive(Debug)]
+pub enum VizError {
+    /// A required DOM object is missing: `window`, `document` or an element id.
+    MissingElement(String),
+    /// The canvas cannot provide a context of this kind, e.g. `webgl2`.
+    ContextUnavailable(String),
+    /// A shader failed to preprocess or compile; locations in `log` refer to the
+    /// original files under `static/shaders/`.
+    ShaderCompile { file: String, log: String },
+    /// Linking `program` (`"vert + frag"`) failed.
+    Link { program: String, log: String },
+    /// A `create_*` call returned null, typically because the context was lost.
+    Resource(&'static str),
+    /// An option passed to `new Viz(canvas, options)` is unknown or malformed.
+    InvalidOption(String),
+    /// No scene with this name is loaded (or it is not in the playlist).
+    UnknownScene(String),
+    /// No preset with this name has been saved.
+    UnknownPreset(String),
+    /// Any other exception thrown by a browser API.
+    Js(String),
+}
+
+impl VizError {
+    /// Stable identifier exposed to JS as `error.code`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::MissingElement(_) => "MISSING_ELEMENT",
+            Self::ContextUnavailable(_) => "CONTEXT_UNAVAILABLE",
+            Self::ShaderCompile { .. } => "SHADER_COMPILE",
+            Self::Link { .. } => "LINK_FAILED",
+            Self::Resource(_) => "RESOURCE_ALLOCATION",
+            Self::InvalidOption(_) => "INVALID_OPTION",
+            Self::UnknownScene(_) => "UNKNOWN_SCENE",
+            Self::UnknownPreset(_) => "UNKNOWN_PRESET",
+            Self::Js(_) => "JS_EXCEPTION",
+        }
+    }
+}
+
+impl fmt::Display for VizError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingElement(what) => write!(f, "missing {what}"),
+            Self::ContextUnavailable(kind) => write!(f, "{kind} context not available"),
+            Self::ShaderCompile { file, log } => write!(f, "{file}: {log}"),
+            Self::Link { program, log } => write!(f, "{program}: link failed: {log}"),
+            Self::Resource(what) => write!(f, "could not create {what}"),
+            Self::InvalidOption(msg) => write!(f, "invalid option: {msg}"),
+            Self::UnknownScene(name) => write!(f, "unknown scene \"{name}\""),
+            Self::UnknownPreset(name) => write!(f, "unknown preset \"{name}\""),
+            Self::Js(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for VizError {}
+
+impl From<JsValue> for VizError {
+    fn from(v: JsValue) -> Self {
+        let msg = v
+            .as_string()
+            .or_else(|| v.dyn_ref::<js_sys::Error>().map(|e| String::from(e.message())))
+            .unwrap_or_else(|| format!("{v:?}"));
+        Self::Js(msg)
+    }
+}
+
+impl From<VizError> for JsValue {
+    fn from(e: VizError) -> Self {
+        let err = js_sys::Error::new(&e.to_string());
+        err.set_name("VizError");
+        let _ = js_sys::Reflect::set(&err, &"code".into(), &e.code().into());
+        if let VizError::ShaderCompile { file, .. } = &e {
+            let _ = js_sys::Reflect::set(&err, &"file".into(), &file.into());
+        }
+        err.into()
+    }
+}
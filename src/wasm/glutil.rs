@@ -0,0 +1,235 @@
+#![cfg(target_arch = "wasm32")]
+
+//! Shared WebGL helpers: shader compilation, program linking, cached uniform
+//! locations, the per-frame `Globals` uniform buffer and a count of live GL
+//! objects for leak checks.
+
+use std::cell::Cell;
+
+use wasm_bindgen::JsValue;
+
+use super::error::VizError;
+use web_sys::{
+    WebGl2RenderingContext as GL, WebGlBuffer, WebGlFramebuffer, WebGlProgram, WebGlQuery, WebGlShader, WebGlSync,
+    WebGlTexture, WebGlTransformFeedback,
+};
+
+pub use crate::glsl::GLOBALS_GLSL;
+
+thread_local! {
+    // GL objects created through `track` and not yet passed to `delete`.
+    static LIVE_OBJECTS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Count an object returned by a `gl.create_*` call; passes the result through.
+pub fn track<T: GlObject>(obj: Option<T>) -> Option<T> {
+    if obj.is_some() {
+        LIVE_OBJECTS.with(|n| n.set(n.get() + 1));
+    }
+    obj
+}
+
+/// Delete an object obtained through [`track`].
+pub fn delete<T: GlObject + ?Sized>(gl: &GL, obj: &T) {
+    obj.delete_from(gl);
+    LIVE_OBJECTS.with(|n| n.set(n.get().saturating_sub(1)));
+}
+
+/// Objects a constructor has created so far, deleted again when it is dropped
+/// before [`done`](Self::done): an early `?` then leaks nothing.
+pub struct Pending<'a> {
+    gl: &'a GL,
+    objects: Vec<Box<dyn GlObject>>,
+}
+
+impl<'a> Pending<'a> {
+    pub fn new(gl: &'a GL) -> Self {
+        Self { gl, objects: Vec::new() }
+    }
+
+    /// Hold on to `obj` until [`done`](Self::done); passes it through.
+    pub fn add<T: GlObject + Clone + 'static>(&mut self, obj: T) -> T {
+        self.objects.push(Box::new(obj.clone()));
+        obj
+    }
+
+    /// Construction succeeded; the objects now belong to the caller.
+    pub fn done(mut self) {
+        self.objects.clear();
+    }
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        for obj in self.objects.drain(..).rev() {
+            delete(self.gl, &*obj);
+        }
+    }
+}
+
+/// GL objects created and not yet deleted, over all contexts. Zero once every
+/// renderer has been destroyed.
+pub fn live_objects() -> usize {
+    LIVE_OBJECTS.with(|n| n.get())
+}
+
+/// A WebGL object type with a matching `gl.delete_*` call.
+pub trait GlObject {
+    fn delete_from(&self, gl: &GL);
+}
+
+macro_rules! gl_object {
+    ($($ty:ty => $delete:ident),* $(,)?) => {
+        $(impl GlObject for $ty {
+            fn delete_from(&self, gl: &GL) {
+                gl.$delete(Some(self));
+            }
+        })*
+    };
+}
+
+gl_object! {
+    WebGlBuffer => delete_buffer,
+    WebGlTexture => delete_texture,
+    WebGlFramebuffer => delete_framebuffer,
+    WebGlProgram => delete_program,
+    WebGlShader => delete_shader,
+    WebGlQuery => delete_query,
+    WebGlTransformFeedback => delete_transform_feedback,
+    WebGlSync => delete_sync,
+}
+
+/// Uniform block binding point shared by every program for the `Globals` block.
+pub const GLOBALS_BINDING: u32 = 0;
+
+/// CPU-side copy of the `Globals` uniform block ([`GLOBALS_GLSL`]).
+#[derive(Clone, Copy)]
+pub struct Globals {
+    pub resolution: [f32; 2],
+    pub time: f32,
+    pub scene_time: f32,
+    pub beat: f32,
+    /// Highest rate shaders may flicker or blink at, in Hz.
+    pub max_flash_hz: f32,
+    pub bands: [f32; 4],
+}
+
+impl Default for Globals {
+    fn default() -> Self {
+        Self {
+            resolution: [0.0; 2],
+            time: 0.0,
+            scene_time: 0.0,
+            beat: 0.0,
+            max_flash_hz: crate::safety::UNLIMITED_FLASH_HZ,
+            bands: [0.0; 4],
+        }
+    }
+}
+
+impl Globals {
+    /// std140 layout: vec2 @0, float @8, float @12, float @16, float @20, vec4 @32.
+    fn pack(&self) -> [f32; 12] {
+        let [rx, ry] = self.resolution;
+        let [b0, b1, b2, b3] = self.bands;
+        [rx, ry, self.time, self.scene_time, self.beat, self.max_flash_hz, 0.0, 0.0, b0, b1, b2, b3]
+    }
+}
+
+/// Uniform buffer holding [`Globals`], bound once to [`GLOBALS_BINDING`].
+pub struct GlobalsUbo {
+    buf: WebGlBuffer,
+}
+
+impl GlobalsUbo {
+    pub fn new(gl: &GL) -> Result<Self, VizError> {
+        let buf = track(gl.create_buffer()).ok_or(VizError::Resource("globals uniform buffer"))?;
+        gl.bind_buffer(GL::UNIFORM_BUFFER, Some(&buf));
+        gl.buffer_data_with_i32(GL::UNIFORM_BUFFER, 12 * 4, GL::DYNAMIC_DRAW);
+        gl.bind_buffer(GL::UNIFORM_BUFFER, None);
+        gl.bind_buffer_base(GL::UNIFORM_BUFFER, GLOBALS_BINDING, Some(&buf));
+        Ok(Self { buf })
+    }
+
+    pub fn delete(&self, gl: &GL) {
+        delete(gl, &self.buf);
+    }
+
+    pub fn upload(&self, gl: &GL, g: &Globals) {
+        let data = g.pack();
+        gl.bind_buffer(GL::UNIFORM_BUFFER, Some(&self.buf));
+        unsafe {
+            let fa = js_sys::Float32Array::view(&data);
+            gl.buffer_sub_data_with_i32_and_array_buffer_view(GL::UNIFORM_BUFFER, 0, &fa);
+        }
+        gl.bind_buffer(GL::UNIFORM_BUFFER, None);
+    }
+}
+
+/// Declare a struct of cached uniform locations, resolved once after linking.
+///
+/// ```ignore
+/// uniforms! { struct SdfUniforms { u_scale, u_rot } }
+/// let u = SdfUniforms::locate(&gl, &prog);
+/// gl.uniform1f(u.u_scale.as_ref(), 1.0);
+/// ```
+macro_rules! uniforms {
+    ($vis:vis struct $name:ident { $($field:ident),* $(,)? }) => {
+        $vis struct $name {
+            $(pub $field: Option<web_sys::WebGlUniformLocation>,)*
+        }
+        impl $name {
+            pub fn locate(gl: &web_sys::WebGl2RenderingContext, prog: &web_sys::WebGlProgram) -> Self {
+                Self { $($field: gl.get_uniform_location(prog, stringify!($field)),)* }
+            }
+        }
+    };
+}
+pub(crate) use uniforms;
+
+/// Compile one stage. On failure the error carries the raw info log under `name`.
+pub fn compile_shader(gl: &GL, name: &str, src: &str, shader_type: u32) -> Result<WebGlShader, VizError> {
+    let shader = track(gl.create_shader(shader_type))
+        .ok_or(VizError::Resource("shader"))?;
+    gl.shader_source(&shader, src);
+    gl.compile_shader(&shader);
+    if !gl
+        .get_shader_parameter(&shader, GL::COMPILE_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        let log = gl.get_shader_info_log(&shader).unwrap_or_default();
+        delete(gl, &shader);
+        return Err(VizError::ShaderCompile { file: name.to_string(), log });
+    }
+    Ok(shader)
+}
+
+/// Link compiled shaders into a program, capturing the `feedback` varyings
+/// (interleaved) by transform feedback. If it declares the `Globals` block, the
+/// block is bound to [`GLOBALS_BINDING`] so the shared UBO feeds it without
+/// per-frame calls.
+pub fn link_program(gl: &GL, name: &str, vert: &WebGlShader, frag: &WebGlShader, feedback: &[&str]) -> Result<WebGlProgram, VizError> {
+    let prog = track(gl.create_program()).ok_or(VizError::Resource("program"))?;
+    gl.attach_shader(&prog, vert);
+    gl.attach_shader(&prog, frag);
+    if !feedback.is_empty() {
+        let names: js_sys::Array = feedback.iter().map(|n| JsValue::from_str(n)).collect();
+        gl.transform_feedback_varyings(&prog, &names, GL::INTERLEAVED_ATTRIBS);
+    }
+    gl.link_program(&prog);
+    if !gl
+        .get_program_parameter(&prog, GL::LINK_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        let log = gl.get_program_info_log(&prog).unwrap_or_default();
+        delete(gl, &prog);
+        return Err(VizError::Link { program: name.to_string(), log });
+    }
+    let block = gl.get_uniform_block_index(&prog, "Globals");
+    if block != GL::INVALID_INDEX {
+        gl.uniform_block_binding(&prog, block, GLOBALS_BINDING);
+    }
+    Ok(prog)
+}
@@ -0,0 +1,279 @@
+#![cfg(target_arch = "wasm32")]
+
+//! Performance HUD: per-pass timings (GPU timer queries when the driver exposes
+//! `EXT_disjoint_timer_query_webgl2`, CPU submit time otherwise), FPS and a
+//! rolling frame-time graph drawn on a small 2D canvas over the visuals.
+
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{window, CanvasRenderingContext2d, HtmlCanvasElement, WebGl2RenderingContext as GL, WebGlQuery};
+
+use super::glutil::{delete, track};
+use crate::perf::{smooth, FrameHistory, FrameStats};
+
+// Constants from EXT_disjoint_timer_query_webgl2 (not exposed by web-sys).
+const TIME_ELAPSED_EXT: u32 = 0x88BF;
+const GPU_DISJOINT_EXT: u32 = 0x8FBB;
+
+/// Number of frames kept for the HUD graph and `frame_stats().history`.
+const HISTORY_LEN: usize = 120;
+
+/// Render passes timed individually. The mask is written together with the
+/// scene color (MRT), so it has no pass of its own.
+#[derive(Clone, Copy)]
+pub enum Pass {
+    Scene = 0,
+    Post = 1,
+}
+
+/// Times render passes, preferring asynchronous GPU timer queries.
+pub struct PassTimer {
+    gpu: bool,
+    active: Option<(Pass, WebGlQuery)>,
+    pending: Vec<(Pass, WebGlQuery)>,
+    free: Vec<WebGlQuery>,
+    cpu_start: f64,
+    ms: [f64; 2],
+}
+
+impl PassTimer {
+    /// Results that never become available are dropped beyond this many in flight.
+    const MAX_PENDING: usize = 16;
+
+    pub fn new(gl: &GL) -> Self {
+        let gpu = gl
+            .get_extension("EXT_disjoint_timer_query_webgl2")
+            .ok()
+            .flatten()
+            .is_some();
+        Self { gpu, active: None, pending: Vec::new(), free: Vec::new(), cpu_start: 0.0, ms: [0.0; 2] }
+    }
+
+    pub fn uses_gpu(&self) -> bool {
+        self.gpu
+    }
+
+    /// Smoothed duration of `pass` in milliseconds.
+    pub fn ms(&self, pass: Pass) -> f64 {
+        self.ms[pass as usize]
+    }
+
+    pub fn begin(&mut self, gl: &GL, pass: Pass) {
+        if self.gpu {
+            if let Some(q) = self.free.pop().or_else(|| track(gl.create_query())) {
+                gl.begin_query(TIME_ELAPSED_EXT, &q);
+                self.active = Some((pass, q));
+            }
+        } else {
+            self.cpu_start = now_ms();
+        }
+    }
+
+    pub fn end(&mut self, gl: &GL, pass: Pass) {
+        if self.gpu {
+            if let Some(active) = self.active.take() {
+                gl.end_query(TIME_ELAPSED_EXT);
+                self.pending.push(active);
+            }
+        } else {
+            let dt = now_ms() - self.cpu_start;
+            self.ms[pass as usize] = smooth(self.ms[pass as usize], dt);
+        }
+    }
+
+    /// Collect finished GPU queries, in submission order. Results are discarded
+    /// when the GPU reports a disjoint event (clock change, context switch).
+    pub fn poll(&mut self, gl: &GL) {
+        if !self.gpu {
+            return;
+        }
+        let disjoint = gl
+            .get_parameter(GPU_DISJOINT_EXT)
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let mut done = 0;
+        for (pass, q) in &self.pending {
+            let ready = gl
+                .get_query_parameter(q, GL::QUERY_RESULT_AVAILABLE)
+                .as_bool()
+                .unwrap_or(false);
+            if !ready {
+                break;
+            }
+            if !disjoint {
+                let ns = gl.get_query_parameter(q, GL::QUERY_RESULT).as_f64().unwrap_or(0.0);
+                let i = *pass as usize;
+                self.ms[i] = smooth(self.ms[i], ns / 1.0e6);
+            }
+            done += 1;
+        }
+        if self.pending.len() - done > Self::MAX_PENDING {
+            done = self.pending.len() - Self::MAX_PENDING;
+        }
+        self.free.extend(self.pending.drain(..done).map(|(_, q)| q));
+    }
+
+    /// Delete every query object, in flight or pooled.
+    pub fn delete(&mut self, gl: &GL) {
+        if let Some((_, q)) = self.active.take() {
+            gl.end_query(TIME_ELAPSED_EXT);
+            delete(gl, &q);
+        }
+        for q in self.pending.drain(..).map(|(_, q)| q).chain(self.free.drain(..)) {
+            delete(gl, &q);
+        }
+    }
+}
+
+fn now_ms() -> f64 {
+    window().and_then(|w| w.performance()).map(|p| p.now()).unwrap_or(0.0)
+}
+
+/// One renderer's frame numbers and the HUD canvas showing them, pinned to
+/// the top-right corner of the renderer's canvas.
+pub struct Hud {
+    visible: bool,
+    // Created the first time the HUD is shown
+    view: Option<HudCanvas>,
+    stats: FrameStats,
+    history: FrameHistory,
+}
+
+impl Hud {
+    pub fn new(visible: bool) -> Self {
+        let mut hud = Self { visible: false, view: None, stats: FrameStats::default(), history: FrameHistory::new(HISTORY_LEN) };
+        hud.set_visible(visible);
+        hud
+    }
+
+    /// Record one frame's numbers; redraws the HUD over `anchor` when visible.
+    pub fn record_frame(&mut self, dt_ms: Option<f64>, stats: FrameStats, anchor: &HtmlCanvasElement) {
+        self.stats = stats;
+        if let Some(dt) = dt_ms {
+            self.history.push(dt);
+        }
+        if let (true, Some(view)) = (self.visible, self.view.as_ref()) {
+            view.place(anchor);
+            view.draw(&self.stats, &self.history);
+        }
+    }
+
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+        if visible && self.view.is_none() {
+            self.view = HudCanvas::new();
+        }
+        if let Some(view) = self.view.as_ref() {
+            let _ = view.canvas.style().set_property("display", if visible { "block" } else { "none" });
+        }
+    }
+
+    /// Take the HUD canvas off the page.
+    pub fn remove(&mut self) {
+        if let Some(view) = self.view.take() {
+            view.canvas.remove();
+        }
+        self.visible = false;
+    }
+
+    /// Latest stats as a plain JS object, for automated/headless runs to log.
+    pub fn stats_js(&self) -> JsValue {
+        let (st, hist) = (&self.stats, &self.history);
+        let obj = js_sys::Object::new();
+        let set = |k: &str, v: JsValue| {
+            let _ = js_sys::Reflect::set(&obj, &JsValue::from_str(k), &v);
+        };
+        set("fps", st.fps.into());
+        set("frameMs", st.frame_ms.into());
+        set("p99Ms", hist.percentile(0.99).into());
+        set("scale", st.scale.into());
+        set("sceneMs", st.scene_ms.into());
+        set("postMs", st.post_ms.into());
+        set("gpuTiming", st.gpu_timing.into());
+        let arr: js_sys::Array = hist.iter().map(JsValue::from).collect();
+        set("history", arr.into());
+        obj.into()
+    }
+}
+
+struct HudCanvas {
+    canvas: HtmlCanvasElement,
+    ctx: CanvasRenderingContext2d,
+}
+
+impl HudCanvas {
+    const W: u32 = 260;
+    const H: u32 = 130;
+    /// Graph ceiling in ms; taller frames are clipped.
+    const GRAPH_MAX_MS: f32 = 50.0;
+    /// Gap to the corner of the canvas, in CSS pixels.
+    const INSET: f64 = 8.0;
+
+    fn new() -> Option<Self> {
+        let document = window()?.document()?;
+        let canvas: HtmlCanvasElement = document.create_element("canvas").ok()?.dyn_into().ok()?;
+        canvas.set_class_name("viz-hud");
+        canvas.set_width(Self::W);
+        canvas.set_height(Self::H);
+        let style = canvas.style();
+        for (k, v) in [
+            ("position", "fixed"),
+            ("pointer-events", "none"),
+            ("z-index", "10002"),
+        ] {
+            let _ = style.set_property(k, v);
+        }
+        document.body()?.append_child(&canvas).ok()?;
+        let ctx = canvas.get_context("2d").ok()??.dyn_into().ok()?;
+        Some(Self { canvas, ctx })
+    }
+
+    // Follow the top-right corner of `anchor`, which may move or resize.
+    fn place(&self, anchor: &HtmlCanvasElement) {
+        let rect = anchor.get_bounding_client_rect();
+        let style = self.canvas.style();
+        let _ = style.set_property("top", &format!("{}px", rect.top() + Self::INSET));
+        let _ = style.set_property("left", &format!("{}px", rect.right() - Self::W as f64 - Self::INSET));
+    }
+
+    fn draw(&self, st: &FrameStats, hist: &FrameHistory) {
+        let (w, h) = (Self::W as f64, Self::H as f64);
+        let ctx = &self.ctx;
+        ctx.clear_rect(0.0, 0.0, w, h);
+        ctx.set_fill_style_str("rgba(0,0,0,0.6)");
+        ctx.fill_rect(0.0, 0.0, w, h);
+
+        ctx.set_font("12px monospace");
+        ctx.set_fill_style_str("#fff");
+        let src = if st.gpu_timing { "gpu" } else { "cpu" };
+        let lines = [
+            format!("{:5.1} fps {:5.2} ms  x{:.2}", st.fps, st.frame_ms, st.scale),
+            format!("scene+mask {:6.2} ms ({src})", st.scene_ms),
+            format!("post       {:6.2} ms ({src})", st.post_ms),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            let _ = ctx.fill_text(line, 6.0, 15.0 + 14.0 * i as f64);
+        }
+
+        // Frame-time graph, newest on the right; 16.7/33.3 ms guides
+        let top = 50.0;
+        let gh = h - top - 4.0;
+        let y_of = |ms: f32| top + gh * (1.0 - (ms / Self::GRAPH_MAX_MS).min(1.0) as f64);
+        ctx.set_fill_style_str("rgba(255,255,255,0.25)");
+        for guide in [1000.0 / 60.0, 1000.0 / 30.0] {
+            ctx.fill_rect(0.0, y_of(guide), w, 1.0);
+        }
+        let bar_w = w / HISTORY_LEN as f64;
+        let x0 = w - bar_w * hist.len() as f64;
+        for (i, ms) in hist.iter().enumerate() {
+            let color = if ms <= 18.0 { "#3c3" } else if ms <= 34.0 { "#fc3" } else { "#f33" };
+            ctx.set_fill_style_str(color);
+            let y = y_of(ms);
+            ctx.fill_rect(x0 + i as f64 * bar_w, y, bar_w.max(1.0), top + gh - y);
+        }
+    }
+}
@@ -0,0 +1,533 @@
+#![cfg(target_arch = "wasm32")]
+
+//! Start-up options for a visualizer instance, read from the plain JS object
+//! passed to `new Viz(canvas, options)`.
+
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::Element;
+
+use super::error::VizError;
+use super::scenes::View;
+use crate::anim::{Animation, Curve, Ease, Key, Target, Wave};
+use crate::keymap::{Command, Keymap};
+use crate::motion::MotionPref;
+use crate::particles::ParticleConfig;
+use crate::pattern::PatternParams;
+use crate::perf::ScaleConfig;
+use crate::query::{DateTime, Fill, Query};
+use crate::schedule::DEFAULT_DURATION_MS;
+use crate::settings::{Presets, Settings};
+use crate::silhouette::{SilhouetteConfig, SilhouetteSource};
+use crate::sync::Role;
+use crate::text::TextConfig;
+use crate::video::{parse_hex_color, MaskMode, VideoConfig};
+
+/// One playlist entry as given by the caller; resolved against the loaded scenes
+/// when the renderer starts.
+#[derive(Clone, Debug)]
+pub struct EntrySpec {
+    pub scene: String,
+    /// Falls back to [`Options::duration_ms`].
+    pub duration_ms: Option<f64>,
+    pub anim: Animation,
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Built-in scenes to load, by name; all of them when `None`.
+    pub scenes: Option<Vec<String>>,
+    /// Play order; every loaded scene once when `None`.
+    pub playlist: Option<Vec<EntrySpec>>,
+    /// Default time per playlist entry.
+    pub duration_ms: f64,
+    /// Element receiving the scene label and error messages.
+    pub overlay: Option<Element>,
+    /// Element receiving the FPS / render-scale line while it is switched on.
+    pub stats: Option<Element>,
+    /// Start with the performance HUD visible.
+    pub hud: bool,
+    /// Limits of the adaptive render scale.
+    pub scale_config: ScaleConfig,
+    /// Advance to the next entry when the current one's duration is up.
+    pub autoplay: bool,
+    /// Listen for keyboard shortcuts on the window.
+    pub keyboard: bool,
+    /// Shortcut bindings used when `keyboard` is on.
+    pub keymap: Keymap,
+    /// Swipe, tap, long-press and pinch gestures on the canvas. Stops the
+    /// browser from scrolling or zooming when the touch starts on it.
+    pub gestures: bool,
+    /// Size the canvas to the window instead of its own layout box.
+    pub fill_window: bool,
+    /// Scene to start on instead of the first playlist entry.
+    pub start_scene: Option<String>,
+    /// Scene on screen when settings were last saved; unlike `start_scene` it
+    /// may be gone since (other `scenes`, another URL), so it is only a hint.
+    pub stored_scene: Option<String>,
+    /// Seed for the pattern parameters; `Math.random` when `None`.
+    pub seed: Option<u64>,
+    /// Fill used for every pattern; random per scene when `None`.
+    pub fill: Option<Fill>,
+    /// Start with the overlay element visible.
+    pub show_overlay: bool,
+    /// `localStorage` key to save settings and presets under; nothing is
+    /// persisted when `None`.
+    pub storage: Option<String>,
+    /// Start with this pattern, locked across scene changes.
+    pub locked_params: Option<PatternParams>,
+    /// Initial pinch zoom and rotation.
+    pub view: View,
+    pub presets: Presets,
+    /// Measure flashing and limit effects to WCAG 2.3.1 flash thresholds.
+    pub safety: bool,
+    /// Calm profile: always, never or following `prefers-reduced-motion`.
+    pub motion: MotionPref,
+    /// Play in lockstep with other displays through the host relay.
+    pub sync: Option<Role>,
+    /// Relay address; the page's host on the sync port when `None`.
+    pub sync_url: Option<String>,
+    /// Adds a "Video" or "Webcam" scene keyed from this source.
+    pub video: Option<VideoConfig>,
+    /// Adds an "Image" scene shaped like this picture or SVG path.
+    pub image: Option<SilhouetteConfig>,
+    /// Adds a "Text" scene spelling out this message.
+    pub text: Option<TextConfig>,
+    /// Emitters, forces and look of the "Particles" scene.
+    pub particles: ParticleConfig,
+}
+
+impl Options {
+    /// Options for the standalone page (`index.html`): full window, `#overlay`,
+    /// keyboard shortcuts and touch gestures.
+    pub fn page() -> Self {
+        let document = web_sys::window().and_then(|w| w.document());
+        let overlay = document.as_ref().and_then(|d| d.get_element_by_id("overlay"));
+        let stats = document.as_ref().and_then(|d| d.get_element_by_id("stats"));
+        Self { overlay, stats, keyboard: true, gestures: true, fill_window: true, ..Self::embedded() }
+    }
+
+    /// Defaults for `new Viz(canvas)`: the canvas keeps its own size, no overlay
+    /// and no global key or touch handling, so several instances can share a page.
+    pub fn embedded() -> Self {
+        Self {
+            scenes: None,
+            playlist: None,
+            duration_ms: DEFAULT_DURATION_MS,
+            overlay: None,
+            stats: None,
+            hud: false,
+            scale_config: ScaleConfig::default(),
+            autoplay: true,
+            keyboard: false,
+            keymap: Keymap::default(),
+            gestures: false,
+            fill_window: false,
+            start_scene: None,
+            stored_scene: None,
+            seed: None,
+            fill: None,
+            show_overlay: true,
+            storage: None,
+            locked_params: None,
+            view: View::default(),
+            presets: Presets::default(),
+            safety: false,
+            motion: MotionPref::Auto,
+            sync: None,
+            sync_url: None,
+            video: None,
+            image: None,
+            text: None,
+            particles: ParticleConfig::default(),
+        }
+    }
+
+    /// Start from persisted settings; explicit options applied afterwards win.
+    pub fn restore(&mut self, s: &Settings) {
+        self.stored_scene = s.scene.clone();
+        self.locked_params = s.locked_params;
+        self.show_overlay = s.overlay;
+        self.hud = s.hud;
+        self.scale_config = s.calibration.render_scale.map(ScaleConfig::validated).unwrap_or_default();
+        self.view = View::new(s.calibration.zoom, s.calibration.rotation);
+        self.presets = s.presets.clone();
+    }
+
+    /// Override fields from a JS object. `undefined`/`null` leave everything as
+    /// is; unknown keys and values of the wrong type are errors.
+    pub fn apply_js(&mut self, obj: &JsValue) -> Result<(), VizError> {
+        if obj.is_undefined() || obj.is_null() {
+            return Ok(());
+        }
+        let obj: &js_sys::Object = obj.dyn_ref().ok_or_else(|| invalid("options", "an object"))?;
+        for key in js_sys::Object::keys(obj).iter() {
+            let key = key.as_string().unwrap_or_default();
+            let v = js_sys::Reflect::get(obj, &JsValue::from_str(&key))?;
+            match key.as_str() {
+                "scenes" => self.scenes = Some(string_list(&v).ok_or_else(|| invalid("scenes", "an array of scene names"))?),
+                "playlist" => self.playlist = Some(playlist(&v)?),
+                "duration" => self.duration_ms = seconds(&v).ok_or_else(|| invalid("duration", "a positive number of seconds"))?,
+                "overlay" => self.overlay = element(&v).ok_or_else(|| invalid("overlay", "an element or null"))?,
+                "stats" => self.stats = element(&v).ok_or_else(|| invalid("stats", "an element or null"))?,
+                "hud" => self.hud = v.as_bool().ok_or_else(|| invalid("hud", "a boolean"))?,
+                "autoplay" => self.autoplay = v.as_bool().ok_or_else(|| invalid("autoplay", "a boolean"))?,
+                "keyboard" => self.keyboard = v.as_bool().ok_or_else(|| invalid("keyboard", "a boolean"))?,
+                "keys" => apply_keys(&mut self.keymap, &v)?,
+                "gestures" => self.gestures = v.as_bool().ok_or_else(|| invalid("gestures", "a boolean"))?,
+                "scene" => self.start_scene = Some(v.as_string().ok_or_else(|| invalid("scene", "a scene name"))?),
+                "seed" => {
+                    let seed = v.as_f64().filter(|s| s.fract() == 0.0 && *s >= 0.0);
+                    self.seed = Some(seed.ok_or_else(|| invalid("seed", "a non-negative integer"))? as u64);
+                }
+                "fill" => {
+                    self.fill = match v.as_string().as_deref() {
+                        Some("stripes") => Some(Fill::Stripes),
+                        Some("polka") => Some(Fill::Polka),
+                        _ => return Err(invalid("fill", "\"stripes\" or \"polka\"")),
+                    }
+                }
+                "fillWindow" => self.fill_window = v.as_bool().ok_or_else(|| invalid("fillWindow", "a boolean"))?,
+                "storage" => self.storage = storage(&v)?,
+                "safety" => self.safety = v.as_bool().ok_or_else(|| invalid("safety", "a boolean"))?,
+                "reducedMotion" => self.motion = motion_pref(&v)?,
+                "sync" => {
+                    let role = v.as_string().and_then(|r| r.parse().ok());
+                    self.sync = Some(role.ok_or_else(|| invalid("sync", "\"leader\" or \"follower\""))?);
+                }
+                "syncUrl" => self.sync_url = Some(v.as_string().ok_or_else(|| invalid("syncUrl", "a URL"))?),
+                "video" => self.video = Some(video(&v)?),
+                "image" => self.image = Some(image(&v)?),
+                "text" => self.text = Some(text(&v)?),
+                "particles" => self.particles = particles(&v)?,
+                _ => return Err(VizError::InvalidOption(format!("unknown option \"{key}\""))),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Options {
+    /// Apply settings from the page URL. The playlist URL is fetched by the caller.
+    pub fn apply_query(&mut self, q: &Query) {
+        if let Some(scene) = &q.scene {
+            self.start_scene = Some(scene.clone());
+        }
+        if let Some(s) = q.duration_s {
+            self.duration_ms = s * 1000.0;
+        }
+        self.seed = q.seed.or(self.seed);
+        self.fill = q.fill.or(self.fill);
+        self.show_overlay = q.overlay.unwrap_or(self.show_overlay);
+        self.hud = q.hud.unwrap_or(self.hud);
+        self.autoplay = q.autoplay.unwrap_or(self.autoplay);
+        self.safety = q.safety.unwrap_or(self.safety);
+        self.motion = q.motion.unwrap_or(self.motion);
+        self.sync = q.sync.or(self.sync);
+        self.sync_url = q.relay.clone().or(self.sync_url.take());
+        if let Some(source) = &q.video {
+            self.video = Some(VideoConfig::new(source.clone()));
+        }
+        if let (Some(video), Some(mask)) = (self.video.as_mut(), q.mask) {
+            video.mask = mask;
+        }
+        if let Some(url) = &q.image {
+            self.image = Some(SilhouetteConfig::new(SilhouetteSource::Image(url.clone())));
+        }
+        if let (Some(image), Some(fit)) = (self.image.as_mut(), q.fit) {
+            image.fit = fit;
+        }
+        if let Some(message) = &q.text {
+            self.text = Some(TextConfig::new(message.clone()));
+        }
+        if let (Some(text), Some(date)) = (self.text.as_mut(), &q.countdown) {
+            text.countdown_to = Some(query_date_ms(date));
+        }
+    }
+}
+
+/// The `storage` option of a JS options object, read ahead of the others so
+/// stored settings can be restored before explicit options override them.
+pub fn storage_key(obj: &JsValue) -> Result<Option<String>, VizError> {
+    if !obj.is_object() {
+        return Ok(None);
+    }
+    storage(&js_sys::Reflect::get(obj, &"storage".into())?)
+}
+
+/// Parse a playlist in the format of the `playlist` option, e.g. fetched JSON.
+pub fn parse_playlist(v: &JsValue) -> Result<Vec<EntrySpec>, VizError> {
+    playlist(v)
+}
+
+// `{ "n": "next", "Backspace": null }`: bind keys to command names, or unbind.
+fn apply_keys(keymap: &mut Keymap, v: &JsValue) -> Result<(), VizError> {
+    let obj: &js_sys::Object = v.dyn_ref().ok_or_else(|| invalid("keys", "an object of key: command"))?;
+    for key in js_sys::Object::keys(obj).iter() {
+        let key = key.as_string().unwrap_or_default();
+        let cmd = js_sys::Reflect::get(obj, &JsValue::from_str(&key))?;
+        bind_key(keymap, &key, cmd.as_string().as_deref())?;
+    }
+    Ok(())
+}
+
+/// Bind `key` to the command named `cmd`, or unbind it when `None`.
+pub fn bind_key(keymap: &mut Keymap, key: &str, cmd: Option<&str>) -> Result<(), VizError> {
+    match cmd {
+        Some(name) => keymap.bind(key, name.parse::<Command>().map_err(VizError::InvalidOption)?),
+        None => keymap.unbind(key),
+    }
+    Ok(())
+}
+
+fn invalid(key: &str, expected: &str) -> VizError {
+    VizError::InvalidOption(format!("\"{key}\" must be {expected}"))
+}
+
+/// `reducedMotion`: `true`, `false` or `"auto"`.
+pub fn motion_pref(v: &JsValue) -> Result<MotionPref, VizError> {
+    match (v.as_bool(), v.as_string().as_deref()) {
+        (Some(true), _) => Ok(MotionPref::Reduce),
+        (Some(false), _) => Ok(MotionPref::Full),
+        (_, Some("auto")) => Ok(MotionPref::Auto),
+        _ => Err(invalid("reducedMotion", "true, false or \"auto\"")),
+    }
+}
+
+fn storage(v: &JsValue) -> Result<Option<String>, VizError> {
+    if v.is_undefined() || v.is_null() {
+        return Ok(None);
+    }
+    v.as_string().filter(|k| !k.is_empty()).map(Some).ok_or_else(|| invalid("storage", "a localStorage key or null"))
+}
+
+// `"webcam"`, a video URL, or `{ src, mask, threshold, key, mirror }`.
+fn video(v: &JsValue) -> Result<VideoConfig, VizError> {
+    let expected = "\"webcam\", a video URL or { src, mask, threshold, key, mirror }";
+    if let Some(src) = v.as_string() {
+        return Ok(VideoConfig::new(src.parse().map_err(VizError::InvalidOption)?));
+    }
+    if !v.is_object() {
+        return Err(invalid("video", expected));
+    }
+    let get = |k: &str| js_sys::Reflect::get(v, &k.into()).unwrap_or(JsValue::UNDEFINED);
+    let src = get("src").as_string().ok_or_else(|| invalid("video.src", "\"webcam\" or a video URL"))?;
+    let mut config = VideoConfig::new(src.parse().map_err(VizError::InvalidOption)?);
+    let mask = get("mask");
+    if !mask.is_undefined() {
+        let mode = mask.as_string().ok_or_else(|| invalid("video.mask", "\"luminance\", \"chroma\" or \"difference\""))?;
+        config.mask = mode.parse().map_err(VizError::InvalidOption)?;
+    }
+    let key = get("key");
+    if !key.is_undefined() {
+        let rgb = key.as_string().as_deref().and_then(parse_hex_color).ok_or_else(|| invalid("video.key", "a #rrggbb colour"))?;
+        let tolerance = config.mask.threshold();
+        config.mask = match config.mask {
+            MaskMode::ChromaKey { .. } => MaskMode::ChromaKey { key: rgb, tolerance },
+            _ => return Err(invalid("video.key", "used with mask: \"chroma\" only")),
+        };
+    }
+    let threshold = get("threshold");
+    if !threshold.is_undefined() {
+        let t = threshold.as_f64().filter(|t| (0.0..=1.0).contains(t)).ok_or_else(|| invalid("video.threshold", "a number from 0 to 1"))?;
+        config.mask = config.mask.with_threshold(t as f32);
+    }
+    let mirror = get("mirror");
+    if !mirror.is_undefined() {
+        config.mirror = mirror.as_bool().ok_or_else(|| invalid("video.mirror", "a boolean"))?;
+    }
+    Ok(config)
+}
+
+// An image URL, or `{ src | path, viewBox, fit, sdf }`.
+fn image(v: &JsValue) -> Result<SilhouetteConfig, VizError> {
+    if let Some(src) = v.as_string().filter(|s| !s.is_empty()) {
+        return Ok(SilhouetteConfig::new(SilhouetteSource::Image(src)));
+    }
+    if !v.is_object() {
+        return Err(invalid("image", "an image URL or { src | path, viewBox, fit, sdf }"));
+    }
+    let get = |k: &str| js_sys::Reflect::get(v, &k.into()).unwrap_or(JsValue::UNDEFINED);
+    let source = match (get("src").as_string(), get("path").as_string()) {
+        (Some(src), None) => SilhouetteSource::Image(src),
+        (None, Some(d)) => {
+            let view_box = get("viewBox");
+            let view_box = if view_box.is_undefined() { Some([0.0, 0.0, 100.0, 100.0]) } else { view_box_of(&view_box) };
+            SilhouetteSource::Path { d, view_box: view_box.ok_or_else(|| invalid("image.viewBox", "[x, y, width, height] with a positive size"))? }
+        }
+        _ => return Err(invalid("image", "given either src or path")),
+    };
+    let mut config = SilhouetteConfig::new(source);
+    let fit = get("fit");
+    if !fit.is_undefined() {
+        config.fit = fit.as_string().and_then(|f| f.parse().ok()).ok_or_else(|| invalid("image.fit", "\"fill\", \"contain\" or \"cover\""))?;
+    }
+    let sdf = get("sdf");
+    if !sdf.is_undefined() {
+        config.sdf = sdf.as_bool().ok_or_else(|| invalid("image.sdf", "a boolean"))?;
+    }
+    Ok(config)
+}
+
+// A message, or `{ text, countdownTo }`.
+fn text(v: &JsValue) -> Result<TextConfig, VizError> {
+    if let Some(message) = v.as_string() {
+        return Ok(TextConfig::new(message));
+    }
+    if !v.is_object() {
+        return Err(invalid("text", "a message or { text, countdownTo }"));
+    }
+    let get = |k: &str| js_sys::Reflect::get(v, &k.into()).unwrap_or(JsValue::UNDEFINED);
+    let mut config = TextConfig::new(get("text").as_string().ok_or_else(|| invalid("text.text", "a string"))?);
+    let to = get("countdownTo");
+    if !to.is_undefined() {
+        config.countdown_to = Some(date_ms(&to).ok_or_else(|| invalid("text.countdownTo", "a Date, timestamp or date string"))?);
+    }
+    Ok(config)
+}
+
+// `{ count, emitters, attractors, curl, curlScale, drag, life, size }`; see
+// `ParticleConfig` for the fields and their defaults.
+fn particles(v: &JsValue) -> Result<ParticleConfig, VizError> {
+    if !v.is_object() {
+        return Err(invalid("particles", "an object"));
+    }
+    let json = js_sys::JSON::stringify(v)?.as_string().unwrap_or_default();
+    ParticleConfig::from_json(&json).map_err(VizError::InvalidOption)
+}
+
+// A `Date`, ms timestamp or date string as ms since the epoch.
+fn date_ms(v: &JsValue) -> Option<f64> {
+    let ms = match v.as_f64() {
+        Some(ms) => ms,
+        None if v.is_string() || v.is_instance_of::<js_sys::Date>() => js_sys::Date::new(v).get_time(),
+        None => return None,
+    };
+    ms.is_finite().then_some(ms)
+}
+
+// `date` in ms since the epoch; without an offset it is the viewer's local time.
+fn query_date_ms(date: &DateTime) -> f64 {
+    date.utc_ms().unwrap_or_else(|| {
+        js_sys::Date::new_with_year_month_day_hr_min_sec(
+            date.year as u32,
+            date.month as i32 - 1,
+            date.day as i32,
+            date.hour as i32,
+            date.minute as i32,
+            date.second as i32,
+        )
+        .get_time()
+    })
+}
+
+// An element, or `None` for `null` / `undefined`; `None` outside for anything else.
+fn element(v: &JsValue) -> Option<Option<Element>> {
+    if v.is_null() || v.is_undefined() {
+        return Some(None);
+    }
+    v.dyn_ref::<Element>().map(|e| Some(e.clone()))
+}
+
+// `[x, y, width, height]` as an array or the string of an SVG `viewBox` attribute.
+fn view_box_of(v: &JsValue) -> Option<[f32; 4]> {
+    let nums: Vec<f32> = match v.as_string() {
+        Some(s) => s.split([' ', ',']).filter(|n| !n.is_empty()).map(|n| n.parse().ok()).collect::<Option<_>>()?,
+        None => js_sys::Array::from(v).iter().map(|n| n.as_f64().map(|n| n as f32)).collect::<Option<_>>()?,
+    };
+    let vb: [f32; 4] = nums.try_into().ok()?;
+    (vb.iter().all(|n| n.is_finite()) && vb[2] > 0.0 && vb[3] > 0.0).then_some(vb)
+}
+
+fn seconds(v: &JsValue) -> Option<f64> {
+    v.as_f64().filter(|s| s.is_finite() && *s > 0.0).map(|s| s * 1000.0)
+}
+
+fn string_list(v: &JsValue) -> Option<Vec<String>> {
+    let arr: &js_sys::Array = v.dyn_ref()?;
+    arr.iter().map(|s| s.as_string()).collect()
+}
+
+// `["Pulsing Plus", { scene: "Rotating Square", duration: 8 }, ...]`
+fn playlist(v: &JsValue) -> Result<Vec<EntrySpec>, VizError> {
+    let expected = "an array of scene names or { scene, duration } objects";
+    let arr: &js_sys::Array = v.dyn_ref().ok_or_else(|| invalid("playlist", expected))?;
+    arr.iter()
+        .map(|item| {
+            if let Some(scene) = item.as_string() {
+                return Ok(EntrySpec { scene, duration_ms: None, anim: Animation::default() });
+            }
+            let scene = js_sys::Reflect::get(&item, &"scene".into())
+                .ok()
+                .and_then(|s| s.as_string())
+                .ok_or_else(|| invalid("playlist", expected))?;
+            let duration = js_sys::Reflect::get(&item, &"duration".into()).unwrap_or(JsValue::UNDEFINED);
+            let duration_ms = if duration.is_undefined() {
+                None
+            } else {
+                Some(seconds(&duration).ok_or_else(|| invalid("playlist[].duration", "a positive number of seconds"))?)
+            };
+            let animate = js_sys::Reflect::get(&item, &"animate".into()).unwrap_or(JsValue::UNDEFINED);
+            let anim = if animate.is_undefined() { Animation::default() } else { animation(&animate)? };
+            Ok(EntrySpec { scene, duration_ms, anim })
+        })
+        .collect()
+}
+
+// `{ scale: 1.5, rotation: { ramp: 0.2 }, x: { lfo: "sine", hz: 0.1, amp: 0.3 },
+//    u_glow: { keys: [[0, 0], [2, 1, "spring"]], loop: true } }`
+fn animation(v: &JsValue) -> Result<Animation, VizError> {
+    let obj: &js_sys::Object = v.dyn_ref().ok_or_else(|| invalid("playlist[].animate", "an object of target: curve"))?;
+    let mut anim = Animation::default();
+    for key in js_sys::Object::keys(obj).iter() {
+        let key = key.as_string().unwrap_or_default();
+        let target: Target = key.parse().map_err(VizError::InvalidOption)?;
+        let spec = js_sys::Reflect::get(obj, &JsValue::from_str(&key))?;
+        anim.set(target, curve(&key, &spec)?);
+    }
+    Ok(anim)
+}
+
+fn curve(target: &str, v: &JsValue) -> Result<Curve, VizError> {
+    let bad = || invalid(&format!("animate.{target}"), "a number or a { ramp }, { keys } or { lfo } curve");
+    if let Some(c) = v.as_f64() {
+        return Ok(Curve::Const(c as f32));
+    }
+    if !v.is_object() {
+        return Err(bad());
+    }
+    let field = |name: &str| js_sys::Reflect::get(v, &name.into()).unwrap_or(JsValue::UNDEFINED);
+    let number = |name: &str, default: f32| {
+        let f = field(name);
+        if f.is_undefined() { Ok(default) } else { f.as_f64().map(|n| n as f32).ok_or_else(bad) }
+    };
+    if let Some(rate) = field("ramp").as_f64() {
+        return Ok(Curve::Ramp(rate as f32));
+    }
+    if let Some(wave) = field("lfo").as_string() {
+        let wave: Wave = wave.parse().map_err(VizError::InvalidOption)?;
+        return Ok(Curve::Lfo {
+            wave,
+            hz: number("hz", 1.0)?,
+            amp: number("amp", 1.0)?,
+            offset: number("offset", 0.0)?,
+            phase: number("phase", 0.0)?,
+        });
+    }
+    let keys = field("keys");
+    let keys: &js_sys::Array = keys.dyn_ref().ok_or_else(bad)?;
+    let keys = keys
+        .iter()
+        .map(|k| {
+            // [t, value] or [t, value, "easing"]
+            let k: js_sys::Array = k.dyn_into().map_err(|_| bad())?;
+            let t = k.get(0).as_f64().ok_or_else(bad)? as f32;
+            let value = k.get(1).as_f64().ok_or_else(bad)? as f32;
+            let ease = match k.get(2).as_string() {
+                Some(e) => e.parse::<Ease>().map_err(VizError::InvalidOption)?,
+                None => Ease::Linear,
+            };
+            Ok(Key { t, value, ease })
+        })
+        .collect::<Result<Vec<_>, VizError>>()?;
+    Ok(Curve::keyframes(keys, field("loop").as_bool().unwrap_or(false)))
+}
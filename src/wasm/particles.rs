@@ -0,0 +1,229 @@
+#![cfg(target_arch = "wasm32")]
+
+//! Particle cloud scene. Transform feedback steps the particles from one
+//! buffer into the other on the GPU; the same points are then drawn twice,
+//! soft sprites into the colour target and hard discs into the mask target,
+//! so the cloud gets the pattern fill and edge flames of any other shape.
+
+use web_sys::{WebGl2RenderingContext as GL, WebGlBuffer, WebGlTransformFeedback};
+
+use super::error::VizError;
+use super::glutil::{delete, track, uniforms};
+use super::post::scene_draw_buffers;
+use super::scenes::{set_pose, ParamCache, SdfUniforms, Visualizer};
+use super::shaders::{ShaderLibrary, ShaderProgram};
+use crate::anim::Pose;
+use crate::glsl::{SCENE_COLOR_LOCATION, SCENE_MASK_LOCATION};
+use crate::particles::{seed, ParticleConfig, MAX_ATTRACTORS, MAX_EMITTERS, PARTICLE_FLOATS};
+
+uniforms! {
+    struct UpdateUniforms {
+        u_dt, u_sim_time, u_curl, u_curl_scale, u_drag, u_life,
+        u_emitter_count, u_emitters, u_emitter_vel, u_attractor_count, u_attractors,
+    }
+}
+uniforms! { struct DrawUniforms { u_size, u_pass } }
+
+/// Outputs of particle_update.vert, in buffer order.
+const FEEDBACK: &[&str] = &["tf_pos", "tf_vel", "tf_age", "tf_life"];
+const STRIDE: i32 = (PARTICLE_FLOATS * 4) as i32;
+// Longest step simulated per frame; after a stall the cloud slows instead of jumping.
+const MAX_DT: f32 = 1.0 / 20.0;
+
+pub struct ParticleScene {
+    config: ParticleConfig,
+    update: Option<ShaderProgram>,
+    update_u: Option<UpdateUniforms>,
+    draw: Option<ShaderProgram>,
+    pose_u: Option<SdfUniforms>,
+    draw_u: Option<DrawUniforms>,
+    params: ParamCache,
+    // The current state first; each step writes the second and swaps them
+    buffers: Vec<WebGlBuffer>,
+    feedback: Option<WebGlTransformFeedback>,
+    last_t: Option<f32>,
+}
+
+impl ParticleScene {
+    pub fn new(config: ParticleConfig) -> Self {
+        Self {
+            config,
+            update: None,
+            update_u: None,
+            draw: None,
+            pose_u: None,
+            draw_u: None,
+            params: Vec::new(),
+            buffers: Vec::new(),
+            feedback: None,
+            last_t: None,
+        }
+    }
+
+    // Put every particle back to its unborn starting state.
+    fn reseed(&self, gl: &GL) {
+        let data: Vec<f32> = (0..self.config.count).flat_map(|i| seed(&self.config, i).to_floats()).collect();
+        for buf in &self.buffers {
+            gl.bind_buffer(GL::ARRAY_BUFFER, Some(buf));
+            unsafe {
+                let fa = js_sys::Float32Array::view(&data);
+                gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &fa, GL::DYNAMIC_COPY);
+            }
+        }
+        gl.bind_buffer(GL::ARRAY_BUFFER, None);
+    }
+
+    // Advance the simulation to scene time `t`, `dt` seconds on.
+    fn step(&mut self, gl: &GL, t: f32, dt: f32) {
+        let (Some(prog), Some(u), Some(tf)) = (self.update.as_ref(), self.update_u.as_ref(), self.feedback.as_ref()) else {
+            return;
+        };
+        let c = &self.config;
+        gl.use_program(Some(&prog.prog));
+        gl.uniform1f(u.u_dt.as_ref(), dt);
+        gl.uniform1f(u.u_sim_time.as_ref(), t);
+        gl.uniform1f(u.u_curl.as_ref(), c.curl);
+        gl.uniform1f(u.u_curl_scale.as_ref(), c.curl_scale);
+        gl.uniform1f(u.u_drag.as_ref(), c.drag);
+        gl.uniform2f(u.u_life.as_ref(), c.life[0], c.life[1]);
+        let mut emitters = vec![0.0; 4 * MAX_EMITTERS as usize];
+        let mut velocities = vec![0.0; 2 * MAX_EMITTERS as usize];
+        for (i, e) in c.emitters.iter().enumerate() {
+            emitters[4 * i..4 * i + 4].copy_from_slice(&[e.pos[0], e.pos[1], e.radius, e.spread]);
+            velocities[2 * i..2 * i + 2].copy_from_slice(&e.velocity);
+        }
+        let mut attractors = vec![0.0; 3 * MAX_ATTRACTORS as usize];
+        for (i, a) in c.attractors.iter().enumerate() {
+            attractors[3 * i..3 * i + 3].copy_from_slice(&[a.pos[0], a.pos[1], a.strength]);
+        }
+        gl.uniform1i(u.u_emitter_count.as_ref(), c.emitters.len() as i32);
+        gl.uniform4fv_with_f32_array(u.u_emitters.as_ref(), &emitters);
+        gl.uniform2fv_with_f32_array(u.u_emitter_vel.as_ref(), &velocities);
+        gl.uniform1i(u.u_attractor_count.as_ref(), c.attractors.len() as i32);
+        gl.uniform3fv_with_f32_array(u.u_attractors.as_ref(), &attractors);
+
+        bind_particles(gl, &self.buffers[0]);
+        gl.bind_transform_feedback(GL::TRANSFORM_FEEDBACK, Some(tf));
+        gl.bind_buffer_base(GL::TRANSFORM_FEEDBACK_BUFFER, 0, Some(&self.buffers[1]));
+        gl.enable(GL::RASTERIZER_DISCARD);
+        gl.begin_transform_feedback(GL::POINTS);
+        gl.draw_arrays(GL::POINTS, 0, c.count as i32);
+        gl.end_transform_feedback();
+        gl.disable(GL::RASTERIZER_DISCARD);
+        // A buffer may not be a feedback target while it is read as vertices
+        gl.bind_buffer_base(GL::TRANSFORM_FEEDBACK_BUFFER, 0, None);
+        gl.bind_transform_feedback(GL::TRANSFORM_FEEDBACK, None);
+        unbind_particles(gl);
+        self.buffers.swap(0, 1);
+    }
+}
+
+fn bind_particles(gl: &GL, buf: &WebGlBuffer) {
+    gl.bind_buffer(GL::ARRAY_BUFFER, Some(buf));
+    // position, velocity, age, life
+    for (loc, size, offset) in [(0, 2, 0), (1, 2, 8), (2, 1, 16), (3, 1, 20)] {
+        gl.enable_vertex_attrib_array(loc);
+        gl.vertex_attrib_pointer_with_i32(loc, size, GL::FLOAT, false, STRIDE, offset);
+    }
+}
+
+fn unbind_particles(gl: &GL) {
+    for loc in 0..4 {
+        gl.disable_vertex_attrib_array(loc);
+    }
+    gl.bind_buffer(GL::ARRAY_BUFFER, None);
+}
+
+impl Visualizer for ParticleScene {
+    fn name(&self) -> &'static str {
+        "Particles"
+    }
+
+    fn init(&mut self, gl: &GL, lib: &ShaderLibrary) -> Result<(), VizError> {
+        let update = ShaderProgram::with_feedback(gl, lib, "particle_update.vert", "particle_update.frag", FEEDBACK)?;
+        self.update_u = Some(UpdateUniforms::locate(gl, &update.prog));
+        self.update = Some(update);
+        let draw = ShaderProgram::new(gl, lib, "particle.vert", "particle.frag")?;
+        self.pose_u = Some(SdfUniforms::locate(gl, &draw.prog));
+        self.draw_u = Some(DrawUniforms::locate(gl, &draw.prog));
+        self.draw = Some(draw);
+        for _ in 0..2 {
+            self.buffers.push(track(gl.create_buffer()).ok_or(VizError::Resource("particle buffer"))?);
+        }
+        self.feedback = Some(track(gl.create_transform_feedback()).ok_or(VizError::Resource("transform feedback"))?);
+        self.reseed(gl);
+        self.last_t = None;
+        Ok(())
+    }
+
+    fn render(&mut self, gl: &GL, t: f32, pose: &Pose) {
+        let dt = match self.last_t {
+            Some(last) if t >= last => (t - last).min(MAX_DT),
+            // Back to the start of the entry (or scrubbed back): build up again
+            Some(_) => {
+                self.reseed(gl);
+                0.0
+            }
+            None => 0.0,
+        };
+        self.last_t = Some(t);
+        if dt > 0.0 {
+            self.step(gl, t, dt);
+        }
+
+        let (Some(prog), Some(pose_u), Some(u)) = (self.draw.as_ref(), self.pose_u.as_ref(), self.draw_u.as_ref()) else {
+            return;
+        };
+        let count = self.config.count as i32;
+        gl.use_program(Some(&prog.prog));
+        set_pose(gl, &prog.prog, pose_u, &mut self.params, pose);
+        gl.uniform1f(u.u_size.as_ref(), self.config.size);
+        bind_particles(gl, &self.buffers[0]);
+        // Soft colour pass, sprites adding up where they overlap
+        gl.draw_buffers(&scene_draw_buffers(Some(SCENE_COLOR_LOCATION)));
+        gl.enable(GL::BLEND);
+        gl.blend_func(GL::ONE, GL::ONE);
+        gl.uniform1i(u.u_pass.as_ref(), 0);
+        gl.draw_arrays(GL::POINTS, 0, count);
+        gl.disable(GL::BLEND);
+        // Hard mask pass
+        gl.draw_buffers(&scene_draw_buffers(Some(SCENE_MASK_LOCATION)));
+        gl.uniform1i(u.u_pass.as_ref(), 1);
+        gl.draw_arrays(GL::POINTS, 0, count);
+        gl.draw_buffers(&scene_draw_buffers(None));
+        unbind_particles(gl);
+    }
+
+    fn reload(&mut self, gl: &GL, lib: &ShaderLibrary, changed: &[String]) -> Result<(), VizError> {
+        if let Some(prog) = self.update.as_mut() {
+            if prog.reload(gl, lib, changed)? {
+                self.update_u = Some(UpdateUniforms::locate(gl, &prog.prog));
+            }
+        }
+        if let Some(prog) = self.draw.as_mut() {
+            if prog.reload(gl, lib, changed)? {
+                self.pose_u = Some(SdfUniforms::locate(gl, &prog.prog));
+                self.draw_u = Some(DrawUniforms::locate(gl, &prog.prog));
+                self.params.clear();
+            }
+        }
+        Ok(())
+    }
+
+    fn destroy(&mut self, gl: &GL) {
+        for prog in [self.update.take(), self.draw.take()].into_iter().flatten() {
+            prog.delete(gl);
+        }
+        for buf in self.buffers.drain(..) {
+            delete(gl, &buf);
+        }
+        if let Some(tf) = self.feedback.take() {
+            delete(gl, &tf);
+        }
+        self.update_u = None;
+        self.pose_u = None;
+        self.draw_u = None;
+        self.params.clear();
+        self.last_t = None;
+    }
+}
@@ -0,0 +1,230 @@
+#![cfg(target_arch = "wasm32")]
+
+//! Offscreen scene/mask targets and the final post-process pass: pattern fill
+//! inside the mask, flame edges, displacement and vignette.
+
+use wasm_bindgen::JsValue;
+use web_sys::{WebGl2RenderingContext as GL, WebGlBuffer, WebGlFramebuffer, WebGlProgram, WebGlTexture};
+
+use super::error::VizError;
+use super::glutil::{delete, track, uniforms, Pending};
+use super::shaders::{ShaderLibrary, ShaderProgram};
+use crate::glsl::{SCENE_COLOR_LOCATION, SCENE_MASK_LOCATION};
+use crate::pattern::PatternParams;
+
+uniforms! {
+    struct PostUniforms {
+        u_src, u_mask, u_texel,
+        u_stripe_theta0, u_stripe_theta_speed, u_stripe_density, u_stripe_thickness,
+        u_stripe_drift_speed, u_color_speed,
+        u_fill_mode, u_dot_theta0, u_dot_theta_speed, u_dot_drift_speed,
+        u_dot_density, u_dot_radius_min, u_dot_radius_max, u_contrast, u_motion,
+    }
+}
+
+/// Adjustments of the post pass for accessibility.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Effects {
+    /// Below 1 flattens the output towards grey.
+    pub contrast: f32,
+    /// Displacement and flame flicker amount; 0 for reduced motion.
+    pub motion: f32,
+}
+
+impl Default for Effects {
+    fn default() -> Self {
+        Self { contrast: 1.0, motion: 1.0 }
+    }
+}
+
+/// Draw-buffer list for the scene framebuffer: fragment output N goes to
+/// attachment N, with only output `only` written when given.
+pub fn scene_draw_buffers(only: Option<u32>) -> js_sys::Array {
+    let mut bufs = [GL::NONE; 2];
+    for loc in [SCENE_COLOR_LOCATION, SCENE_MASK_LOCATION] {
+        if only.is_none_or(|o| o == loc) {
+            bufs[loc as usize] = GL::COLOR_ATTACHMENT0 + loc;
+        }
+    }
+    bufs.iter().map(|&b| JsValue::from(b)).collect()
+}
+
+/// Scene color and mask share a single framebuffer (MRT): visualizers write both
+/// outputs from one fragment shader so the SDF is only evaluated once per pixel.
+pub struct Post {
+    prog: ShaderProgram,
+    u: PostUniforms,
+    vbo: WebGlBuffer,
+    fbo: WebGlFramebuffer,
+    tex_scene: WebGlTexture,
+    tex_mask: WebGlTexture,
+    /// Offscreen target size (canvas size times `scale`)
+    pub w: i32,
+    pub h: i32,
+    /// Canvas drawing-buffer size the final pass upscales to
+    pub screen_w: i32,
+    pub screen_h: i32,
+    scale: f32,
+}
+
+impl Post {
+    pub fn new(gl: &GL, lib: &ShaderLibrary, w: i32, h: i32) -> Result<Self, VizError> {
+        // Deletes what was made so far if a later step fails
+        let mut made = Pending::new(gl);
+        let prog = ShaderProgram::new(gl, lib, "fullscreen.vert", "post.frag")?;
+        made.add(prog.prog.clone());
+        let u = PostUniforms::locate(gl, &prog.prog);
+        Self::bind_samplers(gl, &prog.prog, &u);
+
+        // Fullscreen large triangle VBO
+        let verts: [f32; 6] = [ -1.0, -1.0, 3.0, -1.0, -1.0, 3.0 ];
+        let vbo = made.add(track(gl.create_buffer()).ok_or(VizError::Resource("vertex buffer"))?);
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vbo));
+        unsafe {
+            let fa = js_sys::Float32Array::view(&verts);
+            gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &fa, GL::STATIC_DRAW);
+        }
+
+        // Create scene texture and FBO
+        let tex = made.add(track(gl.create_texture()).ok_or(VizError::Resource("scene texture"))?);
+        gl.bind_texture(GL::TEXTURE_2D, Some(&tex));
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::LINEAR as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::LINEAR as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            GL::TEXTURE_2D, 0, GL::RGBA as i32, w, h, 0, GL::RGBA, GL::UNSIGNED_BYTE, None
+        )?;
+
+        // Mask texture
+        let tex_m = made.add(track(gl.create_texture()).ok_or(VizError::Resource("mask texture"))?);
+        gl.bind_texture(GL::TEXTURE_2D, Some(&tex_m));
+        // Use NEAREST filtering for the mask to avoid edge expansion artifacts
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::NEAREST as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::NEAREST as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            GL::TEXTURE_2D, 0, GL::RGBA as i32, w, h, 0, GL::RGBA, GL::UNSIGNED_BYTE, None
+        )?;
+
+        // One FBO with scene color and mask on the attachments matching their
+        // shader output locations. The draw-buffer list is framebuffer state,
+        // so setting it once here suffices.
+        let fbo = made.add(track(gl.create_framebuffer()).ok_or(VizError::Resource("framebuffer"))?);
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&fbo));
+        let color_att = GL::COLOR_ATTACHMENT0 + SCENE_COLOR_LOCATION;
+        let mask_att = GL::COLOR_ATTACHMENT0 + SCENE_MASK_LOCATION;
+        gl.framebuffer_texture_2d(GL::FRAMEBUFFER, color_att, GL::TEXTURE_2D, Some(&tex), 0);
+        gl.framebuffer_texture_2d(GL::FRAMEBUFFER, mask_att, GL::TEXTURE_2D, Some(&tex_m), 0);
+        gl.draw_buffers(&scene_draw_buffers(None));
+        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+
+        made.done();
+        Ok(Self { prog, u, vbo, fbo, tex_scene: tex, tex_mask: tex_m, w, h, screen_w: w, screen_h: h, scale: 1.0 })
+    }
+
+    // Sampler units never change, so bind them once per link.
+    fn bind_samplers(gl: &GL, prog: &WebGlProgram, u: &PostUniforms) {
+        gl.use_program(Some(prog));
+        gl.uniform1i(u.u_src.as_ref(), 0);
+        gl.uniform1i(u.u_mask.as_ref(), 1);
+    }
+
+    pub fn reload(&mut self, gl: &GL, lib: &ShaderLibrary, changed: &[String]) -> Result<(), VizError> {
+        if self.prog.reload(gl, lib, changed)? {
+            self.u = PostUniforms::locate(gl, &self.prog.prog);
+            Self::bind_samplers(gl, &self.prog.prog, &self.u);
+        }
+        Ok(())
+    }
+
+    /// Delete the program, buffer and render targets.
+    pub fn delete(&self, gl: &GL) {
+        self.prog.delete(gl);
+        delete(gl, &self.vbo);
+        delete(gl, &self.fbo);
+        delete(gl, &self.tex_scene);
+        delete(gl, &self.tex_mask);
+    }
+
+    pub fn resize(&mut self, gl: &GL, w: i32, h: i32) -> Result<(), VizError> {
+        self.screen_w = w; self.screen_h = h;
+        self.alloc_targets(gl)
+    }
+
+    pub fn set_scale(&mut self, gl: &GL, scale: f32) -> Result<(), VizError> {
+        self.scale = scale;
+        self.alloc_targets(gl)
+    }
+
+    /// (Re)allocate the offscreen textures when the scaled size changes.
+    fn alloc_targets(&mut self, gl: &GL) -> Result<(), VizError> {
+        let w = ((self.screen_w as f32 * self.scale).round() as i32).max(1);
+        let h = ((self.screen_h as f32 * self.scale).round() as i32).max(1);
+        if self.w == w && self.h == h { return Ok(()); }
+        self.w = w; self.h = h;
+        gl.bind_texture(GL::TEXTURE_2D, Some(&self.tex_scene));
+        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            GL::TEXTURE_2D, 0, GL::RGBA as i32, w, h, 0, GL::RGBA, GL::UNSIGNED_BYTE, None
+        )?;
+        gl.bind_texture(GL::TEXTURE_2D, Some(&self.tex_mask));
+        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            GL::TEXTURE_2D, 0, GL::RGBA as i32, w, h, 0, GL::RGBA, GL::UNSIGNED_BYTE, None
+        )?;
+        Ok(())
+    }
+
+    /// Bind the scene+mask framebuffer and clear both attachments.
+    pub fn begin(&self, gl: &GL) {
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&self.fbo));
+        gl.viewport(0, 0, self.w, self.h);
+        gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        gl.clear(GL::COLOR_BUFFER_BIT);
+    }
+
+    /// Post-process pass to the canvas, upscaling the offscreen targets.
+    pub fn draw(&self, gl: &GL, sp: &PatternParams, fx: &Effects) {
+        self.draw_into(gl, sp, fx, None, self.screen_w, self.screen_h);
+    }
+
+    /// The same pass into `target` (the canvas when `None`), `w` × `h` pixels.
+    pub fn draw_into(&self, gl: &GL, sp: &PatternParams, fx: &Effects, target: Option<&WebGlFramebuffer>, w: i32, h: i32) {
+        gl.bind_framebuffer(GL::FRAMEBUFFER, target);
+        gl.viewport(0, 0, w, h);
+        gl.use_program(Some(&self.prog.prog));
+
+        // uniforms (resolution/time come from the Globals UBO)
+        let u = &self.u;
+        gl.uniform2f(u.u_texel.as_ref(), 1.0 / self.w as f32, 1.0 / self.h as f32);
+        // stripe params
+        gl.uniform1f(u.u_stripe_theta0.as_ref(), sp.theta0);
+        gl.uniform1f(u.u_stripe_theta_speed.as_ref(), sp.theta_speed);
+        gl.uniform1f(u.u_stripe_density.as_ref(), sp.density);
+        gl.uniform1f(u.u_stripe_thickness.as_ref(), sp.thickness);
+        gl.uniform2f(u.u_stripe_drift_speed.as_ref(), sp.drift_x, sp.drift_y);
+        gl.uniform1f(u.u_color_speed.as_ref(), sp.color_speed);
+        // polka
+        gl.uniform1f(u.u_fill_mode.as_ref(), if sp.mode_polka { 1.0 } else { 0.0 });
+        gl.uniform1f(u.u_dot_theta0.as_ref(), sp.dot_theta0);
+        gl.uniform1f(u.u_dot_theta_speed.as_ref(), sp.dot_theta_speed);
+        gl.uniform2f(u.u_dot_drift_speed.as_ref(), sp.dot_drift_x, sp.dot_drift_y);
+        gl.uniform1f(u.u_dot_density.as_ref(), sp.dot_density);
+        gl.uniform1f(u.u_dot_radius_min.as_ref(), sp.dot_rmin);
+        gl.uniform1f(u.u_dot_radius_max.as_ref(), sp.dot_rmax);
+        gl.uniform1f(u.u_contrast.as_ref(), fx.contrast);
+        gl.uniform1f(u.u_motion.as_ref(), fx.motion);
+        gl.active_texture(GL::TEXTURE0);
+        gl.bind_texture(GL::TEXTURE_2D, Some(&self.tex_scene));
+        gl.active_texture(GL::TEXTURE1);
+        gl.bind_texture(GL::TEXTURE_2D, Some(&self.tex_mask));
+
+        // geometry
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.vbo));
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_with_i32(0, 2, GL::FLOAT, false, 0, 0);
+        gl.draw_arrays(GL::TRIANGLES, 0, 3);
+        gl.disable_vertex_attrib_array(0);
+    }
+}
+
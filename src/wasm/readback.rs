@@ -0,0 +1,137 @@
+#![cfg(target_arch = "wasm32")]
+
+//! Small asynchronous readback of the final image for the flash analyzer.
+//!
+//! The post pass is drawn a second time into a `SIZE` × `SIZE` texture whose
+//! mip chain averages it down to `READ_SIZE` × `READ_SIZE`; that level is read
+//! into one of a few pixel-pack buffers behind a fence, and fetched once the
+//! fence has signalled, so the CPU never waits on the GPU.
+
+use web_sys::{WebGl2RenderingContext as GL, WebGlBuffer, WebGlFramebuffer, WebGlSync, WebGlTexture};
+
+use super::error::VizError;
+use super::glutil::{delete, track, Pending};
+
+/// Size of the copy of the frame the post pass draws.
+pub const SIZE: i32 = 128;
+/// Mip level read back: `SIZE >> LEVEL` pixels square.
+const LEVEL: i32 = 2;
+pub const READ_SIZE: i32 = SIZE >> LEVEL;
+/// Captures that may be in flight at once; a frame is skipped when all are.
+const RING: usize = 3;
+
+// One pixel-pack buffer and the capture waiting in it.
+struct Slot {
+    pbo: WebGlBuffer,
+    // Signals once the copy into `pbo` is complete
+    fence: Option<WebGlSync>,
+    // Time of the frame waiting in `pbo`
+    t_ms: f64,
+}
+
+pub struct Readback {
+    tex: WebGlTexture,
+    // Level 0, drawn into
+    fbo: WebGlFramebuffer,
+    // Level `LEVEL`, read from
+    read_fbo: WebGlFramebuffer,
+    ring: Vec<Slot>,
+    // Oldest capture still to be taken, and the slot the next one goes into
+    oldest: usize,
+    next: usize,
+    pixels: Vec<u8>,
+}
+
+impl Readback {
+    pub fn new(gl: &GL) -> Result<Self, VizError> {
+        let mut made = Pending::new(gl);
+        let tex = made.add(track(gl.create_texture()).ok_or(VizError::Resource("readback texture"))?);
+        gl.bind_texture(GL::TEXTURE_2D, Some(&tex));
+        gl.tex_storage_2d(GL::TEXTURE_2D, LEVEL + 1, GL::RGBA8, SIZE, SIZE);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::LINEAR_MIPMAP_LINEAR as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAX_LEVEL, LEVEL);
+        gl.bind_texture(GL::TEXTURE_2D, None);
+
+        let fbo = made.add(track(gl.create_framebuffer()).ok_or(VizError::Resource("readback framebuffer"))?);
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&fbo));
+        gl.framebuffer_texture_2d(GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0, GL::TEXTURE_2D, Some(&tex), 0);
+        let read_fbo = made.add(track(gl.create_framebuffer()).ok_or(VizError::Resource("readback framebuffer"))?);
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&read_fbo));
+        gl.framebuffer_texture_2d(GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0, GL::TEXTURE_2D, Some(&tex), LEVEL);
+        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+
+        let bytes = (READ_SIZE * READ_SIZE * 4) as usize;
+        let mut ring = Vec::with_capacity(RING);
+        for _ in 0..RING {
+            let pbo = made.add(track(gl.create_buffer()).ok_or(VizError::Resource("pixel pack buffer"))?);
+            gl.bind_buffer(GL::PIXEL_PACK_BUFFER, Some(&pbo));
+            gl.buffer_data_with_i32(GL::PIXEL_PACK_BUFFER, bytes as i32, GL::STREAM_READ);
+            ring.push(Slot { pbo, fence: None, t_ms: 0.0 });
+        }
+        gl.bind_buffer(GL::PIXEL_PACK_BUFFER, None);
+
+        made.done();
+        Ok(Self { tex, fbo, read_fbo, ring, oldest: 0, next: 0, pixels: vec![0; bytes] })
+    }
+
+    pub fn delete(&self, gl: &GL) {
+        delete(gl, &self.tex);
+        delete(gl, &self.fbo);
+        delete(gl, &self.read_fbo);
+        for slot in &self.ring {
+            delete(gl, &slot.pbo);
+            if let Some(fence) = &slot.fence {
+                delete(gl, fence);
+            }
+        }
+    }
+
+    /// Framebuffer to draw the `SIZE` × `SIZE` copy into.
+    pub fn target(&self) -> &WebGlFramebuffer {
+        &self.fbo
+    }
+
+    /// Start reading back what was drawn into [`Readback::target`] for the frame at `t_ms`.
+    pub fn capture(&mut self, gl: &GL, t_ms: f64) {
+        let slot = &mut self.ring[self.next];
+        if slot.fence.is_some() {
+            // The GPU is `RING` frames behind; the analyzer copes with a gap
+            return;
+        }
+        gl.bind_texture(GL::TEXTURE_2D, Some(&self.tex));
+        gl.generate_mipmap(GL::TEXTURE_2D);
+        gl.bind_texture(GL::TEXTURE_2D, None);
+        gl.bind_framebuffer(GL::READ_FRAMEBUFFER, Some(&self.read_fbo));
+        gl.bind_buffer(GL::PIXEL_PACK_BUFFER, Some(&slot.pbo));
+        let ok = gl.read_pixels_with_i32(0, 0, READ_SIZE, READ_SIZE, GL::RGBA, GL::UNSIGNED_BYTE, 0).is_ok();
+        gl.bind_buffer(GL::PIXEL_PACK_BUFFER, None);
+        gl.bind_framebuffer(GL::READ_FRAMEBUFFER, None);
+        if !ok {
+            return;
+        }
+        slot.fence = track(gl.fence_sync(GL::SYNC_GPU_COMMANDS_COMPLETE, 0));
+        slot.t_ms = t_ms;
+        if slot.fence.is_some() {
+            self.next = (self.next + 1) % RING;
+        }
+    }
+
+    /// The oldest finished capture as `READ_SIZE` × `READ_SIZE` RGBA rows
+    /// (bottom first) with its frame time, once. `None` while the GPU is still
+    /// copying it.
+    pub fn take(&mut self, gl: &GL) -> Option<(f64, &[u8])> {
+        let slot = &mut self.ring[self.oldest];
+        let fence = slot.fence.as_ref()?;
+        let status = gl.client_wait_sync_with_u32(fence, 0, 0);
+        if status != GL::ALREADY_SIGNALED && status != GL::CONDITION_SATISFIED {
+            return None;
+        }
+        delete(gl, fence);
+        slot.fence = None;
+        gl.bind_buffer(GL::PIXEL_PACK_BUFFER, Some(&slot.pbo));
+        gl.get_buffer_sub_data_with_i32_and_u8_array(GL::PIXEL_PACK_BUFFER, 0, &mut self.pixels);
+        gl.bind_buffer(GL::PIXEL_PACK_BUFFER, None);
+        self.oldest = (self.oldest + 1) % RING;
+        Some((slot.t_ms, &self.pixels))
+    }
+}
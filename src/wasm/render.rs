@@ -1,778 +1,1296 @@
-
 #![cfg(target_arch = "wasm32")]
 
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
 use wasm_bindgen::{closure::Closure, JsCast, JsValue};
-use web_sys::{
-    window, HtmlCanvasElement, WebGl2RenderingContext as GL, WebGlProgram, WebGlShader,
-    WebGlTexture, WebGlFramebuffer
-};
-
-/// Start render loop – placeholder draws clear color changing.
-pub fn start(canvas: HtmlCanvasElement) -> Result<(), JsValue> {
-    use std::cell::RefCell;
-    use std::rc::Rc;
-
-    let gl: GL = canvas
-        .get_context("webgl2")?
-        .ok_or("WebGL2 not supported")?
-        .dyn_into()?;
-
-    // Helper to match the canvas size & WebGL viewport to the current window size.
-    // Doing this via a small closure keeps the logic in one place so we can invoke
-    // it both on start-up and for every resize event.
-    let adjust_size = |canvas: &HtmlCanvasElement, gl: &GL| {
-        let win = window().unwrap();
-        let css_w = win.inner_width().unwrap().as_f64().unwrap();
-        let css_h = win.inner_height().unwrap().as_f64().unwrap();
-        let dpr = win.device_pixel_ratio();
-
-        let px_w = (css_w * dpr).round() as u32;
-        let px_h = (css_h * dpr).round() as u32;
-
-        // Only update if a change is actually required to avoid needless work.
-        if canvas.width() != px_w || canvas.height() != px_h {
-            let elem: &web_sys::HtmlElement = canvas.unchecked_ref();
-            let style = elem.style();
-            let _ = style.set_property("width", &format!("{}px", css_w));
-            let _ = style.set_property("height", &format!("{}px", css_h));
-
-            canvas.set_width(px_w);
-            canvas.set_height(px_h);
-            gl.viewport(0, 0, px_w as i32, px_h as i32);
-        }
-    };
+use web_sys::{window, Element, Event, EventTarget, HtmlCanvasElement, WebGl2RenderingContext as GL};
+
+use super::error::VizError;
+use super::glutil::{Globals, GlobalsUbo};
+use super::hud::{Hud, Pass, PassTimer};
+use super::options::Options;
+use super::post::{self, Post};
+use super::readback::{self, Readback};
+use super::scenes::{self, View, Visualizer};
+use super::shaders::{self, ShaderLibrary, Watcher};
+use super::silhouette::SilhouetteScene;
+use super::store;
+use super::sync::{self, SyncLink};
+use super::text::TextScene;
+use super::video::VideoScene;
+use crate::anim::Animation;
+use crate::clock::Clock;
+use crate::gesture::{Gesture, GestureRecognizer};
+use crate::keymap::{Command, Keymap};
+use crate::motion::{self, MotionPref, CALM_DURATION};
+use crate::particles::ParticleConfig;
+use crate::pattern::{PatternParams, Rng};
+use crate::query::Fill;
+use crate::safety::{FlashAnalyzer, Limits, SafetyLimiter, UNLIMITED_FLASH_HZ};
+use crate::perf::{AdaptiveScale, FrameMonitor, FrameStats, ScaleConfig};
+use crate::schedule::{Entry, Playlist};
+use crate::settings::{Calibration, Preset, Presets, Settings, MAX_SLOTS};
+use crate::show::{ClockState, Effects, ShowState};
+use crate::sync::{same_show, Role, SyncState};
+
+// Overlay label for scene `idx` of `len`, e.g. "2/5 Rotating Square".
+fn scene_label(idx: usize, len: usize, name: &str) -> String {
+    format!("{}/{} {}", idx + 1, len, name)
+}
+
+// Write the stats line into `elem`, hiding it when `text` is `None`.
+fn set_stats_text(elem: Option<&Element>, text: Option<&str>) {
+    let Some(elem) = elem else { return };
+    elem.set_text_content(text);
+    let elem: &web_sys::HtmlElement = elem.unchecked_ref();
+    let _ = elem.style().set_property("display", if text.is_some() { "block" } else { "none" });
+}
+
+const REDUCED_MOTION_QUERY: &str = "(prefers-reduced-motion: reduce)";
+
+fn prefers_reduced_motion() -> bool {
+    window()
+        .and_then(|w| w.match_media(REDUCED_MOTION_QUERY).ok().flatten())
+        .is_some_and(|q| q.matches())
+}
+
+fn now_ms() -> f64 {
+    window().and_then(|w| w.performance()).map(|p| p.now()).unwrap_or(0.0)
+}
+
+/// Flash measurement and the limiter it drives, present while safety mode is on.
+struct Safety {
+    readback: Readback,
+    analyzer: FlashAnalyzer,
+    limiter: SafetyLimiter,
+}
+
+/// One visualizer instance: GL state, loaded scenes and the playlist cursor.
+pub struct Renderer {
+    canvas: HtmlCanvasElement,
+    gl: GL,
+    fill_window: bool,
+    overlay: Option<Element>,
+    post: Post,
+    globals: GlobalsUbo,
+    timer: PassTimer,
+    // Frame-time monitor driving the dynamic resolution of the offscreen targets
+    monitor: FrameMonitor,
+    scaler: AdaptiveScale,
+    last_stats_ms: f64,
+    // One-line readout, written while `show_stats` is on
+    stats: Option<Element>,
+    show_stats: bool,
+    scale_config: ScaleConfig,
+    hud: Hud,
+    // Shader sources: embedded copies, refreshed from `static/shaders/` by the watcher
+    lib: Rc<RefCell<ShaderLibrary>>,
+    shaders_changed: Rc<RefCell<Vec<String>>>,
+    watcher: Option<Watcher>,
+    scenes: Vec<Box<dyn Visualizer>>,
+    playlist: Playlist,
+    autoplay: bool,
+    // Parameters controlling fill patterns, re-rolled on each scene change
+    params: PatternParams,
+    // Keep `params` when the scene changes
+    params_locked: bool,
+    keymap: Keymap,
+    // Draw black instead of the scene
+    blackout: bool,
+    // Stop drawing, so the canvas keeps its last frame
+    frozen: bool,
+    help_visible: bool,
+    overlay_hidden: bool,
+    gestures: GestureRecognizer,
+    // Pinch zoom and rotation, kept across scene changes
+    view: View,
+    // Current entry's curves over its scene's defaults
+    anim: Animation,
+    // Seeded source for `params`; `Math.random` when `None`
+    rng: Option<Rng>,
+    fill: Option<Fill>,
+    // Drives both the scene time and `Globals::time`
+    clock: Clock,
+    // Clock time at which the current playlist entry started
+    segment_start: f64,
+    // Shown under the first scene label so it stays visible for a whole segment
+    init_notice: Option<String>,
+    // `localStorage` key the settings are saved under after every change
+    storage: Option<String>,
+    presets: Presets,
+    // Preset files read by the import picker, merged on the next frame
+    imported: Rc<RefCell<Vec<String>>>,
+    safety: Option<Safety>,
+    motion: MotionPref,
+    // Calm profile in use: `motion` resolved against `prefers-reduced-motion`
+    reduced_motion: bool,
+    sync: Option<SyncLink>,
+    // Latest leader state; followers derive their playback time from it
+    sync_anchor: Option<SyncState>,
+    // Set once `release` has deleted the GL objects; nothing is drawn after that.
+    released: bool,
+}
 
-    // Initial sizing so the canvas fits the window immediately.
-    adjust_size(&canvas, &gl);
-
-    // Offscreen framebuffer for post-processing
-    struct Post {
-        prog: WebGlProgram,
-        vbo: web_sys::WebGlBuffer,
-        fbo_scene: WebGlFramebuffer,
-        tex_scene: WebGlTexture,
-        fbo_mask: WebGlFramebuffer,
-        tex_mask: WebGlTexture,
-        w: i32,
-        h: i32,
-    }
-
-    impl Post {
-        fn new(gl: &GL, w: i32, h: i32) -> Result<Self, JsValue> {
-            let vsrc = r#"#version 300 es
-            layout(location=0) in vec2 a_pos;
-            void main(){ gl_Position = vec4(a_pos,0.0,1.0); }
-            "#;
-            let fsrc = r#"#version 300 es
-            precision mediump float;
-            out vec4 o;
-            uniform sampler2D u_src;
-            uniform vec2 u_resolution;
-            uniform float u_time;
-
-            // Hash/Noise helpers
-            float hash(vec2 p){ return fract(sin(dot(p, vec2(127.1,311.7))) * 43758.5453123); }
-
-            vec3 sample_src(vec2 uv){
-                // subtle chromatic aberration based on distance from center
-                vec2 c = uv - 0.5;
-                float r = length(c);
-                float ca = 0.002 * r;
-                vec3 col;
-                col.r = texture(u_src, uv + ca * normalize(c)).r;
-                col.g = texture(u_src, uv).g;
-                col.b = texture(u_src, uv - ca * normalize(c)).b;
-                return col;
+impl Renderer {
+    fn new(canvas: HtmlCanvasElement, opts: Options) -> Result<Self, VizError> {
+        let gl: GL = canvas
+            .get_context("webgl2")?
+            .ok_or_else(|| VizError::ContextUnavailable("webgl2".into()))?
+            .dyn_into()
+            .map_err(|_| VizError::ContextUnavailable("webgl2".into()))?;
+        fit_canvas(&canvas, &gl, opts.fill_window);
+
+        let videos = opts.video.iter().map(|c| Box::new(VideoScene::new(c.clone())) as Box<dyn Visualizer>);
+        let images = opts.image.iter().map(|c| Box::new(SilhouetteScene::new(c.clone())) as Box<dyn Visualizer>);
+        let texts = opts.text.iter().map(|c| Box::new(TextScene::new(c.clone())) as Box<dyn Visualizer>);
+        let extra = videos.chain(images).chain(texts).collect();
+        let mut scenes = select_scenes(opts.scenes.as_deref(), &opts.particles, extra)?;
+        let lib = Rc::new(RefCell::new(ShaderLibrary::embedded()));
+        let shaders_changed: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(Vec::new()));
+        // Neither holds GL objects, so failing here has nothing to clean up
+        let watcher = shaders::watch(lib.clone(), shaders_changed.clone())?;
+        let sync = opts
+            .sync
+            .map(|role| SyncLink::start(role, &opts.sync_url.clone().unwrap_or_else(sync::default_url)))
+            .transpose()?;
+        // A leader always rolls from a seed, published with its snapshots, so
+        // followers draw the same patterns on the same frame
+        let seed = match opts.sync {
+            Some(Role::Leader) => Some(opts.seed.unwrap_or_else(|| {
+                let half = || (js_sys::Math::random() * 4_294_967_296.0) as u64;
+                half() << 32 | half()
+            })),
+            _ => opts.seed,
+        };
+
+        // A scene that fails to initialise (driver quirk, shader the GPU rejects) is
+        // left out of the rotation rather than taking the whole module down.
+        let mut skipped: Vec<String> = Vec::new();
+        let mut skipped_names: Vec<&'static str> = Vec::new();
+        scenes.retain_mut(|v| match v.init(&gl, &lib.borrow()) {
+            Ok(()) => true,
+            Err(e) => {
+                v.destroy(&gl);
+                skipped.push(format!("{}: {e}", v.name()));
+                skipped_names.push(v.name());
+                false
             }
+        });
+        let init_notice = (!skipped.is_empty())
+            .then(|| format!("skipped {} scene(s) that failed to initialise:\n{}", skipped.len(), skipped.join("\n")));
+        if let Some(notice) = &init_notice {
+            web_sys::console::error_1(&notice.into());
+        }
 
-            void main(){
-                vec2 uv = gl_FragCoord.xy / u_resolution;
-                vec2 center = vec2(0.5);
-                vec2 p = (uv - center);
-
-                // Accumulate displacement
-                vec2 disp = vec2(0.0);
-
-                // 1) Waves – large-scale ripple across screen
-                float wave = sin(uv.y*12.0 + u_time*1.5) * 0.003;
-                wave += sin((uv.x+uv.y)*10.0 - u_time*1.2) * 0.002;
-                disp += vec2(wave, 0.0);
-
-                // 2) Warp spirals – two drifting centers
-                vec2 s1 = vec2(0.3+0.2*sin(u_time*0.4), 0.4+0.2*cos(u_time*0.35));
-                vec2 s2 = vec2(0.7+0.2*cos(u_time*0.37), 0.6+0.2*sin(u_time*0.31));
-                for(int i=0;i<2;i++){
-                    vec2 c = (i==0)? s1 : s2;
-                    vec2 d = uv - c;
-                    float r = length(d)+1e-4;
-                    float ang = 0.15 * sin(u_time*0.8 + r*25.0);
-                    mat2 rot = mat2(cos(ang),-sin(ang),sin(ang),cos(ang));
-                    disp += (rot * d - d) * smoothstep(0.25, 0.0, r);
+        // Until the renderer exists to release them, an error here has to free
+        // the scenes' (and the post pass') GL objects itself.
+        let built = (|| -> Result<_, VizError> {
+            let playlist = match &opts.playlist {
+                None => Playlist::sequential(scenes.len(), opts.duration_ms),
+                Some(specs) => {
+                    let mut entries = Vec::with_capacity(specs.len());
+                    for spec in specs {
+                        let duration_ms = spec.duration_ms.unwrap_or(opts.duration_ms);
+                        match scenes.iter().position(|v| v.name() == spec.scene) {
+                            Some(scene) => entries.push(Entry { scene, duration_ms, anim: spec.anim.clone() }),
+                            // Already reported in `init_notice`
+                            None if skipped_names.contains(&spec.scene.as_str()) => {}
+                            None => return Err(VizError::UnknownScene(spec.scene.clone())),
+                        }
+                    }
+                    Playlist::new(entries)
                 }
+            };
+            let post = Post::new(&gl, &lib.borrow(), gl.drawing_buffer_width(), gl.drawing_buffer_height())?;
+            // Per-frame globals shared by every program through one UBO
+            let globals = GlobalsUbo::new(&gl).inspect_err(|_| post.delete(&gl))?;
+            Ok((playlist, post, globals))
+        })();
+        let (playlist, post, globals) = match built {
+            Ok(built) => built,
+            Err(e) => {
+                for v in scenes.iter_mut() {
+                    v.destroy(&gl);
+                }
+                return Err(e);
+            }
+        };
+        let timer = PassTimer::new(&gl);
+
+        let mut r = Self {
+            canvas,
+            gl,
+            fill_window: opts.fill_window,
+            overlay: opts.overlay,
+            post,
+            globals,
+            timer,
+            monitor: FrameMonitor::default(),
+            scaler: AdaptiveScale::default(),
+            last_stats_ms: 0.0,
+            stats: opts.stats,
+            show_stats: false,
+            scale_config: opts.scale_config,
+            hud: Hud::new(opts.hud),
+            lib,
+            shaders_changed,
+            watcher: Some(watcher),
+            scenes,
+            playlist,
+            autoplay: opts.autoplay,
+            params: opts.locked_params.unwrap_or_default(),
+            params_locked: opts.locked_params.is_some(),
+            keymap: opts.keymap,
+            blackout: false,
+            frozen: false,
+            help_visible: false,
+            overlay_hidden: !opts.show_overlay,
+            gestures: GestureRecognizer::default(),
+            view: opts.view,
+            anim: Animation::default(),
+            rng: seed.map(Rng::new),
+            fill: opts.fill,
+            clock: Clock::default(),
+            segment_start: 0.0,
+            init_notice,
+            storage: opts.storage,
+            presets: opts.presets,
+            imported: Rc::new(RefCell::new(Vec::new())),
+            safety: None,
+            motion: opts.motion,
+            reduced_motion: opts.motion.reduced(prefers_reduced_motion()),
+            sync,
+            sync_anchor: None,
+            released: false,
+        };
+        // From here on dropping `r` releases everything
+        if opts.safety {
+            r.set_safety(true)?;
+        }
+        r.clock.tick(now_ms());
+        r.style_overlay();
+        match (&opts.start_scene, &opts.stored_scene) {
+            (Some(name), _) => r.goto(name)?,
+            // A stored scene that no longer exists must not keep the page from starting
+            (None, Some(name)) => {
+                if let Err(e) = r.goto(name) {
+                    web_sys::console::warn_1(&format!("stored scene: {e}; starting at the top").into());
+                    r.enter();
+                }
+            }
+            (None, None) => r.enter(),
+        }
+        Ok(r)
+    }
+
+    /// Stop the shader watcher and delete every GL object this instance created.
+    /// Idempotent; the renderer is inert afterwards.
+    fn release(&mut self) {
+        if std::mem::replace(&mut self.released, true) {
+            return;
+        }
+        self.watcher = None;
+        self.sync = None;
+        let gl = &self.gl;
+        for v in self.scenes.iter_mut() {
+            v.destroy(gl);
+        }
+        self.post.delete(gl);
+        self.globals.delete(gl);
+        self.hud.remove();
+        if self.show_stats {
+            set_stats_text(self.stats.as_ref(), None);
+        }
+        self.timer.delete(gl);
+        if let Some(s) = self.safety.take() {
+            s.readback.delete(gl);
+        }
+    }
+
+    fn show(&self, text: &str) {
+        if self.help_visible {
+            return;
+        }
+        if let Some(elem) = &self.overlay {
+            elem.set_text_content(Some(text));
+        }
+    }
+
+    // Start the current playlist entry from the top.
+    fn enter(&mut self) {
+        self.segment_start = self.clock.now();
+        if let Some(entry) = self.playlist.current() {
+            self.anim = entry.anim.over(&self.scenes[entry.scene].animation());
+        }
+        if !self.params_locked {
+            self.randomize();
+        }
+        let label = self.label();
+        match (label, self.init_notice.take()) {
+            (Some(label), Some(notice)) => self.show(&format!("{label}\n{notice}")),
+            (Some(label), None) => self.show(&label),
+            (None, Some(notice)) => self.show(&notice),
+            (None, None) => {}
+        }
+        self.persist();
+    }
+
+    fn randomize(&mut self) {
+        self.params = match &mut self.rng {
+            Some(rng) => PatternParams::random(|| rng.next_f32()),
+            None => PatternParams::random(|| js_sys::Math::random() as f32),
+        };
+        if let Some(fill) = self.fill {
+            self.params.mode_polka = fill == Fill::Polka;
+        }
+    }
+
+    // Scene label, plus the clock state when it is not simply running.
+    fn label(&self) -> Option<String> {
+        let name = self.scene_name()?;
+        let mut label = scene_label(self.playlist.position(), self.playlist.len(), name);
+        if self.clock.paused() {
+            label += &format!("\npaused at {:.2} s", self.scene_time_ms() / 1000.0);
+        }
+        if self.clock.speed() != 1.0 {
+            label += &format!("\nspeed x{}", self.clock.speed());
+        }
+        let flags: Vec<&str> = [
+            (self.params_locked, "pattern locked"),
+            (self.blackout, "blackout"),
+            (self.frozen, "frozen"),
+            (self.safety.is_some(), "flash limiter"),
+            (self.reduced_motion, "reduced motion"),
+            (self.sync_role() == Some(Role::Leader), "sync leader"),
+            (self.sync_role() == Some(Role::Follower), "sync follower"),
+        ]
+        .into_iter()
+        .filter_map(|(on, name)| on.then_some(name))
+        .collect();
+        if !flags.is_empty() {
+            label += &format!("\n{}", flags.join(", "));
+        }
+        Some(label)
+    }
+
+    fn show_label(&self) {
+        if let Some(label) = self.label() {
+            self.show(&label);
+        }
+    }
+
+    /// Name of the scene on screen.
+    pub fn scene_name(&self) -> Option<&'static str> {
+        self.playlist.current().map(|e| self.scenes[e.scene].name())
+    }
+
+    pub fn next(&mut self) {
+        if self.playlist.next_entry().is_some() {
+            self.enter();
+        }
+    }
+
+    pub fn prev(&mut self) {
+        if self.playlist.prev_entry().is_some() {
+            self.enter();
+        }
+    }
+
+    /// Jump to the first playlist entry showing the scene called `name`.
+    pub fn goto(&mut self, name: &str) -> Result<(), VizError> {
+        let pos = self
+            .scenes
+            .iter()
+            .position(|v| v.name() == name)
+            .and_then(|scene| self.playlist.find_scene(scene))
+            .ok_or_else(|| VizError::UnknownScene(name.into()))?;
+        self.playlist.goto(pos);
+        self.enter();
+        Ok(())
+    }
+
+    /// Freeze scene time and the playlist; frames keep being drawn.
+    pub fn pause(&mut self) {
+        self.clock.set_paused(true);
+        self.show_label();
+    }
 
-                // 3) Bubbles – wobbling radial in/out around moving seeds
-                for(int i=0; i<3; ++i){
-                    vec2 seed = vec2(hash(vec2(float(i),0.123)), hash(vec2(float(i)+2.3,4.2)));
-                    seed = 0.2 + 0.6*seed + 0.05*vec2(sin(u_time*(1.0+float(i)*0.3)+float(i)), cos(u_time*(1.2+float(i)*0.17)+float(i)));
-                    vec2 d = uv - seed;
-                    float r = length(d);
-                    float r0 = 0.18 + 0.05*sin(u_time*1.7+float(i));
-                    float amp = 0.008 * sin((r-r0)*40.0 - u_time*3.0);
-                    disp += normalize(d) * amp * smoothstep(r0, 0.0, r);
+    pub fn resume(&mut self) {
+        self.clock.set_paused(false);
+        self.show_label();
+    }
+
+    pub fn paused(&self) -> bool {
+        self.clock.paused()
+    }
+
+    /// Pause and move `frames` frames forward (or back when negative), staying
+    /// within the current entry. Stepping past its end moves to the next entry
+    /// when autoplay is on.
+    pub fn step(&mut self, frames: i32) {
+        self.clock.step(frames);
+        self.clock.seek(self.clock.now().max(self.segment_start));
+        self.show_label();
+    }
+
+    // How long `entry` stays up; longer in the calm profile.
+    fn duration_ms(&self, entry: &Entry) -> f64 {
+        if self.reduced_motion { entry.duration_ms * CALM_DURATION } else { entry.duration_ms }
+    }
+
+    /// Reduce motion always (`Reduce`), never (`Full`) or when the system asks
+    /// for it (`Auto`).
+    pub fn set_motion(&mut self, pref: MotionPref) {
+        self.motion = pref;
+        self.reduced_motion = pref.reduced(prefers_reduced_motion());
+        self.show_label();
+    }
+
+    pub fn reduced_motion(&self) -> bool {
+        self.reduced_motion
+    }
+
+    // The system setting changed; only matters when following it.
+    fn on_motion_query(&mut self, reduce: bool) {
+        self.reduced_motion = self.motion.reduced(reduce);
+        self.show_label();
+    }
+
+    /// Time into the current entry, in milliseconds.
+    pub fn scene_time_ms(&self) -> f64 {
+        self.clock.now() - self.segment_start
+    }
+
+    /// Scrub to `ms` into the current entry, clamped to its duration.
+    pub fn seek(&mut self, ms: f64) {
+        let duration = self.playlist.current().map_or(0.0, |e| self.duration_ms(e));
+        if ms.is_finite() {
+            self.clock.seek(self.segment_start + ms.clamp(0.0, duration));
+            self.show_label();
+        }
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.clock.speed()
+    }
+
+    /// Update the limits used by the adaptive render scale.
+    pub fn set_scale_config(&mut self, cfg: ScaleConfig) {
+        self.scale_config = cfg;
+        self.persist();
+    }
+
+    /// Show or hide the FPS / render-scale stats line.
+    pub fn set_stats_visible(&mut self, visible: bool) {
+        self.show_stats = visible;
+        self.last_stats_ms = 0.0;
+        if !visible {
+            set_stats_text(self.stats.as_ref(), None);
+        }
+    }
+
+    pub fn hud_visible(&self) -> bool {
+        self.hud.visible()
+    }
+
+    pub fn set_hud_visible(&mut self, visible: bool) {
+        self.hud.set_visible(visible);
+        self.persist();
+    }
+
+    /// Latest frame statistics as a plain JS object.
+    pub fn frame_stats(&self) -> JsValue {
+        self.hud.stats_js()
+    }
+
+    /// Playback speed factor, clamped to `0..=MAX_SPEED`.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.clock.set_speed(speed);
+        self.show_label();
+    }
+
+    fn resize(&mut self) {
+        if !self.released && fit_canvas(&self.canvas, &self.gl, self.fill_window) {
+            let (w, h) = (self.gl.drawing_buffer_width(), self.gl.drawing_buffer_height());
+            let _ = self.post.resize(&self.gl, w, h);
+        }
+    }
+
+    // Space: next scene; P: pause/resume; , and .: step one frame back/forward;
+    // arrows: scrub one second; [ and ]: halve/double the speed.
+    fn on_key(&mut self, ev: &web_sys::KeyboardEvent) {
+        if ev.ctrl_key() || ev.meta_key() || ev.alt_key() {
+            return;
+        }
+        if let Some(el) = ev.target().and_then(|t| t.dyn_into::<Element>().ok()) {
+            let tag = el.tag_name();
+            if tag == "INPUT" || tag == "TEXTAREA" || el.get_attribute("contenteditable").is_some() {
+                return;
+            }
+        }
+        let key = ev.key();
+        // Shift+F1 etc.; for printable keys Shift already shows in `key`
+        let chord = (ev.shift_key() && key.chars().count() > 1).then(|| format!("Shift+{key}"));
+        let Some(cmd) = chord.and_then(|c| self.keymap.lookup(&c)).or_else(|| self.keymap.lookup(&key)) else {
+            return;
+        };
+        ev.prevent_default();
+        self.run(cmd);
+    }
+
+    /// Carry out a keymap command.
+    pub fn run(&mut self, cmd: Command) {
+        match cmd {
+            Command::Next => self.next(),
+            Command::Prev => self.prev(),
+            Command::Scene(n) => {
+                if n <= self.playlist.len() {
+                    self.playlist.goto(n - 1);
+                    self.enter();
+                }
+            }
+            Command::ToggleFill => self.params.mode_polka = !self.params.mode_polka,
+            Command::Randomize => self.randomize(),
+            Command::LockParams => self.params_locked = !self.params_locked,
+            Command::Blackout => self.blackout = !self.blackout,
+            Command::Freeze => self.frozen = !self.frozen,
+            Command::Pause => if self.paused() { self.resume() } else { self.pause() },
+            Command::StepBack => self.step(-1),
+            Command::StepForward => self.step(1),
+            Command::ScrubBack => self.seek(self.scene_time_ms() - 1000.0),
+            Command::ScrubForward => self.seek(self.scene_time_ms() + 1000.0),
+            Command::Slower => self.set_speed((self.speed() / 2.0).max(1.0 / 8.0)),
+            Command::Faster => self.set_speed(if self.speed() > 0.0 { self.speed() * 2.0 } else { 1.0 }),
+            Command::ResetView => self.view = View::default(),
+            Command::ToggleHud => self.set_hud_visible(!self.hud.visible()),
+            Command::ToggleOverlay => {
+                self.overlay_hidden = !self.overlay_hidden;
+                self.style_overlay();
+            }
+            Command::Preset(n) => {
+                match self.presets.slot(n).cloned() {
+                    Some(preset) => self.apply_preset(&preset),
+                    None => self.show(&format!("no preset {n}")),
                 }
+                return;
+            }
+            Command::SavePreset(n) => {
+                if !self.presets.save_slot(n, self.preset(String::new())) {
+                    self.show(&format!("no preset slot {n} (1 to {MAX_SLOTS})"));
+                    return;
+                }
+                let name = self.presets.slot(n).map_or_else(String::new, |p| p.name.clone());
+                self.show(&format!("saved preset \"{name}\""));
+                self.persist();
+                return;
+            }
+            Command::ExportPresets => {
+                if let Err(e) = self.export_presets() {
+                    web_sys::console::error_1(&e.to_string().into());
+                }
+            }
+            Command::ImportPresets => {
+                if let Err(e) = store::pick_file(self.imported.clone()) {
+                    web_sys::console::error_1(&e.to_string().into());
+                }
+            }
+            Command::ToggleSafety => {
+                if let Err(e) = self.set_safety(self.safety.is_none()) {
+                    web_sys::console::error_1(&e.to_string().into());
+                }
+            }
+            Command::Help => self.toggle_help(),
+        }
+        self.show_label();
+        self.persist();
+    }
 
-                // Apply displacement
-                vec2 suv = clamp(uv + disp, 0.0, 1.0);
-                vec3 col = sample_src(suv);
-
-                // 4) Edge flame – detect edges via Sobel on displaced UV
-                vec2 px = 1.0 / u_resolution;
-                float l00 = dot(texture(u_src, suv + px*vec2(-1.0,-1.0)).rgb, vec3(0.2126,0.7152,0.0722));
-                float l10 = dot(texture(u_src, suv + px*vec2( 0.0,-1.0)).rgb, vec3(0.2126,0.7152,0.0722));
-                float l20 = dot(texture(u_src, suv + px*vec2( 1.0,-1.0)).rgb, vec3(0.2126,0.7152,0.0722));
-                float l01 = dot(texture(u_src, suv + px*vec2(-1.0, 0.0)).rgb, vec3(0.2126,0.7152,0.0722));
-                float l21 = dot(texture(u_src, suv + px*vec2( 1.0, 0.0)).rgb, vec3(0.2126,0.7152,0.0722));
-                float l02 = dot(texture(u_src, suv + px*vec2(-1.0, 1.0)).rgb, vec3(0.2126,0.7152,0.0722));
-                float l12 = dot(texture(u_src, suv + px*vec2( 0.0, 1.0)).rgb, vec3(0.2126,0.7152,0.0722));
-                float l22 = dot(texture(u_src, suv + px*vec2( 1.0, 1.0)).rgb, vec3(0.2126,0.7152,0.0722));
-                float gx = (l20 + 2.0*l21 + l22) - (l00 + 2.0*l01 + l02);
-                float gy = (l02 + 2.0*l12 + l22) - (l00 + 2.0*l10 + l20);
-                float edge = clamp(length(vec2(gx,gy))*1.5, 0.0, 1.0);
-                float flicker = 0.6 + 0.4*sin(u_time*15.0 + suv.x*30.0 + suv.y*25.0);
-                vec3 flame = vec3(1.0, 0.5, 0.05) * pow(edge, 0.8) * flicker;
-                col = col + flame * 0.6;
-
-                // 5) Solid stripes in low-luminance regions (background)
-                float baseLum = dot(texture(u_src, uv).rgb, vec3(0.2126,0.7152,0.0722));
-                float bands = floor((uv.y + 0.2*sin(u_time*0.25)) * 12.0);
-                if (mod(bands, 2.0) < 1.0 && baseLum < 0.18) {
-                    vec3 stripeCol = vec3(0.06, 0.06, 0.08) + 0.6*vec3(0.25+0.25*sin(u_time+bands*0.15), 0.35+0.2*sin(u_time*0.7), 0.6);
-                    col = stripeCol; // solid fill region
+    /// Turn the photosensitivity limiter on or off. While on, every frame is
+    /// also drawn small and read back to measure flashing.
+    pub fn set_safety(&mut self, on: bool) -> Result<(), VizError> {
+        match (on, self.safety.is_some()) {
+            (true, false) => {
+                let readback = Readback::new(&self.gl)?;
+                self.safety = Some(Safety { readback, analyzer: FlashAnalyzer::default(), limiter: SafetyLimiter::default() });
+            }
+            (false, true) => {
+                if let Some(s) = self.safety.take() {
+                    s.readback.delete(&self.gl);
                 }
+            }
+            _ => {}
+        }
+        self.show_label();
+        Ok(())
+    }
+
+    pub fn safety(&self) -> bool {
+        self.safety.is_some()
+    }
+
+    // Analyse the previous frame's readback and return the limits for this one.
+    fn safety_limits(&mut self) -> Option<Limits> {
+        let s = self.safety.as_mut()?;
+        let size = readback::READ_SIZE as usize;
+        if let Some((t, pixels)) = s.readback.take(&self.gl) {
+            let report = s.analyzer.push(t, pixels, size, size);
+            s.limiter.update(&report);
+        }
+        Some(s.limiter.limits())
+    }
 
-                // Vignette for cohesion
-                float v = smoothstep(0.95, 0.4, length(uv-0.5));
-                col *= v;
+    /// Everything needed to reproduce the current frame.
+    pub fn snapshot(&self) -> ShowState {
+        ShowState {
+            scene: self.scene_name().map(String::from),
+            entry: self.playlist.position(),
+            clock: ClockState {
+                time_ms: self.clock.now(),
+                scene_ms: self.scene_time_ms(),
+                speed: self.clock.speed(),
+                paused: self.clock.paused(),
+            },
+            seed: self.rng.as_ref().map(Rng::state),
+            fill: self.fill,
+            params: self.params,
+            params_locked: self.params_locked,
+            effects: Effects { blackout: self.blackout, frozen: self.frozen, zoom: self.view.zoom, rotation: self.view.rot },
+        }
+    }
 
-                o = vec4(col, 1.0);
+    /// Return to a [`Renderer::snapshot`]. The scene is looked up by name, at
+    /// the same playlist position when that still shows it.
+    pub fn restore(&mut self, state: &ShowState) -> Result<(), VizError> {
+        if let Some(name) = &state.scene {
+            let scene = self
+                .scenes
+                .iter()
+                .position(|v| v.name() == name)
+                .ok_or_else(|| VizError::UnknownScene(name.clone()))?;
+            let pos = match self.playlist.entries().get(state.entry) {
+                Some(e) if e.scene == scene => state.entry,
+                _ => self.playlist.find_scene(scene).ok_or_else(|| VizError::UnknownScene(name.clone()))?,
+            };
+            self.playlist.goto(pos);
+            self.enter();
+        }
+        let c = &state.clock;
+        self.clock.seek(c.time_ms);
+        self.clock.set_speed(c.speed);
+        self.clock.set_paused(c.paused);
+        self.segment_start = c.time_ms - c.scene_ms;
+        self.rng = state.seed.map(Rng::new);
+        self.fill = state.fill;
+        self.params = state.params;
+        self.params_locked = state.params_locked;
+        let e = &state.effects;
+        self.blackout = e.blackout;
+        self.frozen = e.frozen;
+        self.view = View::new(e.zoom, e.rotation);
+        self.show_label();
+        self.persist();
+        Ok(())
+    }
+
+    fn sync_role(&self) -> Option<Role> {
+        self.sync.as_ref().map(SyncLink::role)
+    }
+
+    // Follower: adopt leader states that change the show, then run the clock
+    // from the host's. Returns the playback time once synced.
+    fn follow(&mut self, now: f64) -> Option<f64> {
+        let (host_now, state) = {
+            let link = self.sync.as_ref().filter(|l| l.role() == Role::Follower)?;
+            (link.host_now(now)?, link.take())
+        };
+        if let Some(state) = state {
+            let show = state.at(host_now);
+            // Scene changes already made from the shared clock need no restore
+            if !same_show(&self.snapshot(), &show) {
+                if let Err(e) = self.restore(&show) {
+                    self.show(&e.to_string());
+                }
             }
-            "#;
-
-            // Post fragment shader with stripes clipped by mask
-            let fsrc = r#"#version 300 es
-            precision mediump float;
-            out vec4 o;
-            uniform sampler2D u_src;
-            uniform sampler2D u_mask;
-            uniform vec2 u_resolution;
-            uniform float u_time;
-            uniform float u_stripe_theta0;
-            uniform float u_stripe_theta_speed;
-            uniform float u_stripe_density;
-            uniform float u_stripe_thickness;
-            uniform vec2  u_stripe_drift_speed;
-            uniform float u_color_speed;
-            // Polka dot uniforms
-            uniform float u_fill_mode; // 0 = stripes, 1 = polka
-            uniform float u_dot_theta0;
-            uniform float u_dot_theta_speed;
-            uniform vec2  u_dot_drift_speed;
-            uniform float u_dot_density;       // average dots per unit
-            uniform float u_dot_radius_min;    // min radius in UV units
-            uniform float u_dot_radius_max;    // max radius in UV units
-
-            vec3 sample_src(vec2 uv){
-                vec2 c = uv - 0.5; float r = length(c); float ca = 0.002 * r;
-                vec3 col; col.r = texture(u_src, uv + ca * normalize(c)).r; col.g = texture(u_src, uv).g; col.b = texture(u_src, uv - ca * normalize(c)).b; return col;
+            self.sync_anchor = Some(state);
+        }
+        let t = self.sync_anchor.as_ref()?.playback_ms(host_now);
+        self.clock.seek(t);
+        Some(t)
+    }
+
+    // Leader: post the show whenever it changes.
+    fn lead(&mut self, now: f64) {
+        if self.sync_role() == Some(Role::Leader) {
+            let show = self.snapshot();
+            if let Some(link) = self.sync.as_mut() {
+                link.publish(show, now);
             }
+        }
+    }
+
+    // What gets restored on the next start.
+    fn settings(&self) -> Settings {
+        let render_scale = Some(self.scale_config).filter(|c| *c != ScaleConfig::default());
+        Settings {
+            scene: self.scene_name().map(String::from),
+            locked_params: self.params_locked.then_some(self.params),
+            overlay: !self.overlay_hidden,
+            hud: self.hud.visible(),
+            calibration: Calibration { zoom: self.view.zoom, rotation: self.view.rot, render_scale },
+            presets: self.presets.clone(),
+        }
+    }
 
-            vec3 hsv2rgb(vec3 c){
-                vec3 p = abs(fract(c.xxx + vec3(0.0, 2.0/6.0, 4.0/6.0)) * 6.0 - 3.0);
-                vec3 rgb = c.z * mix(vec3(1.0), clamp(p - 1.0, 0.0, 1.0), c.y);
-                return rgb;
+    fn persist(&self) {
+        if let (Some(key), false) = (&self.storage, self.released) {
+            store::save(key, &self.settings());
+        }
+    }
+
+    // The current look as a preset called `name`.
+    fn preset(&self, name: String) -> Preset {
+        Preset {
+            name,
+            scene: self.scene_name().map(String::from),
+            params: self.params,
+            locked: self.params_locked,
+            fill: self.fill,
+            blackout: self.blackout,
+            speed: self.speed(),
+            zoom: self.view.zoom,
+            rotation: self.view.rot,
+            safety: self.safety(),
+            motion: self.motion,
+        }
+    }
+
+    fn apply_preset(&mut self, preset: &Preset) {
+        if let Some(scene) = &preset.scene {
+            // Presets may come from a setup with other scenes; keep the current one
+            if let Err(e) = self.goto(scene) {
+                web_sys::console::warn_1(&format!("preset \"{}\": {e}", preset.name).into());
             }
+        }
+        self.params = preset.params;
+        self.params_locked = preset.locked;
+        self.fill = preset.fill;
+        self.blackout = preset.blackout;
+        self.clock.set_speed(preset.speed);
+        self.view = View::new(preset.zoom, preset.rotation);
+        self.set_motion(preset.motion);
+        if let Err(e) = self.set_safety(preset.safety) {
+            web_sys::console::warn_1(&format!("preset \"{}\": {e}", preset.name).into());
+        }
+        if let Some(label) = self.label() {
+            self.show(&format!("{label}\npreset \"{}\"", preset.name));
+        }
+        self.persist();
+    }
 
-            // Hash helpers for polka jitter
-            float hash11(float n) { return fract(sin(n)*43758.5453123); }
-            float hash12(vec2 p) { return fract(sin(dot(p, vec2(127.1, 311.7))) * 43758.5453); }
-            vec2  hash22(vec2 p) { return fract(sin(vec2(dot(p,vec2(127.1,311.7)), dot(p,vec2(269.5,183.3))))*43758.5453); }
-
-            void main(){
-                vec2 res = u_resolution;
-                // Compute a centered, square-normalized coordinate uv in [0,1]^2
-                float side = min(res.x, res.y);
-                vec2 origin = 0.5*(res - vec2(side));
-                vec2 uv = (gl_FragCoord.xy - origin) / side;
-                // Outside the centered square: black bars
-                if (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0)))) {
-                    o = vec4(0.0,0.0,0.0,1.0);
-                    return;
+    /// Save the current look (see [`Preset`]) as `name`, replacing any
+    /// preset of that name.
+    pub fn save_preset(&mut self, name: &str) {
+        self.presets.save(self.preset(name.to_string()));
+        self.persist();
+    }
+
+    pub fn recall_preset(&mut self, name: &str) -> Result<(), VizError> {
+        let preset = self.presets.get(name).cloned().ok_or_else(|| VizError::UnknownPreset(name.into()))?;
+        self.apply_preset(&preset);
+        Ok(())
+    }
+
+    pub fn delete_preset(&mut self, name: &str) -> Result<(), VizError> {
+        if !self.presets.remove(name) {
+            return Err(VizError::UnknownPreset(name.into()));
+        }
+        self.persist();
+        Ok(())
+    }
+
+    pub fn presets(&self) -> &Presets {
+        &self.presets
+    }
+
+    /// Add presets from the JSON of an exported file; same names are replaced.
+    pub fn import_presets(&mut self, json: &str) -> Result<usize, VizError> {
+        let presets = Presets::from_json(json).map_err(VizError::InvalidOption)?;
+        let n = presets.len();
+        self.presets.merge(presets);
+        self.persist();
+        Ok(n)
+    }
+
+    /// Download the presets as `presets.json`.
+    pub fn export_presets(&self) -> Result<(), VizError> {
+        store::download("presets.json", &self.presets.to_json())
+    }
+
+    // Files picked by `Command::ImportPresets`.
+    fn take_imports(&mut self) {
+        let files: Vec<String> = self.imported.borrow_mut().drain(..).collect();
+        for json in files {
+            match self.import_presets(&json) {
+                Ok(n) => self.show(&format!("imported {n} preset(s)")),
+                Err(e) => {
+                    web_sys::console::error_1(&e.to_string().into());
+                    self.show(&e.to_string());
                 }
-                // Aspect-correct square space where effects stay consistent across viewport sizes
-                // uv is already normalized to the centered square; use it directly
-                vec2 a = vec2(min(res.x, res.y)) / res; // components <= 1
-                vec2 uv_sq = uv;
-
-                // Build displacement in square space
-                vec2 disp = vec2(0.0);
-                float wave = sin(uv_sq.y*12.0 + u_time*1.5) * 0.003; wave += sin((uv_sq.x+uv_sq.y)*10.0 - u_time*1.2) * 0.002; disp += vec2(wave, 0.0);
-                vec2 s1 = vec2(0.3+0.2*sin(u_time*0.4), 0.4+0.2*cos(u_time*0.35));
-                vec2 s2 = vec2(0.7+0.2*cos(u_time*0.37), 0.6+0.2*sin(u_time*0.31));
-                for(int i=0;i<2;i++){ vec2 c = (i==0)? s1 : s2; vec2 d = uv_sq - c; float r = length(d)+1e-4; float ang = 0.15 * sin(u_time*0.8 + r*25.0); mat2 rot = mat2(cos(ang),-sin(ang),sin(ang),cos(ang)); disp += (rot * d - d) * smoothstep(0.25, 0.0, r); }
-                for(int i=0; i<3; ++i){ vec2 seed = vec2(fract(sin(float(i)*12.9898+78.233)*43758.5453), fract(sin(float(i)*19.123+11.73)*24634.6345)); seed = 0.2 + 0.6*seed + 0.05*vec2(sin(u_time*(1.0+float(i)*0.3)+float(i)), cos(u_time*(1.2+float(i)*0.17)+float(i))); vec2 d = uv_sq - seed; float r = length(d); float r0 = 0.18 + 0.05*sin(u_time*1.7+float(i)); float amp = 0.008 * sin((r-r0)*40.0 - u_time*3.0); disp += normalize(d) * amp * smoothstep(r0, 0.0, r); }
-
-                // Apply displacement in square space, convert back to texture space for sampling
-                vec2 suv_sq = clamp(uv_sq + disp, 0.0, 1.0);
-                // Map square UVs back into the inscribed square band of the rectangular textures
-                vec2 suv = (suv_sq - 0.5) / a + 0.5;
-
-                vec3 base = sample_src(suv);
-                float mask = texture(u_mask, suv).r;
-
-                // Diagonal zebra stripes (aspect-invariant)
-                float t = u_time;
-                float theta = u_stripe_theta0 + u_stripe_theta_speed * t;
-                mat2 R = mat2(cos(theta), -sin(theta), sin(theta), cos(theta));
-                vec2 q = R * (suv_sq - 0.5) + u_stripe_drift_speed * t;
-                float s = fract(q.y * u_stripe_density);
-                float stripeMask = step(s, clamp(u_stripe_thickness, 0.02, 0.98));
-                float hue = fract(q.x * (u_stripe_density*0.5) + t * u_color_speed);
-                vec3 rainbow = hsv2rgb(vec3(hue, 0.9, 1.0));
-                vec3 stripes = stripeMask * rainbow;
-
-                // Polka dots pattern (aspect-invariant)
-                float theta_d = u_dot_theta0 + u_dot_theta_speed * t;
-                mat2 RD = mat2(cos(theta_d), -sin(theta_d), sin(theta_d), cos(theta_d));
-                vec2 pd = RD * (suv_sq - 0.5) + u_dot_drift_speed * t + 0.5;
-                // Grid cell and local coords
-                float dens = max(2.0, u_dot_density);
-                vec2 g = pd * dens;
-                vec2 cell = floor(g);
-                vec2 f = fract(g);
-                // Random center jitter within cell
-                vec2 j = (hash22(cell) - 0.5) * 0.8; // up to 40% of cell size
-                vec2 center = 0.5 + j;
-                float rmin = max(0.005, u_dot_radius_min);
-                float rmax = max(rmin+0.002, u_dot_radius_max);
-                float r = mix(rmin, rmax, hash12(cell+13.17));
-                float d = length(f - center);
-                float dotMask = step(d, r);
-                float hue_d = fract((cell.x + cell.y*1.37) * 0.15 + t * u_color_speed);
-                vec3 dotColor = hsv2rgb(vec3(hue_d, 0.9, 1.0));
-                vec3 polka = dotMask * dotColor;
-
-                // Pick pattern: u_fill_mode 0 -> stripes, 1 -> polka
-                vec3 pattern = mix(stripes, polka, clamp(u_fill_mode, 0.0, 1.0));
-
-                // Flaming edges from source
-                vec2 px = 1.0 / u_resolution;
-                float l00 = dot(texture(u_src, suv + px*vec2(-1.0,-1.0)).rgb, vec3(0.2126,0.7152,0.0722));
-                float l10 = dot(texture(u_src, suv + px*vec2( 0.0,-1.0)).rgb, vec3(0.2126,0.7152,0.0722));
-                float l20 = dot(texture(u_src, suv + px*vec2( 1.0,-1.0)).rgb, vec3(0.2126,0.7152,0.0722));
-                float l01 = dot(texture(u_src, suv + px*vec2(-1.0, 0.0)).rgb, vec3(0.2126,0.7152,0.0722));
-                float l21 = dot(texture(u_src, suv + px*vec2( 1.0, 0.0)).rgb, vec3(0.2126,0.7152,0.0722));
-                float l02 = dot(texture(u_src, suv + px*vec2(-1.0, 1.0)).rgb, vec3(0.2126,0.7152,0.0722));
-                float l12 = dot(texture(u_src, suv + px*vec2( 0.0, 1.0)).rgb, vec3(0.2126,0.7152,0.0722));
-                float l22 = dot(texture(u_src, suv + px*vec2( 1.0, 1.0)).rgb, vec3(0.2126,0.7152,0.0722));
-                float gx = (l20 + 2.0*l21 + l22) - (l00 + 2.0*l01 + l02);
-                float gy = (l02 + 2.0*l12 + l22) - (l00 + 2.0*l10 + l20);
-                float edge = clamp(length(vec2(gx,gy))*1.5, 0.0, 1.0);
-                float flicker = 0.6 + 0.4*sin(u_time*15.0 + suv.x*30.0 + suv.y*25.0);
-                vec3 flame = vec3(1.0, 0.5, 0.05) * pow(edge, 0.8) * flicker;
-
-                vec3 col = mix(vec3(0.0), pattern, mask);
-                col += flame * 0.6;
-                float v = smoothstep(0.95, 0.4, length(uv_sq-0.5));
-                col *= v;
-                o = vec4(col, 1.0);
             }
-            "#;
-
-            let prog = link_program(gl, vsrc, fsrc)?;
+        }
+    }
 
-            // Fullscreen large triangle VBO
-            let verts: [f32; 6] = [ -1.0, -1.0, 3.0, -1.0, -1.0, 3.0 ];
-            let vbo = gl.create_buffer().ok_or("vbo")?;
-            gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vbo));
-            unsafe {
-                let fa = js_sys::Float32Array::view(&verts);
-                gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &fa, GL::STATIC_DRAW);
+    fn on_pointer(&mut self, ev: &web_sys::PointerEvent) {
+        let (id, x, y, t) = (ev.pointer_id(), ev.client_x() as f32, ev.client_y() as f32, ev.time_stamp());
+        let gesture = match ev.type_().as_str() {
+            "pointerdown" => {
+                // Keep receiving moves when the finger leaves the canvas
+                let _ = self.canvas.set_pointer_capture(id);
+                self.gestures.down(id, x, y, t);
+                None
+            }
+            "pointermove" => self.gestures.moved(id, x, y),
+            "pointerup" => self.gestures.up(id, x, y, t),
+            _ => {
+                self.gestures.cancel(id);
+                None
             }
+        };
+        if let Some(g) = gesture {
+            self.on_gesture(g);
+        }
+    }
 
-            // Create scene texture and FBO
-            let tex = gl.create_texture().ok_or("tex")?;
-            gl.bind_texture(GL::TEXTURE_2D, Some(&tex));
-            gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::LINEAR as i32);
-            gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::LINEAR as i32);
-            gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
-            gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
-            gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
-                GL::TEXTURE_2D, 0, GL::RGBA as i32, w, h, 0, GL::RGBA, GL::UNSIGNED_BYTE, None
-            )?;
-
-            let fbo = gl.create_framebuffer().ok_or("fbo")?;
-            gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&fbo));
-            gl.framebuffer_texture_2d(GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0, GL::TEXTURE_2D, Some(&tex), 0);
-            gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+    fn on_gesture(&mut self, g: Gesture) {
+        match g {
+            Gesture::SwipeLeft => self.run(Command::Next),
+            Gesture::SwipeRight => self.run(Command::Prev),
+            Gesture::TwoFingerTap => self.run(Command::Randomize),
+            Gesture::LongPress => self.run(Command::ToggleOverlay),
+            Gesture::Transform { scale, rotate } => {
+                self.view.zoom = (self.view.zoom * scale).clamp(View::MIN_ZOOM, View::MAX_ZOOM);
+                self.view.rot += rotate;
+                self.persist();
+            }
+        }
+    }
 
-            // Mask texture and FBO
-            let tex_m = gl.create_texture().ok_or("masktex")?;
-            gl.bind_texture(GL::TEXTURE_2D, Some(&tex_m));
-            // Use NEAREST filtering for the mask to avoid edge expansion artifacts
-            gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::NEAREST as i32);
-            gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::NEAREST as i32);
-            gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
-            gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
-            gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
-                GL::TEXTURE_2D, 0, GL::RGBA as i32, w, h, 0, GL::RGBA, GL::UNSIGNED_BYTE, None
-            )?;
-
-            let fbo_m = gl.create_framebuffer().ok_or("mfbo")?;
-            gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&fbo_m));
-            gl.framebuffer_texture_2d(GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0, GL::TEXTURE_2D, Some(&tex_m), 0);
-            gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+    pub fn keymap_mut(&mut self) -> &mut Keymap {
+        &mut self.keymap
+    }
 
-            Ok(Self { prog, vbo, fbo_scene: fbo, tex_scene: tex, fbo_mask: fbo_m, tex_mask: tex_m, w, h })
+    // Key bindings in the overlay (or the console when there is none).
+    fn toggle_help(&mut self) {
+        self.help_visible = !self.help_visible;
+        if self.help_visible {
+            let text = format!("keys\n{}", self.keymap.help().join("\n"));
+            match &self.overlay {
+                Some(elem) => elem.set_text_content(Some(&text)),
+                None => web_sys::console::info_1(&text.into()),
+            }
         }
+        self.style_overlay();
+    }
 
-        fn resize(&mut self, gl: &GL, w: i32, h: i32) -> Result<(), JsValue> {
-            if self.w == w && self.h == h { return Ok(()); }
-            self.w = w; self.h = h;
-            gl.bind_texture(GL::TEXTURE_2D, Some(&self.tex_scene));
-            gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
-                GL::TEXTURE_2D, 0, GL::RGBA as i32, w, h, 0, GL::RGBA, GL::UNSIGNED_BYTE, None
-            )?;
-            gl.bind_texture(GL::TEXTURE_2D, Some(&self.tex_mask));
-            gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
-                GL::TEXTURE_2D, 0, GL::RGBA as i32, w, h, 0, GL::RGBA, GL::UNSIGNED_BYTE, None
-            )?;
-            Ok(())
+    fn style_overlay(&self) {
+        let Some(elem) = self.overlay.as_ref().and_then(|e| e.dyn_ref::<web_sys::HtmlElement>()) else {
+            return;
+        };
+        let style = elem.style();
+        let _ = style.set_property("display", if self.overlay_hidden && !self.help_visible { "none" } else { "" });
+        // The help is a table aligned with spaces
+        let _ = style.set_property("white-space", if self.help_visible { "pre" } else { "" });
+    }
+
+    // Hot-reload: rebuild programs whose shader files changed. On a compile
+    // error the previous program keeps running and the log goes to the overlay.
+    fn reload_shaders(&mut self) {
+        let changed: Vec<String> = self.shaders_changed.borrow_mut().drain(..).collect();
+        if changed.is_empty() {
+            return;
         }
+        let lib = self.lib.borrow();
+        let mut errors: Vec<String> = Vec::new();
+        errors.extend(self.post.reload(&self.gl, &lib, &changed).err().map(|e| e.to_string()));
+        for v in self.scenes.iter_mut() {
+            errors.extend(v.reload(&self.gl, &lib, &changed).err().map(|e| e.to_string()));
+        }
+        drop(lib);
+        if errors.is_empty() {
+            if let Some(label) = self.label() {
+                self.show(&label);
+            }
+        } else {
+            web_sys::console::error_1(&errors.join("\n").into());
+            self.show(&errors.join("\n"));
+        }
+    }
 
-        fn begin_scene(&self, gl: &GL) {
-            gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&self.fbo_scene));
-            gl.viewport(0, 0, self.w, self.h);
-            gl.clear_color(0.0, 0.0, 0.0, 1.0);
-            gl.clear(GL::COLOR_BUFFER_BIT);
+    /// Draw one frame. Returns `false` when there is nothing to draw and the
+    /// animation loop can stop.
+    fn frame(&mut self, now: f64) -> bool {
+        if self.released {
+            return false;
+        }
+        let gl = self.gl.clone();
+        if !self.fill_window {
+            // Embedded canvases follow their layout box, which can change
+            // without a window resize.
+            self.resize();
+        }
+        let frame_dt = self.monitor.tick(now);
+        if let Some(dt) = frame_dt {
+            let scale = self.scaler.update(&self.scale_config, self.monitor.avg_ms(), dt);
+            let _ = self.post.set_scale(&gl, scale);
         }
+        self.timer.poll(&gl);
+        self.reload_shaders();
+        self.take_imports();
+        // `PointerEvent.timeStamp` and rAF times share the `performance.now()` clock
+        if let Some(g) = self.gestures.poll(now) {
+            self.on_gesture(g);
+        }
+        if self.show_stats && now - self.last_stats_ms >= 250.0 {
+            self.last_stats_ms = now;
+            let text = format!("{:.0} fps  {:.1} ms  scale {:.2}", self.monitor.fps(), self.monitor.avg_ms(), self.scaler.scale());
+            set_stats_text(self.stats.as_ref(), Some(&text));
+        }
+        let Some(entry) = self.playlist.current().cloned() else {
+            return false;
+        };
 
-        fn begin_mask(&self, gl: &GL) {
-            gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&self.fbo_mask));
-            gl.viewport(0, 0, self.w, self.h);
+        let mut t = self.clock.tick(now);
+        if let Some(synced) = self.follow(now) {
+            t = synced;
+        }
+        if self.autoplay && t - self.segment_start >= self.duration_ms(&entry) {
+            self.next();
+        }
+        let Some(entry) = self.playlist.current().cloned() else {
+            return false;
+        };
+        self.lead(now);
+        let local_t = ((t - self.segment_start) / 1000.0) as f32;
+        if self.frozen {
+            return true;
+        }
+        if self.blackout {
+            gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+            gl.viewport(0, 0, self.post.screen_w, self.post.screen_h);
             gl.clear_color(0.0, 0.0, 0.0, 1.0);
             gl.clear(GL::COLOR_BUFFER_BIT);
+            return true;
         }
 
-        fn draw(&self, gl: &GL, time: f32, sp: &PatternParams) {
-            // Post-process pass: default framebuffer
+        let limits = self.safety_limits();
+        let mut params = if self.reduced_motion { motion::calm(&self.params) } else { self.params };
+        if let Some(l) = &limits {
+            params.color_speed = params.color_speed.min(l.max_color_speed);
+        }
+        let fx = post::Effects {
+            contrast: limits.map_or(1.0, |l| l.contrast),
+            motion: if self.reduced_motion { 0.0 } else { 1.0 },
+        };
+
+        // Render scene color + mask in one MRT pass, then apply post-process to screen
+        let mut frame = Globals {
+            resolution: [self.post.w as f32, self.post.h as f32],
+            time: (t as f32) / 1000.0,
+            scene_time: local_t,
+            max_flash_hz: limits.map_or(UNLIMITED_FLASH_HZ, |l| l.max_flash_hz),
+            ..Globals::default()
+        };
+        self.globals.upload(&gl, &frame);
+        self.timer.begin(&gl, Pass::Scene);
+        self.post.begin(&gl);
+        let mut pose = self.anim.sample(local_t);
+        pose.scale /= self.view.zoom;
+        pose.rotation += self.view.rot;
+        self.scenes[entry.scene].render(&gl, local_t, &pose);
+        self.timer.end(&gl, Pass::Scene);
+        // The final pass runs at canvas resolution
+        frame.resolution = [self.post.screen_w as f32, self.post.screen_h as f32];
+        self.globals.upload(&gl, &frame);
+        self.timer.begin(&gl, Pass::Post);
+        self.post.draw(&gl, &params, &fx);
+        self.timer.end(&gl, Pass::Post);
+        if let Some(s) = self.safety.as_mut() {
+            let size = readback::SIZE;
+            frame.resolution = [size as f32, size as f32];
+            self.globals.upload(&gl, &frame);
+            self.post.draw_into(&gl, &params, &fx, Some(s.readback.target()), size, size);
             gl.bind_framebuffer(GL::FRAMEBUFFER, None);
-            gl.viewport(0, 0, self.w, self.h);
-            gl.use_program(Some(&self.prog));
-
-            // uniforms
-            let loc_res = gl.get_uniform_location(&self.prog, "u_resolution");
-            gl.uniform2f(loc_res.as_ref(), self.w as f32, self.h as f32);
-            let loc_time = gl.get_uniform_location(&self.prog, "u_time");
-            gl.uniform1f(loc_time.as_ref(), time);
-            // stripe params
-            gl.uniform1f(gl.get_uniform_location(&self.prog, "u_stripe_theta0").as_ref(), sp.theta0);
-            gl.uniform1f(gl.get_uniform_location(&self.prog, "u_stripe_theta_speed").as_ref(), sp.theta_speed);
-            gl.uniform1f(gl.get_uniform_location(&self.prog, "u_stripe_density").as_ref(), sp.density);
-            gl.uniform1f(gl.get_uniform_location(&self.prog, "u_stripe_thickness").as_ref(), sp.thickness);
-            gl.uniform2f(gl.get_uniform_location(&self.prog, "u_stripe_drift_speed").as_ref(), sp.drift_x, sp.drift_y);
-            gl.uniform1f(gl.get_uniform_location(&self.prog, "u_color_speed").as_ref(), sp.color_speed);
-            // polka
-            gl.uniform1f(gl.get_uniform_location(&self.prog, "u_fill_mode").as_ref(), if sp.mode_polka { 1.0 } else { 0.0 });
-            gl.uniform1f(gl.get_uniform_location(&self.prog, "u_dot_theta0").as_ref(), sp.dot_theta0);
-            gl.uniform1f(gl.get_uniform_location(&self.prog, "u_dot_theta_speed").as_ref(), sp.dot_theta_speed);
-            gl.uniform2f(gl.get_uniform_location(&self.prog, "u_dot_drift_speed").as_ref(), sp.dot_drift_x, sp.dot_drift_y);
-            gl.uniform1f(gl.get_uniform_location(&self.prog, "u_dot_density").as_ref(), sp.dot_density);
-            gl.uniform1f(gl.get_uniform_location(&self.prog, "u_dot_radius_min").as_ref(), sp.dot_rmin);
-            gl.uniform1f(gl.get_uniform_location(&self.prog, "u_dot_radius_max").as_ref(), sp.dot_rmax);
-            let loc_src = gl.get_uniform_location(&self.prog, "u_src");
-            gl.active_texture(GL::TEXTURE0);
-            gl.bind_texture(GL::TEXTURE_2D, Some(&self.tex_scene));
-            gl.uniform1i(loc_src.as_ref(), 0);
-            let loc_mask = gl.get_uniform_location(&self.prog, "u_mask");
-            gl.active_texture(GL::TEXTURE1);
-            gl.bind_texture(GL::TEXTURE_2D, Some(&self.tex_mask));
-            gl.uniform1i(loc_mask.as_ref(), 1);
-
-            // geometry
-            gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.vbo));
-            gl.enable_vertex_attrib_array(0);
-            gl.vertex_attrib_pointer_with_i32(0, 2, GL::FLOAT, false, 0, 0);
-            gl.draw_arrays(GL::TRIANGLES, 0, 3);
-            gl.disable_vertex_attrib_array(0);
-        }
-    }
-
-    // (moved) Resize handling is set up after post-process initialization
-
-    // ---------- Visualization framework ----------
-
-    trait Visualizer {
-        fn name(&self) -> &'static str;
-        fn init(&mut self, _gl: &GL) {}
-        fn render_mask(&mut self, gl: &GL, t: f32);
-        fn render_color(&mut self, gl: &GL, t: f32);
-    }
-
-    // ---------- WebGL helpers ----------
-    fn compile_shader(gl: &GL, src: &str, shader_type: u32) -> Result<WebGlShader, JsValue> {
-        let shader = gl
-            .create_shader(shader_type)
-            .ok_or("could not create shader")?;
-        gl.shader_source(&shader, src);
-        gl.compile_shader(&shader);
-        if !gl
-            .get_shader_parameter(&shader, GL::COMPILE_STATUS)
-            .as_bool()
-            .unwrap_or(false)
-        {
-            return Err(JsValue::from(gl.get_shader_info_log(&shader).unwrap_or_default()));
-        }
-        Ok(shader)
-    }
-
-    fn link_program(gl: &GL, vert_src: &str, frag_src: &str) -> Result<WebGlProgram, JsValue> {
-        let vert = compile_shader(gl, vert_src, GL::VERTEX_SHADER)?;
-        let frag = compile_shader(gl, frag_src, GL::FRAGMENT_SHADER)?;
-        let prog = gl.create_program().ok_or("could not create program")?;
-        gl.attach_shader(&prog, &vert);
-        gl.attach_shader(&prog, &frag);
-        gl.link_program(&prog);
-        if !gl
-            .get_program_parameter(&prog, GL::LINK_STATUS)
-            .as_bool()
-            .unwrap_or(false)
-        {
-            return Err(JsValue::from(
-                gl.get_program_info_log(&prog).unwrap_or_default(),
-            ));
-        }
-        Ok(prog)
-    }
-
-    // Basic circle line geometry prepared once and shared.
-    const SEGMENTS: usize = 128;
-
-    // Fullscreen vertex shader used by SDF-based visualizers
-    const VERT_FS: &str = r#"#version 300 es
-    layout(location=0) in vec2 a_pos;
-    void main(){ gl_Position = vec4(a_pos, 0.0, 1.0); }
-    "#;
-
-    // ---------- New Line-based Visualizers ----------
-
-    struct PulseCircle { prog_color: Option<WebGlProgram>, prog_mask: Option<WebGlProgram>, vbo: Option<web_sys::WebGlBuffer> }
-    impl Default for PulseCircle { fn default() -> Self { Self { prog_color: None, prog_mask: None, vbo: None } } }
-    impl Visualizer for PulseCircle {
-        fn name(&self) -> &'static str { "Pulsing Circle" }
-        fn init(&mut self, gl: &GL) {
-            let frag_common = r#"
-                precision mediump float;
-                uniform vec2 u_resolution; uniform float u_time; uniform float u_scale; uniform float u_rot; out vec4 o;
-                float sdCircle(vec2 p, float r){ return length(p)-r; }
-                vec2 toP(vec2 uv){ vec2 res=u_resolution; vec2 a=vec2(min(res.x,res.y))/res; vec2 p=(uv*2.0-1.0)*a*u_scale; float c=cos(u_rot), s=sin(u_rot); return mat2(c,-s,s,c)*p; }
-            "#;
-            let frag_color = format!("#version 300 es\n{}\nvoid main(){{ vec2 uv=gl_FragCoord.xy/u_resolution; vec2 p=toP(uv); float d=sdCircle(p,0.7); float a=smoothstep(0.0,-0.005,d); float clip=1.0 - smoothstep(0.85, 1.0, length(p)); a*=clip; float bright=0.5+0.5*sin(u_time); o=vec4(vec3(bright), a); }}", frag_common);
-            let frag_mask = format!("#version 300 es\n{}\nvoid main(){{ vec2 uv=gl_FragCoord.xy/u_resolution; vec2 p=toP(uv); float d=sdCircle(p,0.7); float a=step(d,0.0); float clip=1.0 - smoothstep(0.85, 1.0, length(p)); a*=clip; o=vec4(a,a,a,1.0); }}", frag_common);
-            self.prog_color = Some(link_program(gl, VERT_FS, &frag_color).unwrap());
-            self.prog_mask = Some(link_program(gl, VERT_FS, &frag_mask).unwrap());
-            // FS triangle
-            let verts: [f32; 6] = [ -1.0, -1.0, 3.0, -1.0, -1.0, 3.0 ];
-            let vbo = gl.create_buffer().unwrap(); gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vbo)); unsafe{let fa=js_sys::Float32Array::view(&verts); gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER,&fa,GL::STATIC_DRAW);} self.vbo=Some(vbo);
-        }
-        fn render_mask(&mut self, gl: &GL, t: f32){
-            let prog=self.prog_mask.as_ref().unwrap(); gl.use_program(Some(prog));
-            let (w,h)=(gl.drawing_buffer_width() as f32, gl.drawing_buffer_height() as f32);
-            gl.uniform2f(gl.get_uniform_location(prog,"u_resolution").as_ref(), w,h);
-            gl.uniform1f(gl.get_uniform_location(prog,"u_time").as_ref(), t);
-            gl.uniform1f(gl.get_uniform_location(prog,"u_scale").as_ref(), 1.0);
-            gl.uniform1f(gl.get_uniform_location(prog,"u_rot").as_ref(), 0.0);
-            gl.bind_buffer(GL::ARRAY_BUFFER,self.vbo.as_ref()); gl.enable_vertex_attrib_array(0); gl.vertex_attrib_pointer_with_i32(0,2,GL::FLOAT,false,0,0); gl.draw_arrays(GL::TRIANGLES,0,3); gl.disable_vertex_attrib_array(0);
-        }
-        fn render_color(&mut self, gl: &GL, t: f32){
-            let prog=self.prog_color.as_ref().unwrap(); gl.use_program(Some(prog));
-            let (w,h)=(gl.drawing_buffer_width() as f32, gl.drawing_buffer_height() as f32);
-            gl.uniform2f(gl.get_uniform_location(prog,"u_resolution").as_ref(), w,h);
-            gl.uniform1f(gl.get_uniform_location(prog,"u_time").as_ref(), t);
-            gl.uniform1f(gl.get_uniform_location(prog,"u_scale").as_ref(), 1.0);
-            gl.uniform1f(gl.get_uniform_location(prog,"u_rot").as_ref(), 0.0);
-            gl.bind_buffer(GL::ARRAY_BUFFER,self.vbo.as_ref()); gl.enable_vertex_attrib_array(0); gl.vertex_attrib_pointer_with_i32(0,2,GL::FLOAT,false,0,0); gl.draw_arrays(GL::TRIANGLES,0,3); gl.disable_vertex_attrib_array(0);
-        }
-    }
-
-    struct RotatingSquare { prog_color: Option<WebGlProgram>, prog_mask: Option<WebGlProgram>, vbo: Option<web_sys::WebGlBuffer> }
-    impl Default for RotatingSquare { fn default() -> Self { Self { prog_color: None, prog_mask: None, vbo: None } } }
-    impl Visualizer for RotatingSquare {
-        fn name(&self) -> &'static str { "Rotating Square" }
-        fn init(&mut self, gl: &GL) {
-            let frag_common = r#"
-                precision mediump float;
-                uniform vec2 u_resolution; uniform float u_time; uniform float u_scale; uniform float u_rot; out vec4 o;
-                float sdBox(vec2 p, vec2 b){ vec2 d=abs(p)-b; return length(max(d,0.0))+min(max(d.x,d.y),0.0); }
-                vec2 toP(vec2 uv){ vec2 res=u_resolution; vec2 a=vec2(min(res.x,res.y))/res; vec2 p=(uv*2.0-1.0)*a*u_scale; float c=cos(u_rot), s=sin(u_rot); return mat2(c,-s,s,c)*p; }
-            "#;
-            let frag_color = format!("#version 300 es\n{}\nvoid main(){{ vec2 uv=gl_FragCoord.xy/u_resolution; vec2 p=toP(uv); float d=sdBox(p, vec2(0.6)); float a=smoothstep(0.0,-0.005,d); float clip=1.0 - smoothstep(0.85, 1.0, length(p)); a*=clip; o=vec4(1.0,0.3,0.0,a); }}", frag_common);
-            let frag_mask = format!("#version 300 es\n{}\nvoid main(){{ vec2 uv=gl_FragCoord.xy/u_resolution; vec2 p=toP(uv); float d=sdBox(p, vec2(0.6)); float a=step(d,0.0); float clip=1.0 - smoothstep(0.85, 1.0, length(p)); a*=clip; o=vec4(a,a,a,1.0); }}", frag_common);
-            self.prog_color = Some(link_program(gl, VERT_FS, &frag_color).unwrap());
-            self.prog_mask = Some(link_program(gl, VERT_FS, &frag_mask).unwrap());
-            let verts:[f32;6]=[-1.0,-1.0,3.0,-1.0,-1.0,3.0]; let vbo=gl.create_buffer().unwrap(); gl.bind_buffer(GL::ARRAY_BUFFER,Some(&vbo)); unsafe{let fa=js_sys::Float32Array::view(&verts); gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER,&fa,GL::STATIC_DRAW);} self.vbo=Some(vbo);
-        }
-        fn render_mask(&mut self, gl:&GL, t:f32){ let prog=self.prog_mask.as_ref().unwrap(); gl.use_program(Some(prog)); let (w,h)=(gl.drawing_buffer_width() as f32, gl.drawing_buffer_height() as f32); gl.uniform2f(gl.get_uniform_location(prog,"u_resolution").as_ref(),w,h); gl.uniform1f(gl.get_uniform_location(prog,"u_time").as_ref(),t); gl.uniform1f(gl.get_uniform_location(prog,"u_scale").as_ref(),1.0); gl.uniform1f(gl.get_uniform_location(prog,"u_rot").as_ref(), t); gl.bind_buffer(GL::ARRAY_BUFFER,self.vbo.as_ref()); gl.enable_vertex_attrib_array(0); gl.vertex_attrib_pointer_with_i32(0,2,GL::FLOAT,false,0,0); gl.draw_arrays(GL::TRIANGLES,0,3); gl.disable_vertex_attrib_array(0); }
-        fn render_color(&mut self, gl:&GL, t:f32){ let prog=self.prog_color.as_ref().unwrap(); gl.use_program(Some(prog)); let (w,h)=(gl.drawing_buffer_width() as f32, gl.drawing_buffer_height() as f32); gl.uniform2f(gl.get_uniform_location(prog,"u_resolution").as_ref(),w,h); gl.uniform1f(gl.get_uniform_location(prog,"u_time").as_ref(),t); gl.uniform1f(gl.get_uniform_location(prog,"u_scale").as_ref(),1.0); gl.uniform1f(gl.get_uniform_location(prog,"u_rot").as_ref(), t); gl.bind_buffer(GL::ARRAY_BUFFER,self.vbo.as_ref()); gl.enable_vertex_attrib_array(0); gl.vertex_attrib_pointer_with_i32(0,2,GL::FLOAT,false,0,0); gl.draw_arrays(GL::TRIANGLES,0,3); gl.disable_vertex_attrib_array(0); }
-    }
-
-    struct StarLines { prog_color: Option<WebGlProgram>, prog_mask: Option<WebGlProgram>, vbo: Option<web_sys::WebGlBuffer> }
-    impl Default for StarLines { fn default()->Self{ Self{ prog_color:None, prog_mask:None, vbo:None } } }
-    impl Visualizer for StarLines {
-        fn name(&self)-> &'static str { "Twinkling Star" }
-        fn init(&mut self, gl:&GL){
-            let frag_common = r#"
-                precision mediump float; out vec4 o;
-                uniform vec2 u_resolution; uniform float u_time; uniform float u_scale; uniform float u_rot;
-                vec2 toP(vec2 uv){ vec2 res=u_resolution; vec2 a=vec2(min(res.x,res.y))/res; vec2 p=(uv*2.0-1.0)*a*u_scale; float c=cos(u_rot),s=sin(u_rot); return mat2(c,-s,s,c)*p; }
-            "#;
-            // star via angular radius modulation
-            let frag_color = format!("#version 300 es\n{}\nvoid main(){{ vec2 uv=gl_FragCoord.xy/u_resolution; vec2 p=toP(uv); float th=atan(p.y,p.x); float r=length(p); float k=5.0; float r1=0.75, r2=0.35; float rr = mix(r1, r2, 0.5+0.5*cos(th*k)); float a = smoothstep(rr, rr-0.01, r); float clip=1.0 - smoothstep(0.85, 1.0, r); a*=clip; float blink=abs(sin(u_time*5.0)); vec3 col=vec3(1.0, blink, 0.0); o=vec4(col, a); }}", frag_common);
-            let frag_mask = format!("#version 300 es\n{}\nvoid main(){{ vec2 uv=gl_FragCoord.xy/u_resolution; vec2 p=toP(uv); float th=atan(p.y,p.x); float r=length(p); float k=5.0; float r1=0.75, r2=0.35; float rr = mix(r1, r2, 0.5+0.5*cos(th*k)); float a = step(r, rr); float clip=1.0 - smoothstep(0.85, 1.0, r); a*=clip; o=vec4(a,a,a,1.0); }}", frag_common);
-            self.prog_color=Some(link_program(gl, VERT_FS, &frag_color).unwrap());
-            self.prog_mask=Some(link_program(gl, VERT_FS, &frag_mask).unwrap());
-            let verts:[f32;6]=[-1.0,-1.0,3.0,-1.0,-1.0,3.0]; let vbo=gl.create_buffer().unwrap(); gl.bind_buffer(GL::ARRAY_BUFFER,Some(&vbo)); unsafe{let fa=js_sys::Float32Array::view(&verts); gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER,&fa,GL::STATIC_DRAW);} self.vbo=Some(vbo);
-        }
-        fn render_mask(&mut self, gl:&GL,t:f32){ let prog=self.prog_mask.as_ref().unwrap(); gl.use_program(Some(prog)); let (w,h)=(gl.drawing_buffer_width() as f32, gl.drawing_buffer_height() as f32); gl.uniform2f(gl.get_uniform_location(prog,"u_resolution").as_ref(),w,h); gl.uniform1f(gl.get_uniform_location(prog,"u_time").as_ref(),t); gl.uniform1f(gl.get_uniform_location(prog,"u_scale").as_ref(),1.0); gl.uniform1f(gl.get_uniform_location(prog,"u_rot").as_ref(), t*0.5); gl.bind_buffer(GL::ARRAY_BUFFER,self.vbo.as_ref()); gl.enable_vertex_attrib_array(0); gl.vertex_attrib_pointer_with_i32(0,2,GL::FLOAT,false,0,0); gl.draw_arrays(GL::TRIANGLES,0,3); gl.disable_vertex_attrib_array(0);}        
-        fn render_color(&mut self, gl:&GL,t:f32){ let prog=self.prog_color.as_ref().unwrap(); gl.use_program(Some(prog)); let (w,h)=(gl.drawing_buffer_width() as f32, gl.drawing_buffer_height() as f32); gl.uniform2f(gl.get_uniform_location(prog,"u_resolution").as_ref(),w,h); gl.uniform1f(gl.get_uniform_location(prog,"u_time").as_ref(),t); gl.uniform1f(gl.get_uniform_location(prog,"u_scale").as_ref(),1.0); gl.uniform1f(gl.get_uniform_location(prog,"u_rot").as_ref(), t*0.5); gl.bind_buffer(GL::ARRAY_BUFFER,self.vbo.as_ref()); gl.enable_vertex_attrib_array(0); gl.vertex_attrib_pointer_with_i32(0,2,GL::FLOAT,false,0,0); gl.draw_arrays(GL::TRIANGLES,0,3); gl.disable_vertex_attrib_array(0);}        
-    }
-
-    struct RadiatingSpokes { prog_color: Option<WebGlProgram>, prog_mask: Option<WebGlProgram>, vbo: Option<web_sys::WebGlBuffer> }
-    impl Default for RadiatingSpokes { fn default()->Self{Self{prog_color:None, prog_mask:None, vbo:None}} }
-    impl Visualizer for RadiatingSpokes {
-        fn name(&self)-> &'static str { "Radiating Spokes" }
-        fn init(&mut self, gl:&GL){
-            let frag_common = r#"
-                precision mediump float; out vec4 o;
-                uniform vec2 u_resolution; uniform float u_time; uniform float u_scale; uniform float u_rot;
-                vec2 toP(vec2 uv){ vec2 res=u_resolution; vec2 a=vec2(min(res.x,res.y))/res; vec2 p=(uv*2.0-1.0)*a*u_scale; float c=cos(u_rot),s=sin(u_rot); return mat2(c,-s,s,c)*p; }
-            "#;
-            let frag_color = format!("#version 300 es\n{}\nvoid main(){{ vec2 uv=gl_FragCoord.xy/u_resolution; vec2 p=toP(uv); float th=atan(p.y,p.x); float r=length(p); float n=18.0; float w=0.12; float band = abs(sin(th*n + u_time*0.6)); float m = smoothstep(w,w-0.01,band) * smoothstep(0.9,0.2,r); float clip=1.0 - smoothstep(0.85, 1.0, r); m*=clip; o=vec4(0.0,0.8,1.0,m); }}", frag_common);
-            let frag_mask = format!("#version 300 es\n{}\nvoid main(){{ vec2 uv=gl_FragCoord.xy/u_resolution; vec2 p=toP(uv); float th=atan(p.y,p.x); float r=length(p); float n=18.0; float w=0.12; float band = abs(sin(th*n + u_time*0.6)); float a = step(band,w) * step(r,0.95); float clip=1.0 - smoothstep(0.85, 1.0, r); a*=clip; o=vec4(a,a,a,1.0); }}", frag_common);
-            self.prog_color=Some(link_program(gl, VERT_FS, &frag_color).unwrap());
-            self.prog_mask=Some(link_program(gl, VERT_FS, &frag_mask).unwrap());
-            let verts:[f32;6]=[-1.0,-1.0,3.0,-1.0,-1.0,3.0]; let vbo=gl.create_buffer().unwrap(); gl.bind_buffer(GL::ARRAY_BUFFER,Some(&vbo)); unsafe{let fa=js_sys::Float32Array::view(&verts); gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER,&fa,GL::STATIC_DRAW);} self.vbo=Some(vbo);
-        }
-        fn render_mask(&mut self, gl:&GL,t:f32){ let prog=self.prog_mask.as_ref().unwrap(); gl.use_program(Some(prog)); let (w,h)=(gl.drawing_buffer_width() as f32, gl.drawing_buffer_height() as f32); gl.uniform2f(gl.get_uniform_location(prog,"u_resolution").as_ref(),w,h); gl.uniform1f(gl.get_uniform_location(prog,"u_time").as_ref(),t); gl.uniform1f(gl.get_uniform_location(prog,"u_scale").as_ref(),1.0); gl.uniform1f(gl.get_uniform_location(prog,"u_rot").as_ref(), 0.0); gl.bind_buffer(GL::ARRAY_BUFFER,self.vbo.as_ref()); gl.enable_vertex_attrib_array(0); gl.vertex_attrib_pointer_with_i32(0,2,GL::FLOAT,false,0,0); gl.draw_arrays(GL::TRIANGLES,0,3); gl.disable_vertex_attrib_array(0); }
-        fn render_color(&mut self, gl:&GL,t:f32){ let prog=self.prog_color.as_ref().unwrap(); gl.use_program(Some(prog)); let (w,h)=(gl.drawing_buffer_width() as f32, gl.drawing_buffer_height() as f32); gl.uniform2f(gl.get_uniform_location(prog,"u_resolution").as_ref(),w,h); gl.uniform1f(gl.get_uniform_location(prog,"u_time").as_ref(),t); gl.uniform1f(gl.get_uniform_location(prog,"u_scale").as_ref(),1.0); gl.uniform1f(gl.get_uniform_location(prog,"u_rot").as_ref(), 0.0); gl.bind_buffer(GL::ARRAY_BUFFER,self.vbo.as_ref()); gl.enable_vertex_attrib_array(0); gl.vertex_attrib_pointer_with_i32(0,2,GL::FLOAT,false,0,0); gl.draw_arrays(GL::TRIANGLES,0,3); gl.disable_vertex_attrib_array(0); }
-    }
-
-    struct ExpandingCrossLines { prog_color: Option<WebGlProgram>, prog_mask: Option<WebGlProgram>, vbo: Option<web_sys::WebGlBuffer> }
-    impl Default for ExpandingCrossLines { fn default()->Self{Self{prog_color:None, prog_mask:None, vbo:None}} }
-    impl Visualizer for ExpandingCrossLines {
-        fn name(&self)-> &'static str { "Pulsing Plus" }
-        fn init(&mut self, gl:&GL){
-            let frag_common = r#"
-                precision mediump float; out vec4 o;
-                uniform vec2 u_resolution; uniform float u_time; uniform float u_scale; uniform float u_rot;
-                vec2 toP(vec2 uv){ vec2 res=u_resolution; vec2 a=vec2(min(res.x,res.y))/res; vec2 p=(uv*2.0-1.0)*a*u_scale; float c=cos(u_rot),s=sin(u_rot); return mat2(c,-s,s,c)*p; }
-                float sdBox(vec2 p, vec2 b){ vec2 d=abs(p)-b; return length(max(d,0.0))+min(max(d.x,d.y),0.0); }
-            "#;
-            let frag_color = format!("#version 300 es\n{}\nvoid main(){{ vec2 uv=gl_FragCoord.xy/u_resolution; vec2 p=toP(uv); float th=0.25+0.1*abs(sin(u_time*2.0)); float d=min(sdBox(p, vec2(0.8, th)), sdBox(p, vec2(th, 0.8))); float a=smoothstep(0.0,-0.005,d); float clip=1.0 - smoothstep(0.85, 1.0, length(p)); a*=clip; o=vec4(1.0,1.0,0.0,a); }}", frag_common);
-            let frag_mask = format!("#version 300 es\n{}\nvoid main(){{ vec2 uv=gl_FragCoord.xy/u_resolution; vec2 p=toP(uv); float th=0.25+0.1*abs(sin(u_time*2.0)); float a = step(min(sdBox(p, vec2(0.8, th)), sdBox(p, vec2(th, 0.8))), 0.0); float clip=1.0 - smoothstep(0.85, 1.0, length(p)); a*=clip; o=vec4(a,a,a,1.0); }}", frag_common);
-            self.prog_color=Some(link_program(gl, VERT_FS, &frag_color).unwrap());
-            self.prog_mask=Some(link_program(gl, VERT_FS, &frag_mask).unwrap());
-            let verts:[f32;6]=[-1.0,-1.0,3.0,-1.0,-1.0,3.0]; let vbo=gl.create_buffer().unwrap(); gl.bind_buffer(GL::ARRAY_BUFFER,Some(&vbo)); unsafe{let fa=js_sys::Float32Array::view(&verts); gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER,&fa,GL::STATIC_DRAW);} self.vbo=Some(vbo);
-        }
-        fn render_mask(&mut self, gl:&GL,t:f32){ let prog=self.prog_mask.as_ref().unwrap(); gl.use_program(Some(prog)); let (w,h)=(gl.drawing_buffer_width() as f32, gl.drawing_buffer_height() as f32); gl.uniform2f(gl.get_uniform_location(prog,"u_resolution").as_ref(),w,h); gl.uniform1f(gl.get_uniform_location(prog,"u_time").as_ref(),t); gl.uniform1f(gl.get_uniform_location(prog,"u_scale").as_ref(),1.0); gl.uniform1f(gl.get_uniform_location(prog,"u_rot").as_ref(),0.0); gl.bind_buffer(GL::ARRAY_BUFFER,self.vbo.as_ref()); gl.enable_vertex_attrib_array(0); gl.vertex_attrib_pointer_with_i32(0,2,GL::FLOAT,false,0,0); gl.draw_arrays(GL::TRIANGLES,0,3); gl.disable_vertex_attrib_array(0);}        
-        fn render_color(&mut self, gl:&GL,t:f32){ let prog=self.prog_color.as_ref().unwrap(); gl.use_program(Some(prog)); let (w,h)=(gl.drawing_buffer_width() as f32, gl.drawing_buffer_height() as f32); gl.uniform2f(gl.get_uniform_location(prog,"u_resolution").as_ref(),w,h); gl.uniform1f(gl.get_uniform_location(prog,"u_time").as_ref(),t); gl.uniform1f(gl.get_uniform_location(prog,"u_scale").as_ref(),1.0); gl.uniform1f(gl.get_uniform_location(prog,"u_rot").as_ref(),0.0); gl.bind_buffer(GL::ARRAY_BUFFER,self.vbo.as_ref()); gl.enable_vertex_attrib_array(0); gl.vertex_attrib_pointer_with_i32(0,2,GL::FLOAT,false,0,0); gl.draw_arrays(GL::TRIANGLES,0,3); gl.disable_vertex_attrib_array(0);}        
-    }
-
-    let mut viz_vec: Vec<Box<dyn Visualizer>> = vec![
-        Box::new(PulseCircle::default()),
-        Box::new(RotatingSquare::default()),
-        Box::new(StarLines::default()),
-        Box::new(RadiatingSpokes::default()),
-        Box::new(ExpandingCrossLines::default()),
-    ];
-
-    for v in viz_vec.iter_mut() {
-        v.init(&gl);
-    }
-
-    // Wrap in Rc<RefCell> so the animation closure can own mutable access.
-    let visualizers = Rc::new(RefCell::new(viz_vec));
-
-    const DURATION_MS: f64 = 20_000.0;
-
-    // Parameters controlling fill patterns, randomized on each visualizer change
-    #[derive(Clone, Copy)]
-    struct PatternParams {
-        // stripes
-        theta0: f32, theta_speed: f32, density: f32, thickness: f32, drift_x: f32, drift_y: f32,
-        // polka
-        mode_polka: bool,
-        dot_theta0: f32, dot_theta_speed: f32, dot_drift_x: f32, dot_drift_y: f32,
-        dot_density: f32, dot_rmin: f32, dot_rmax: f32,
-        // shared
-        color_speed: f32,
-    }
-    impl Default for PatternParams {
-        fn default() -> Self {
-            Self {
-                theta0: 0.0, theta_speed: 0.1, density: 16.0, thickness: 0.5, drift_x: 0.05, drift_y: 0.03,
-                mode_polka: false,
-                dot_theta0: 0.0, dot_theta_speed: 0.08, dot_drift_x: 0.03, dot_drift_y: -0.02,
-                dot_density: 10.0, dot_rmin: 0.05, dot_rmax: 0.18,
-                color_speed: 0.1,
-            }
+            // Flashes are perceived in wall time, whatever the playback speed
+            s.readback.capture(&gl, now);
         }
+
+        let stats = FrameStats {
+            fps: self.monitor.fps(),
+            frame_ms: self.monitor.avg_ms(),
+            scale: self.scaler.scale(),
+            scene_ms: self.timer.ms(Pass::Scene),
+            post_ms: self.timer.ms(Pass::Post),
+            gpu_timing: self.timer.uses_gpu(),
+        };
+        self.hud.record_frame(frame_dt, stats, &self.canvas);
+        true
     }
-    fn frand() -> f32 { js_sys::Math::random() as f32 }
-    fn randomize_params(p: &Rc<RefCell<PatternParams>>) {
-        let mut s = p.borrow_mut();
-        s.theta0 = frand() * std::f32::consts::PI;
-        s.theta_speed = 0.05 + frand() * 0.3; // rad/s
-        s.density = 8.0 + frand() * 24.0;     // lines per unit
-        s.thickness = 0.15 + frand() * 0.7;   // 0..1 fraction
-        s.drift_x = (frand() * 2.0 - 1.0) * 0.15; // units/s
-        s.drift_y = (frand() * 2.0 - 1.0) * 0.15;
-        s.color_speed = 0.05 + frand() * 0.4; // hue cycles/s
-        // switch mode randomly
-        s.mode_polka = frand() > 0.5;
-        // polka params
-        s.dot_theta0 = frand() * std::f32::consts::TAU;
-        s.dot_theta_speed = 0.02 + frand() * 0.2;
-        s.dot_drift_x = (frand()*2.0 - 1.0) * 0.2;
-        s.dot_drift_y = (frand()*2.0 - 1.0) * 0.2;
-        s.dot_density = 6.0 + frand() * 20.0;
-        let rmin = 0.03 + frand() * 0.12;
-        let rmax = rmin + 0.03 + frand() * 0.2;
-        s.dot_rmin = rmin; s.dot_rmax = rmax;
-    }
-
-    let stripe_params = Rc::new(RefCell::new(PatternParams::default()));
+}
 
-    // ---------- Animation loop ----------
-    // `f` holds the animation-frame closure so that we can keep calling
-    // `request_animation_frame` recursively. Storing it inside an `Option`
-    // allows us to create the `Closure` first and then obtain a reference to
-    // it from within itself.
+impl Drop for Renderer {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+// Built-in and `extra` scenes named in `names`, in that order; all of them
+// when `None`.
+fn select_scenes(
+    names: Option<&[String]>,
+    particles: &ParticleConfig,
+    extra: Vec<Box<dyn Visualizer>>,
+) -> Result<Vec<Box<dyn Visualizer>>, VizError> {
+    let mut all = scenes::builtin(particles);
+    all.extend(extra);
+    let Some(names) = names else {
+        return Ok(all);
+    };
+    let mut picked = Vec::with_capacity(names.len());
+    for name in names {
+        let i = all
+            .iter()
+            .position(|v| v.name() == name.as_str())
+            .ok_or_else(|| VizError::UnknownScene(name.clone()))?;
+        picked.push(all.swap_remove(i));
+    }
+    Ok(picked)
+}
 
-    let f: Rc<RefCell<Option<Closure<dyn FnMut()>>>> = Rc::new(RefCell::new(None));
-    let g = f.clone();
+// Match the canvas drawing buffer and the WebGL viewport to its display size:
+// the window for the standalone page, the canvas's own layout box otherwise.
+// Returns whether the size changed.
+fn fit_canvas(canvas: &HtmlCanvasElement, gl: &GL, fill_window: bool) -> bool {
+    let Some(win) = window() else {
+        return false;
+    };
+    let (css_w, css_h) = if fill_window {
+        let w = win.inner_width().ok().and_then(|v| v.as_f64()).unwrap_or(0.0);
+        let h = win.inner_height().ok().and_then(|v| v.as_f64()).unwrap_or(0.0);
+        (w, h)
+    } else {
+        (canvas.client_width() as f64, canvas.client_height() as f64)
+    };
+    // Not laid out (yet); keep the current buffer.
+    if css_w <= 0.0 || css_h <= 0.0 {
+        return false;
+    }
+    let dpr = win.device_pixel_ratio();
+    let px_w = (css_w * dpr).round() as u32;
+    let px_h = (css_h * dpr).round() as u32;
 
-    let start_time = window().unwrap().performance().unwrap().now();
-    let current_index: Rc<RefCell<usize>> = Rc::new(RefCell::new(usize::MAX)); // force first update
-    let segment_start_ms: Rc<RefCell<f64>> = Rc::new(RefCell::new(start_time));
+    // Only update if a change is actually required to avoid needless work.
+    if canvas.width() == px_w && canvas.height() == px_h {
+        return false;
+    }
+    if fill_window {
+        let elem: &web_sys::HtmlElement = canvas.unchecked_ref();
+        let style = elem.style();
+        let _ = style.set_property("width", &format!("{}px", css_w));
+        let _ = style.set_property("height", &format!("{}px", css_h));
+    }
+    canvas.set_width(px_w);
+    canvas.set_height(px_h);
+    gl.viewport(0, 0, px_w as i32, px_h as i32);
+    true
+}
 
-    let visualizers_clone = visualizers.clone();
-    let gl_clone = gl.clone();
+/// An event listener that is removed again when dropped.
+struct Listener {
+    target: EventTarget,
+    kind: &'static str,
+    callback: Closure<dyn FnMut(Event)>,
+}
 
-    // Initialize post-process pipeline
-    let post = Rc::new(RefCell::new(Post::new(
-        &gl_clone,
-        gl_clone.drawing_buffer_width() as i32,
-        gl_clone.drawing_buffer_height() as i32,
-    )?));
+impl Listener {
+    fn add(target: &EventTarget, kind: &'static str, f: impl FnMut(Event) + 'static) -> Result<Self, VizError> {
+        let callback = Closure::wrap(Box::new(f) as Box<dyn FnMut(Event)>);
+        target.add_event_listener_with_callback(kind, callback.as_ref().unchecked_ref())?;
+        Ok(Self { target: target.clone(), kind, callback })
+    }
+}
 
-    // Resize: adjust canvas and the offscreen texture size
-    {
-        let canvas = canvas.clone();
-        let gl = gl.clone();
-        let post_rc = post.clone();
-        let resize_closure = Closure::wrap(Box::new(move || {
-            adjust_size(&canvas, &gl);
-            let w = gl.drawing_buffer_width() as i32;
-            let h = gl.drawing_buffer_height() as i32;
-            let _ = post_rc.borrow_mut().resize(&gl, w, h);
-        }) as Box<dyn FnMut()>);
-        window()
-            .unwrap()
-            .add_event_listener_with_callback("resize", resize_closure.as_ref().unchecked_ref())?;
-        resize_closure.forget();
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = self
+            .target
+            .remove_event_listener_with_callback(self.kind, self.callback.as_ref().unchecked_ref());
+    }
+}
+
+// The animation-frame closure re-registers itself every frame, so it has to be
+// reachable from inside itself; it is created first and stored here afterwards.
+type FrameLoop = Rc<RefCell<Option<Closure<dyn FnMut()>>>>;
+
+/// A running renderer plus the callbacks driving it. Dropping the handle (or
+/// calling [`Handle::destroy`]) stops the animation loop and removes the listeners.
+pub struct Handle {
+    renderer: Rc<RefCell<Renderer>>,
+    frame_loop: FrameLoop,
+    raf_id: Rc<Cell<Option<i32>>>,
+    listeners: Vec<Listener>,
+}
+
+impl Handle {
+    pub fn renderer(&self) -> &Rc<RefCell<Renderer>> {
+        &self.renderer
     }
 
+    /// Stop rendering, detach from the page and free the GL resources. Safe to
+    /// call more than once.
+    pub fn destroy(&mut self) {
+        if let (Some(id), Some(win)) = (self.raf_id.take(), window()) {
+            let _ = win.cancel_animation_frame(id);
+        }
+        // Dropping the closure also breaks its reference cycle through `frame_loop`.
+        self.frame_loop.borrow_mut().take();
+        self.listeners.clear();
+        self.renderer.borrow_mut().release();
+    }
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        self.destroy();
+    }
+}
+
+/// Start rendering into `canvas`.
+pub fn start(canvas: HtmlCanvasElement, opts: Options) -> Result<Handle, VizError> {
+    let win = window().ok_or_else(|| VizError::MissingElement("window".into()))?;
+    let keyboard = opts.keyboard;
+    let gestures = opts.gestures;
+    let canvas_target: EventTarget = canvas.clone().into();
+    if gestures {
+        let _ = canvas.style().set_property("touch-action", "none");
+    }
+    let renderer = Rc::new(RefCell::new(Renderer::new(canvas, opts)?));
+
+    let mut listeners = Vec::new();
     {
-        let visualizers_k = visualizers.clone();
-        let current_index_k = current_index.clone();
-        let segment_start_k = segment_start_ms.clone();
-        let stripe_params_k = stripe_params.clone();
-        let keydown = Closure::wrap(Box::new(move |ev: web_sys::KeyboardEvent| {
-            let key = ev.key();
-            let code = ev.code();
-            if key == " " || code == "Space" {
-                ev.prevent_default();
-                if let Some(t) = ev.target() {
-                    if let Some(el) = t.dyn_ref::<web_sys::Element>() {
-                        let tag = el.tag_name();
-                        if tag == "INPUT" || tag == "TEXTAREA" || el.get_attribute("contenteditable").is_some() {
-                            return;
-                        }
-                    }
-                }
-                let len = visualizers_k.borrow().len();
-                if len > 0 {
-                    let mut idx = current_index_k.borrow_mut();
-                    let next = if *idx == usize::MAX { 0 } else { (*idx + 1) % len };
-                    *idx = next;
-                    *segment_start_k.borrow_mut() = window().unwrap().performance().unwrap().now();
-                    randomize_params(&stripe_params_k);
-                    let name = visualizers_k.borrow()[*idx].name();
-                    let label = format!("{}/{} {}", *idx + 1, len, name);
-                    let _ = super::set_overlay_text(&label);
-                }
+        let r = renderer.clone();
+        listeners.push(Listener::add(&win, "resize", move |_| r.borrow_mut().resize())?);
+    }
+    if keyboard {
+        let r = renderer.clone();
+        listeners.push(Listener::add(&win, "keydown", move |ev| {
+            if let Some(ev) = ev.dyn_ref::<web_sys::KeyboardEvent>() {
+                r.borrow_mut().on_key(ev);
             }
-        }) as Box<dyn FnMut(_)>);
-        window().unwrap().add_event_listener_with_callback("keydown", keydown.as_ref().unchecked_ref())?;
-        keydown.forget();
+        })?);
     }
 
-    *g.borrow_mut() = Some(Closure::wrap(Box::new(move || {
-        let now = window().unwrap().performance().unwrap().now();
-        let len = visualizers_clone.borrow().len();
-        if len == 0 {
-            return;
+    if gestures {
+        for kind in ["pointerdown", "pointermove", "pointerup", "pointercancel"] {
+            let r = renderer.clone();
+            listeners.push(Listener::add(&canvas_target, kind, move |ev| {
+                if let Some(ev) = ev.dyn_ref::<web_sys::PointerEvent>() {
+                    r.borrow_mut().on_pointer(ev);
+                }
+            })?);
         }
+    }
 
-        if *current_index.borrow() == usize::MAX {
-            *current_index.borrow_mut() = 0;
-            *segment_start_ms.borrow_mut() = now;
-            let name = visualizers_clone.borrow()[0].name();
-            let label = format!("{}/{} {}", 1, len, name);
-            let _ = super::set_overlay_text(&label);
-            randomize_params(&stripe_params);
-        }
-        let elapsed_in_segment = now - *segment_start_ms.borrow();
-        if elapsed_in_segment >= DURATION_MS {
-            let mut idx_ref = current_index.borrow_mut();
-            *idx_ref = (*idx_ref + 1) % len;
-            *segment_start_ms.borrow_mut() = now;
-            let name = visualizers_clone.borrow()[*idx_ref].name();
-            let label = format!("{}/{} {}", *idx_ref + 1, len, name);
-            let _ = super::set_overlay_text(&label);
-            randomize_params(&stripe_params);
-        }
-        let local_t = ((now - *segment_start_ms.borrow()) / 1000.0) as f32;
-        let idx_now = *current_index.borrow();
-
-        // Render mask then scene into offscreen targets, then apply post-process to screen
-        post.borrow().begin_mask(&gl_clone);
-        visualizers_clone.borrow_mut()[idx_now].render_mask(&gl_clone, local_t);
-        post.borrow().begin_scene(&gl_clone);
-        visualizers_clone.borrow_mut()[idx_now].render_color(&gl_clone, local_t);
-        let sp = *stripe_params.borrow();
-        post.borrow().draw(&gl_clone, (now as f32) / 1000.0, &sp);
-
-        // schedule next frame
-        window()
-            .unwrap()
-            .request_animation_frame(f.borrow().as_ref().unwrap().as_ref().unchecked_ref())
-            .unwrap();
-    }) as Box<dyn FnMut()>));
+    // Follow changes of the system setting while the page is open
+    if let Some(query) = win.match_media(REDUCED_MOTION_QUERY).ok().flatten() {
+        let r = renderer.clone();
+        let target: EventTarget = query.clone().into();
+        listeners.push(Listener::add(&target, "change", move |_| r.borrow_mut().on_motion_query(query.matches()))?);
+    }
 
-    window()
-        .unwrap()
-        .request_animation_frame(g.borrow().as_ref().unwrap().as_ref().unchecked_ref())?;
+    // ---------- Animation loop ----------
+    let frame_loop: FrameLoop = Rc::new(RefCell::new(None));
+    let raf_id: Rc<Cell<Option<i32>>> = Rc::new(Cell::new(None));
+    {
+        let r = renderer.clone();
+        let f = frame_loop.clone();
+        let id = raf_id.clone();
+        *frame_loop.borrow_mut() = Some(Closure::wrap(Box::new(move || {
+            id.set(None);
+            if !r.borrow_mut().frame(now_ms()) {
+                return;
+            }
+            // schedule next frame
+            if let (Some(cb), Some(win)) = (f.borrow().as_ref(), window()) {
+                id.set(win.request_animation_frame(cb.as_ref().unchecked_ref()).ok());
+            }
+        }) as Box<dyn FnMut()>));
+    }
+    if let Some(cb) = frame_loop.borrow().as_ref() {
+        raf_id.set(Some(win.request_animation_frame(cb.as_ref().unchecked_ref())?));
+    }
+
+    Ok(Handle { renderer, frame_loop, raf_id, listeners })
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wasm::glutil::live_objects;
+    use crate::wasm::options::EntrySpec;
+    use wasm_bindgen_test::*;
+
+    fn test_canvas() -> HtmlCanvasElement {
+        let document = window().unwrap().document().unwrap();
+        let canvas: HtmlCanvasElement = document.create_element("canvas").unwrap().dyn_into().unwrap();
+        canvas.set_width(64);
+        canvas.set_height(64);
+        canvas
+    }
+
+    #[wasm_bindgen_test]
+    fn start_and_destroy_repeatedly_frees_everything() {
+        let canvas = test_canvas();
+        let baseline = live_objects();
+        for _ in 0..5 {
+            let mut handle = start(canvas.clone(), Options::embedded()).expect("start");
+            assert!(live_objects() > baseline);
+            let renderer = Rc::downgrade(handle.renderer());
+            handle.destroy();
+            handle.destroy();
+            assert_eq!(live_objects(), baseline, "GL objects left after destroy");
+            drop(handle);
+            // Listeners and the frame closure each held a reference
+            assert!(renderer.upgrade().is_none(), "renderer still referenced after destroy");
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn instances_keep_their_own_hud() {
+        let document = window().unwrap().document().unwrap();
+        let hud_shown = || document.query_selector(".viz-hud").unwrap().is_some();
+        let mut a = start(test_canvas(), Options::embedded()).expect("start a");
+        let mut b = start(test_canvas(), Options::embedded()).expect("start b");
+        a.renderer().borrow_mut().set_hud_visible(true);
+        assert!(a.renderer().borrow().hud_visible());
+        assert!(!b.renderer().borrow().hud_visible());
+        assert!(hud_shown());
+        a.destroy();
+        assert!(!hud_shown(), "HUD canvas left after destroy");
+        b.destroy();
+    }
+
+    #[wasm_bindgen_test]
+    fn failed_start_frees_everything() {
+        let canvas = test_canvas();
+        let baseline = live_objects();
+        let mut opts = Options::embedded();
+        opts.playlist = Some(vec![EntrySpec {
+            scene: "No Such Scene".into(),
+            duration_ms: None,
+            anim: Animation::default(),
+        }]);
+        assert!(matches!(start(canvas, opts), Err(VizError::UnknownScene(_))));
+        assert_eq!(live_objects(), baseline, "GL objects left after a failed start");
+    }
 }
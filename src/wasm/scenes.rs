@@ -0,0 +1,151 @@
+#![cfg(target_arch = "wasm32")]
+
+//! Scene implementations drawn into the offscreen scene/mask targets.
+
+use web_sys::{WebGl2RenderingContext as GL, WebGlBuffer, WebGlProgram, WebGlUniformLocation};
+
+use super::error::VizError;
+use super::glutil::{delete, track, uniforms};
+use super::particles::ParticleScene;
+use super::shaders::{ShaderLibrary, ShaderProgram};
+use crate::anim::{Animation, Curve, Pose, Target};
+use crate::particles::ParticleConfig;
+
+/// Visualizers draw into `Post`'s MRT framebuffer: `layout(location =
+/// SCENE_COLOR_LOCATION)` receives the soft color pass and `layout(location =
+/// SCENE_MASK_LOCATION)` the hard mask, both from one shader (`common/scene.glsl`
+/// declares the two outputs).
+pub trait Visualizer {
+    fn name(&self) -> &'static str;
+    /// Create GL resources. A failing scene is dropped from the rotation.
+    fn init(&mut self, _gl: &GL, _lib: &ShaderLibrary) -> Result<(), VizError> { Ok(()) }
+    /// Draw at `t` seconds into the playlist entry, transformed by `pose`.
+    fn render(&mut self, gl: &GL, t: f32, pose: &Pose);
+    /// Default motion; playlist entries can override it per target.
+    fn animation(&self) -> Animation { Animation::default() }
+    /// Rebuild programs built from any of the `changed` shader files, keeping
+    /// the previous program if the new source fails to compile.
+    fn reload(&mut self, _gl: &GL, _lib: &ShaderLibrary, _changed: &[String]) -> Result<(), VizError> { Ok(()) }
+    /// Delete whatever `init` created, including after a failed `init`.
+    fn destroy(&mut self, _gl: &GL) {}
+}
+
+/// Viewer transform from pinch/rotate gestures, applied on top of the scene's
+/// own motion.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct View {
+    /// Magnification; 2 shows the shape twice as large.
+    pub zoom: f32,
+    /// Extra rotation in radians, counter-clockwise.
+    pub rot: f32,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self { zoom: 1.0, rot: 0.0 }
+    }
+}
+
+impl View {
+    pub const MIN_ZOOM: f32 = 0.25;
+    pub const MAX_ZOOM: f32 = 4.0;
+
+    /// View from values that may come from storage or the network: zoom is kept
+    /// within the pinch range and non-finite values fall back to the defaults.
+    pub fn new(zoom: f32, rot: f32) -> Self {
+        let d = Self::default();
+        Self {
+            zoom: if zoom.is_finite() { zoom.clamp(Self::MIN_ZOOM, Self::MAX_ZOOM) } else { d.zoom },
+            rot: if rot.is_finite() { rot } else { d.rot },
+        }
+    }
+}
+
+/// Vertex buffer of the oversized triangle `fullscreen.vert` expects.
+pub fn fullscreen_vbo(gl: &GL) -> Result<WebGlBuffer, VizError> {
+    let verts: [f32; 6] = [ -1.0, -1.0, 3.0, -1.0, -1.0, 3.0 ];
+    let vbo = track(gl.create_buffer()).ok_or(VizError::Resource("vertex buffer"))?; gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vbo)); unsafe{let fa=js_sys::Float32Array::view(&verts); gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER,&fa,GL::STATIC_DRAW);}
+    Ok(vbo)
+}
+
+/// Draw the triangle from [`fullscreen_vbo`] with the current program.
+pub fn draw_fullscreen(gl: &GL, vbo: Option<&WebGlBuffer>) {
+    gl.bind_buffer(GL::ARRAY_BUFFER,vbo); gl.enable_vertex_attrib_array(0); gl.vertex_attrib_pointer_with_i32(0,2,GL::FLOAT,false,0,0); gl.draw_arrays(GL::TRIANGLES,0,3); gl.disable_vertex_attrib_array(0);
+}
+
+// Per-program uniforms shared by the SDF visualizers; resolution/time come from `Globals`.
+uniforms! { pub struct SdfUniforms { u_scale, u_rot, u_offset } }
+
+/// Uniform locations of animated `u_*` parameters, looked up by name on first use.
+pub type ParamCache = Vec<(String, Option<WebGlUniformLocation>)>;
+
+/// Set the transform and animated parameters of `pose` on the bound `prog`,
+/// which includes `common/scene.glsl`.
+pub fn set_pose(gl: &GL, prog: &WebGlProgram, u: &SdfUniforms, params: &mut ParamCache, pose: &Pose) {
+    gl.uniform1f(u.u_scale.as_ref(), pose.scale);
+    gl.uniform1f(u.u_rot.as_ref(), pose.rotation);
+    gl.uniform2f(u.u_offset.as_ref(), pose.offset[0], pose.offset[1]);
+    for (name, v) in &pose.params {
+        let loc = match params.iter().find(|(n, _)| n == name) {
+            Some((_, loc)) => loc.clone(),
+            None => {
+                let loc = gl.get_uniform_location(prog, name);
+                params.push((name.clone(), loc.clone()));
+                loc
+            }
+        };
+        gl.uniform1f(loc.as_ref(), *v);
+    }
+}
+
+/// Fullscreen SDF shape: one fragment shader from `static/shaders/` writes the
+/// color and mask outputs; `spin` rotates it by that many radians per second.
+/// Animated `u_*` parameters are looked up by name on first use.
+struct SdfScene { name: &'static str, frag: &'static str, spin: f32, prog: Option<ShaderProgram>, u: Option<SdfUniforms>, params: ParamCache, vbo: Option<WebGlBuffer> }
+impl SdfScene {
+    fn new(name: &'static str, frag: &'static str, spin: f32) -> Self { Self { name, frag, spin, prog: None, u: None, params: Vec::new(), vbo: None } }
+}
+impl Visualizer for SdfScene {
+    fn name(&self) -> &'static str { self.name }
+    fn init(&mut self, gl: &GL, lib: &ShaderLibrary) -> Result<(), VizError> {
+        let prog = ShaderProgram::new(gl, lib, "fullscreen.vert", self.frag)?; self.u = Some(SdfUniforms::locate(gl, &prog.prog)); self.prog = Some(prog);
+        self.vbo = Some(fullscreen_vbo(gl)?);
+        Ok(())
+    }
+    fn render(&mut self, gl: &GL, _t: f32, pose: &Pose){
+        let (Some(prog), Some(u)) = (self.prog.as_ref(), self.u.as_ref()) else { return };
+        gl.use_program(Some(&prog.prog));
+        set_pose(gl, &prog.prog, u, &mut self.params, pose);
+        draw_fullscreen(gl, self.vbo.as_ref());
+    }
+    fn reload(&mut self, gl: &GL, lib: &ShaderLibrary, changed: &[String]) -> Result<(), VizError> {
+        if let Some(prog) = self.prog.as_mut() {
+            if prog.reload(gl, lib, changed)? {
+                self.u = Some(SdfUniforms::locate(gl, &prog.prog));
+                self.params.clear();
+            }
+        }
+        Ok(())
+    }
+    fn animation(&self) -> Animation {
+        Animation::default().with(Target::Rotation, Curve::Ramp(self.spin))
+    }
+    fn destroy(&mut self, gl: &GL) {
+        if let Some(prog) = self.prog.take() { prog.delete(gl); }
+        if let Some(vbo) = self.vbo.take() { delete(gl, &vbo); }
+        self.u = None;
+        self.params.clear();
+    }
+}
+
+/// The built-in scenes, in default playlist order. Not yet initialised.
+pub fn builtin(particles: &ParticleConfig) -> Vec<Box<dyn Visualizer>> {
+    vec![
+        Box::new(SdfScene::new("Pulsing Circle", "pulse_circle.frag", 0.0)),
+        Box::new(SdfScene::new("Rotating Square", "rotating_square.frag", 1.0)),
+        Box::new(SdfScene::new("Twinkling Star", "star_lines.frag", 0.5)),
+        Box::new(SdfScene::new("Radiating Spokes", "radiating_spokes.frag", 0.0)),
+        Box::new(SdfScene::new("Pulsing Plus", "pulsing_plus.frag", 0.0)),
+        Box::new(ParticleScene::new(particles.clone())),
+    ]
+}
@@ -0,0 +1,265 @@
+#![cfg(target_arch = "wasm32")]
+
+//! GLSL sources live in `static/shaders/`. Copies are embedded at build time so
+//! the bundle works stand-alone; at startup and whenever the host's watcher
+//! reports a change (`shaders/manifest.json`) the files are re-fetched and the
+//! affected programs are rebuilt in place. Sources go through
+//! [`crate::glsl::preprocess`], so `#include "common/..."` works and compile
+//! errors name the snippet file and line they came from.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{window, Response, WebGl2RenderingContext as GL, WebGlProgram};
+
+use super::error::VizError;
+use super::glutil::{compile_shader, delete, link_program, GLOBALS_GLSL};
+use crate::glsl::{builtin_defines, preprocess, Preprocessed};
+
+/// Path of the shader directory relative to the page.
+const BASE_URL: &str = "./shaders/";
+/// How often to check the manifest written by the host watcher.
+const POLL_MS: i32 = 1000;
+
+const EMBEDDED: &[(&str, &str)] = &[
+    ("common/color.glsl", include_str!("../../static/shaders/common/color.glsl")),
+    ("common/hash.glsl", include_str!("../../static/shaders/common/hash.glsl")),
+    ("common/jfa.glsl", include_str!("../../static/shaders/common/jfa.glsl")),
+    ("common/scene.glsl", include_str!("../../static/shaders/common/scene.glsl")),
+    ("common/sdf.glsl", include_str!("../../static/shaders/common/sdf.glsl")),
+    ("common/sobel.glsl", include_str!("../../static/shaders/common/sobel.glsl")),
+    ("fullscreen.vert", include_str!("../../static/shaders/fullscreen.vert")),
+    ("post.frag", include_str!("../../static/shaders/post.frag")),
+    ("pulse_circle.frag", include_str!("../../static/shaders/pulse_circle.frag")),
+    ("rotating_square.frag", include_str!("../../static/shaders/rotating_square.frag")),
+    ("star_lines.frag", include_str!("../../static/shaders/star_lines.frag")),
+    ("radiating_spokes.frag", include_str!("../../static/shaders/radiating_spokes.frag")),
+    ("pulsing_plus.frag", include_str!("../../static/shaders/pulsing_plus.frag")),
+    ("video.frag", include_str!("../../static/shaders/video.frag")),
+    ("silhouette.frag", include_str!("../../static/shaders/silhouette.frag")),
+    ("jfa_seed.frag", include_str!("../../static/shaders/jfa_seed.frag")),
+    ("jfa_step.frag", include_str!("../../static/shaders/jfa_step.frag")),
+    ("jfa_sdf.frag", include_str!("../../static/shaders/jfa_sdf.frag")),
+    ("text.vert", include_str!("../../static/shaders/text.vert")),
+    ("text.frag", include_str!("../../static/shaders/text.frag")),
+    ("particle_update.vert", include_str!("../../static/shaders/particle_update.vert")),
+    ("particle_update.frag", include_str!("../../static/shaders/particle_update.frag")),
+    ("particle.vert", include_str!("../../static/shaders/particle.vert")),
+    ("particle.frag", include_str!("../../static/shaders/particle.frag")),
+];
+
+/// Current source text of every shader file, keyed by path below `static/shaders/`.
+pub struct ShaderLibrary {
+    files: HashMap<String, String>,
+    defines: Vec<(String, String)>,
+}
+
+impl ShaderLibrary {
+    pub fn embedded() -> Self {
+        let files = EMBEDDED.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Self { files, defines: builtin_defines() }
+    }
+
+    /// Inject `#define name value` into every shader, so GLSL can share
+    /// constants with the Rust side instead of repeating them.
+    pub fn define(&mut self, name: &str, value: impl std::fmt::Display) {
+        self.defines.retain(|(n, _)| n != name);
+        self.defines.push((name.to_string(), value.to_string()));
+    }
+
+    /// Source of `name` ready for compilation: includes resolved, defines and
+    /// the `Globals` block inserted after the `#version` line.
+    pub fn source(&self, name: &str) -> Result<Preprocessed, VizError> {
+        preprocess(name, &self.defines, GLOBALS_GLSL, |f| self.files.get(f).cloned())
+            .map_err(|log| VizError::ShaderCompile { file: name.to_string(), log })
+    }
+
+    /// Store new text for `name`; returns whether it differed from what we had.
+    fn update(&mut self, name: &str, text: String) -> bool {
+        if self.files.get(name) == Some(&text) {
+            return false;
+        }
+        self.files.insert(name.to_string(), text);
+        true
+    }
+}
+
+/// A linked program plus the files it was built from, so it can be rebuilt in place.
+pub struct ShaderProgram {
+    pub prog: WebGlProgram,
+    vert: &'static str,
+    frag: &'static str,
+    /// Varyings captured by transform feedback.
+    feedback: &'static [&'static str],
+    /// Both stages' files including everything they `#include`.
+    deps: Vec<String>,
+}
+
+impl ShaderProgram {
+    pub fn new(gl: &GL, lib: &ShaderLibrary, vert: &'static str, frag: &'static str) -> Result<Self, VizError> {
+        Self::with_feedback(gl, lib, vert, frag, &[])
+    }
+
+    /// A program whose vertex stage writes `feedback` (interleaved, in that
+    /// order) to the bound transform feedback buffer.
+    pub fn with_feedback(
+        gl: &GL,
+        lib: &ShaderLibrary,
+        vert: &'static str,
+        frag: &'static str,
+        feedback: &'static [&'static str],
+    ) -> Result<Self, VizError> {
+        let (prog, deps) = Self::link(gl, lib, vert, frag, feedback)?;
+        Ok(Self { prog, vert, frag, feedback, deps })
+    }
+
+    fn link(gl: &GL, lib: &ShaderLibrary, vert: &str, frag: &str, feedback: &[&str]) -> Result<(WebGlProgram, Vec<String>), VizError> {
+        let vs = lib.source(vert)?;
+        let fs = lib.source(frag)?;
+        // Point log locations at the original files instead of the flattened source
+        let compile = |name: &str, src: &Preprocessed, kind: u32| {
+            compile_shader(gl, name, &src.source, kind).map_err(|e| match e {
+                VizError::ShaderCompile { file, log } => VizError::ShaderCompile { file, log: src.map_log(&log) },
+                e => e,
+            })
+        };
+        let vert_sh = compile(vert, &vs, GL::VERTEX_SHADER)?;
+        let frag_sh = match compile(frag, &fs, GL::FRAGMENT_SHADER) {
+            Ok(sh) => sh,
+            Err(e) => {
+                delete(gl, &vert_sh);
+                return Err(e);
+            }
+        };
+        let prog = link_program(gl, &format!("{vert} + {frag}"), &vert_sh, &frag_sh, feedback);
+        // The program keeps what it needs; the shader objects can go either way.
+        delete(gl, &vert_sh);
+        delete(gl, &frag_sh);
+        let deps = vs.files().iter().chain(fs.files()).cloned().collect();
+        Ok((prog?, deps))
+    }
+
+    pub fn uses(&self, file: &str) -> bool {
+        self.deps.iter().any(|f| f == file)
+    }
+
+    /// Relink if any of `changed` feeds this program. On failure the previous
+    /// program stays in use and the info log is returned. `Ok(true)` means the
+    /// program object was replaced and uniform locations must be re-resolved.
+    pub fn reload(&mut self, gl: &GL, lib: &ShaderLibrary, changed: &[String]) -> Result<bool, VizError> {
+        if !changed.iter().any(|f| self.uses(f)) {
+            return Ok(false);
+        }
+        let (prog, deps) = Self::link(gl, lib, self.vert, self.frag, self.feedback)?;
+        delete(gl, &self.prog);
+        self.prog = prog;
+        self.deps = deps;
+        Ok(true)
+    }
+
+    pub fn delete(&self, gl: &GL) {
+        delete(gl, &self.prog);
+    }
+}
+
+/// GET `url` as text; `None` on network errors and non-2xx responses.
+pub async fn fetch_text(url: &str) -> Option<String> {
+    let win = window()?;
+    let resp: Response = JsFuture::from(win.fetch_with_str(url)).await.ok()?.dyn_into().ok()?;
+    if !resp.ok() {
+        return None;
+    }
+    JsFuture::from(resp.text().ok()?).await.ok()?.as_string()
+}
+
+fn cache_bust() -> f64 {
+    js_sys::Date::now()
+}
+
+/// Fetch the shader files once at startup and then follow the host watcher's
+/// manifest. Names of files whose text changed are pushed onto `changed`; the
+/// render loop drains it and rebuilds the affected programs. Polling stops when
+/// the returned [`Watcher`] is dropped.
+pub fn watch(lib: Rc<RefCell<ShaderLibrary>>, changed: Rc<RefCell<Vec<String>>>) -> Result<Watcher, VizError> {
+    let mtimes: Rc<RefCell<HashMap<String, f64>>> = Rc::new(RefCell::new(HashMap::new()));
+    let busy = Rc::new(Cell::new(false));
+    // Without a manifest on the first poll there is no watcher; stop polling
+    // after the initial fetch.
+    let watching = Rc::new(Cell::new(true));
+    let first = Rc::new(Cell::new(true));
+
+    let tick = Closure::wrap(Box::new(move || {
+        if busy.get() || !watching.get() {
+            return;
+        }
+        busy.set(true);
+        let (lib, changed, mtimes, busy, watching, first) =
+            (lib.clone(), changed.clone(), mtimes.clone(), busy.clone(), watching.clone(), first.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            let first = first.replace(false);
+            let manifest = fetch_text(&format!("{BASE_URL}manifest.json?t={}", cache_bust())).await;
+            let stale: Vec<String> = match manifest.as_deref().and_then(parse_manifest) {
+                Some(entries) => {
+                    let mut m = mtimes.borrow_mut();
+                    entries
+                        .into_iter()
+                        .filter(|(name, t)| m.insert(name.clone(), *t) != Some(*t))
+                        .map(|(name, _)| name)
+                        .collect()
+                }
+                None if first => {
+                    watching.set(false);
+                    EMBEDDED.iter().map(|(k, _)| k.to_string()).collect()
+                }
+                // Unreachable or caught mid-write: try again on the next poll
+                None => Vec::new(),
+            };
+            for name in stale {
+                if let Some(text) = fetch_text(&format!("{BASE_URL}{name}?t={}", cache_bust())).await {
+                    if lib.borrow_mut().update(&name, text) {
+                        changed.borrow_mut().push(name);
+                    }
+                }
+            }
+            busy.set(false);
+        });
+    }) as Box<dyn FnMut()>);
+
+    let win = window().ok_or_else(|| VizError::MissingElement("window".into()))?;
+    let f: &js_sys::Function = tick.as_ref().unchecked_ref();
+    f.call0(&JsValue::NULL)?;
+    let interval = win.set_interval_with_callback_and_timeout_and_arguments_0(f, POLL_MS)?;
+    Ok(Watcher { interval, _tick: tick })
+}
+
+/// Handle to the polling started by [`watch`]; clears the interval on drop.
+pub struct Watcher {
+    interval: i32,
+    _tick: Closure<dyn FnMut()>,
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        if let Some(win) = window() {
+            win.clear_interval_with_handle(self.interval);
+        }
+    }
+}
+
+// `{"post.frag": 1712345678901, ...}` -> [(name, mtime)]
+fn parse_manifest(text: &str) -> Option<Vec<(String, f64)>> {
+    let obj: js_sys::Object = js_sys::JSON::parse(text).ok()?.dyn_into().ok()?;
+    let entries = js_sys::Object::entries(&obj);
+    Some(
+        entries
+            .iter()
+            .filter_map(|e| {
+                let pair: js_sys::Array = e.dyn_into().ok()?;
+                Some((pair.get(0).as_string()?, pair.get(1).as_f64()?))
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,335 @@
+#![cfg(target_arch = "wasm32")]
+
+//! Scene drawing a PNG, SVG file or SVG path as the shape. The picture is
+//! rasterized once on a 2D canvas, uploaded, and turned into a distance field
+//! by jump flooding on the GPU (see [`crate::silhouette`]).
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{
+    window, CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement, Path2d, WebGl2RenderingContext as GL,
+    WebGlBuffer, WebGlFramebuffer, WebGlTexture, WebGlUniformLocation,
+};
+
+use super::error::VizError;
+use super::glutil::{delete, track, uniforms, Pending};
+use super::scenes::{draw_fullscreen, fullscreen_vbo, set_pose, ParamCache, SdfUniforms, Visualizer};
+use super::shaders::{ShaderLibrary, ShaderProgram};
+use crate::anim::Pose;
+use crate::silhouette::{jump_flood_steps, raster_size, SilhouetteConfig, SilhouetteSource, SDF_RANGE};
+
+uniforms! { struct SilhouetteUniforms { u_image, u_sdf, u_fit, u_has_sdf, u_sdf_scale } }
+// Shared by the three jump-flood programs; each uses a subset
+uniforms! { struct JfaUniforms { u_image, u_seeds, u_step, u_range } }
+
+pub struct SilhouetteScene {
+    config: SilhouetteConfig,
+    // The rasterized picture, once loaded; uploaded by the next `render`
+    raster: Rc<RefCell<Option<HtmlCanvasElement>>>,
+    // Cleared by `destroy`; an image loading after that is dropped
+    alive: Rc<Cell<bool>>,
+    prog: Option<ShaderProgram>,
+    pose_u: Option<SdfUniforms>,
+    u: Option<SilhouetteUniforms>,
+    params: ParamCache,
+    vbo: Option<WebGlBuffer>,
+    jfa: Option<JumpFlood>,
+    image: Option<WebGlTexture>,
+    sdf: Option<WebGlTexture>,
+    size: (u32, u32),
+}
+
+impl SilhouetteScene {
+    pub fn new(config: SilhouetteConfig) -> Self {
+        Self {
+            config,
+            raster: Rc::new(RefCell::new(None)),
+            alive: Rc::new(Cell::new(true)),
+            prog: None,
+            pose_u: None,
+            u: None,
+            params: Vec::new(),
+            vbo: None,
+            jfa: None,
+            image: None,
+            sdf: None,
+            size: (1, 1),
+        }
+    }
+
+    // Upload the picture and build its distance field. Leaves the caller's
+    // framebuffer and viewport bound.
+    fn upload(&mut self, gl: &GL, canvas: &HtmlCanvasElement) -> Result<(), VizError> {
+        let tex = track(gl.create_texture()).ok_or(VizError::Resource("image texture"))?;
+        gl.bind_texture(GL::TEXTURE_2D, Some(&tex));
+        set_filter(gl, GL::LINEAR);
+        let uploaded =
+            gl.tex_image_2d_with_u32_and_u32_and_html_canvas_element(GL::TEXTURE_2D, 0, GL::RGBA as i32, GL::RGBA, GL::UNSIGNED_BYTE, canvas);
+        gl.bind_texture(GL::TEXTURE_2D, None);
+        if let Err(e) = uploaded {
+            delete(gl, &tex);
+            return Err(e.into());
+        }
+        self.size = (canvas.width(), canvas.height());
+        if let (Some(jfa), Some(vbo)) = (&self.jfa, &self.vbo) {
+            let fbo = gl.get_parameter(GL::FRAMEBUFFER_BINDING).ok().and_then(|v| v.dyn_into::<WebGlFramebuffer>().ok());
+            let viewport = gl.get_parameter(GL::VIEWPORT).ok().and_then(|v| v.dyn_into::<js_sys::Int32Array>().ok());
+            let field = jfa.run(gl, vbo, &tex, self.size.0 as i32, self.size.1 as i32);
+            gl.bind_framebuffer(GL::FRAMEBUFFER, fbo.as_ref());
+            if let Some(v) = viewport.map(|v| v.to_vec()) {
+                gl.viewport(v[0], v[1], v[2], v[3]);
+            }
+            match field {
+                Ok(field) => self.sdf = Some(field),
+                // Hard alpha edges still work
+                Err(e) => web_sys::console::error_1(&format!("silhouette distance field: {e}").into()),
+            }
+        }
+        self.image = Some(tex);
+        Ok(())
+    }
+}
+
+fn set_filter(gl: &GL, filter: u32) {
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, filter as i32);
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, filter as i32);
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+    gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+}
+
+fn canvas_2d(w: u32, h: u32) -> Result<(HtmlCanvasElement, CanvasRenderingContext2d), VizError> {
+    let document = window()
+        .and_then(|w| w.document())
+        .ok_or_else(|| VizError::MissingElement("document".into()))?;
+    let canvas: HtmlCanvasElement = document
+        .create_element("canvas")?
+        .dyn_into()
+        .map_err(|_| VizError::MissingElement("canvas element".into()))?;
+    canvas.set_width(w);
+    canvas.set_height(h);
+    let ctx: CanvasRenderingContext2d = canvas
+        .get_context("2d")?
+        .and_then(|c| c.dyn_into().ok())
+        .ok_or_else(|| VizError::ContextUnavailable("2d".into()))?;
+    Ok((canvas, ctx))
+}
+
+// `d` filled in white, `view_box` scaled to the raster.
+fn rasterize_path(d: &str, view_box: [f32; 4]) -> Result<HtmlCanvasElement, VizError> {
+    let [x, y, vw, vh] = view_box;
+    let (w, h) = raster_size(vw, vh);
+    let (canvas, ctx) = canvas_2d(w, h)?;
+    let path = Path2d::new_with_path_string(d)?;
+    ctx.scale(w as f64 / vw as f64, h as f64 / vh as f64)?;
+    ctx.translate(-x as f64, -y as f64)?;
+    ctx.set_fill_style_str("#fff");
+    ctx.fill_with_path_2d(&path);
+    Ok(canvas)
+}
+
+// Load `url` and rasterize it into `raster` once it arrives.
+fn load_image(url: &str, raster: Rc<RefCell<Option<HtmlCanvasElement>>>, alive: Rc<Cell<bool>>) -> Result<(), VizError> {
+    let img = HtmlImageElement::new()?;
+    img.set_cross_origin(Some("anonymous"));
+    let loaded = img.clone();
+    // Each is freed when it runs; the one that never fires leaks, as in `store::pick_file`
+    let on_load = Closure::once_into_js(move || {
+        if !alive.get() {
+            return;
+        }
+        let (w, h) = raster_size(loaded.natural_width() as f32, loaded.natural_height() as f32);
+        let drawn = canvas_2d(w, h).and_then(|(canvas, ctx)| {
+            ctx.draw_image_with_html_image_element_and_dw_and_dh(&loaded, 0.0, 0.0, w as f64, h as f64)?;
+            Ok(canvas)
+        });
+        match drawn {
+            Ok(canvas) => *raster.borrow_mut() = Some(canvas),
+            Err(e) => web_sys::console::error_1(&format!("silhouette: {e}").into()),
+        }
+    });
+    let failed = url.to_string();
+    let on_error = Closure::once_into_js(move || {
+        web_sys::console::error_1(&format!("silhouette: could not load {failed}").into());
+    });
+    img.set_onload(Some(on_load.unchecked_ref()));
+    img.set_onerror(Some(on_error.unchecked_ref()));
+    img.set_src(url);
+    Ok(())
+}
+
+impl Visualizer for SilhouetteScene {
+    fn name(&self) -> &'static str {
+        self.config.scene_name()
+    }
+
+    fn init(&mut self, gl: &GL, lib: &ShaderLibrary) -> Result<(), VizError> {
+        let prog = ShaderProgram::new(gl, lib, "fullscreen.vert", "silhouette.frag")?;
+        self.pose_u = Some(SdfUniforms::locate(gl, &prog.prog));
+        self.u = Some(SilhouetteUniforms::locate(gl, &prog.prog));
+        self.prog = Some(prog);
+        self.vbo = Some(fullscreen_vbo(gl)?);
+        if self.config.sdf {
+            self.jfa = Some(JumpFlood::new(gl, lib)?);
+        }
+        self.alive.set(true);
+        match &self.config.source {
+            SilhouetteSource::Image(url) => load_image(url, self.raster.clone(), self.alive.clone())?,
+            SilhouetteSource::Path { d, view_box } => *self.raster.borrow_mut() = Some(rasterize_path(d, *view_box)?),
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, gl: &GL, _t: f32, pose: &Pose) {
+        if self.image.is_none() {
+            let Some(canvas) = self.raster.borrow_mut().take() else { return };
+            if let Err(e) = self.upload(gl, &canvas) {
+                web_sys::console::error_1(&format!("silhouette: {e}").into());
+                return;
+            }
+        }
+        let (Some(prog), Some(pose_u), Some(u)) = (self.prog.as_ref(), self.pose_u.as_ref(), self.u.as_ref()) else {
+            return;
+        };
+        let (w, h) = self.size;
+        let fit = self.config.fit.scale(w as f32 / h as f32);
+        gl.use_program(Some(&prog.prog));
+        set_pose(gl, &prog.prog, pose_u, &mut self.params, pose);
+        gl.uniform2f(u.u_fit.as_ref(), fit[0], fit[1]);
+        gl.uniform1f(u.u_has_sdf.as_ref(), if self.sdf.is_some() { 1.0 } else { 0.0 });
+        // Field value - 0.5 -> texels -> scene units (one texel is 2 / (w * fit) across)
+        gl.uniform1f(u.u_sdf_scale.as_ref(), 2.0 * SDF_RANGE * 2.0 / (w as f32 * fit[0]));
+        gl.uniform1i(u.u_image.as_ref(), 0);
+        gl.uniform1i(u.u_sdf.as_ref(), 1);
+        gl.active_texture(GL::TEXTURE0);
+        gl.bind_texture(GL::TEXTURE_2D, self.image.as_ref());
+        gl.active_texture(GL::TEXTURE1);
+        gl.bind_texture(GL::TEXTURE_2D, self.sdf.as_ref());
+        draw_fullscreen(gl, self.vbo.as_ref());
+        gl.bind_texture(GL::TEXTURE_2D, None);
+        gl.active_texture(GL::TEXTURE0);
+        gl.bind_texture(GL::TEXTURE_2D, None);
+    }
+
+    fn reload(&mut self, gl: &GL, lib: &ShaderLibrary, changed: &[String]) -> Result<(), VizError> {
+        if let Some(prog) = self.prog.as_mut() {
+            if prog.reload(gl, lib, changed)? {
+                self.pose_u = Some(SdfUniforms::locate(gl, &prog.prog));
+                self.u = Some(SilhouetteUniforms::locate(gl, &prog.prog));
+                self.params.clear();
+            }
+        }
+        if let Some(jfa) = self.jfa.as_mut() {
+            jfa.reload(gl, lib, changed)?;
+        }
+        Ok(())
+    }
+
+    fn destroy(&mut self, gl: &GL) {
+        self.alive.set(false);
+        self.raster.borrow_mut().take();
+        if let Some(prog) = self.prog.take() {
+            prog.delete(gl);
+        }
+        if let Some(jfa) = self.jfa.take() {
+            jfa.delete(gl);
+        }
+        for obj in [self.vbo.take()].into_iter().flatten() {
+            delete(gl, &obj);
+        }
+        for tex in [self.image.take(), self.sdf.take()].into_iter().flatten() {
+            delete(gl, &tex);
+        }
+        self.pose_u = None;
+        self.u = None;
+        self.params.clear();
+    }
+}
+
+/// The jump-flood passes: seed, `log2(size)` flood steps, distance.
+struct JumpFlood {
+    seed: ShaderProgram,
+    step: ShaderProgram,
+    field: ShaderProgram,
+    // Uniforms of `seed`, `step` and `field`, in that order
+    u: [JfaUniforms; 3],
+}
+
+impl JumpFlood {
+    fn new(gl: &GL, lib: &ShaderLibrary) -> Result<Self, VizError> {
+        let seed = ShaderProgram::new(gl, lib, "fullscreen.vert", "jfa_seed.frag")?;
+        let step = ShaderProgram::new(gl, lib, "fullscreen.vert", "jfa_step.frag").inspect_err(|_| seed.delete(gl))?;
+        let field = ShaderProgram::new(gl, lib, "fullscreen.vert", "jfa_sdf.frag").inspect_err(|_| {
+            seed.delete(gl);
+            step.delete(gl);
+        })?;
+        let u = [&seed, &step, &field].map(|p| JfaUniforms::locate(gl, &p.prog));
+        Ok(Self { seed, step, field, u })
+    }
+
+    fn reload(&mut self, gl: &GL, lib: &ShaderLibrary, changed: &[String]) -> Result<(), VizError> {
+        for (prog, u) in [&mut self.seed, &mut self.step, &mut self.field].into_iter().zip(&mut self.u) {
+            if prog.reload(gl, lib, changed)? {
+                *u = JfaUniforms::locate(gl, &prog.prog);
+            }
+        }
+        Ok(())
+    }
+
+    fn delete(&self, gl: &GL) {
+        self.seed.delete(gl);
+        self.step.delete(gl);
+        self.field.delete(gl);
+    }
+
+    /// Distance field of the `w` × `h` `image` as a new texture. Binds its own
+    /// framebuffer; the caller restores theirs.
+    fn run(&self, gl: &GL, vbo: &WebGlBuffer, image: &WebGlTexture, w: i32, h: i32) -> Result<WebGlTexture, VizError> {
+        let target = |filter: u32| -> Result<WebGlTexture, VizError> {
+            let tex = track(gl.create_texture()).ok_or(VizError::Resource("distance field texture"))?;
+            gl.bind_texture(GL::TEXTURE_2D, Some(&tex));
+            gl.tex_storage_2d(GL::TEXTURE_2D, 1, GL::RGBA8, w, h);
+            set_filter(gl, filter);
+            Ok(tex)
+        };
+        // Scratch objects, deleted however this returns. The field is created
+        // last so nothing can fail once it exists.
+        let mut scratch = Pending::new(gl);
+        let seeds = [scratch.add(target(GL::NEAREST)?), scratch.add(target(GL::NEAREST)?)];
+        let fbo = scratch.add(track(gl.create_framebuffer()).ok_or(VizError::Resource("distance field framebuffer"))?);
+        let field = target(GL::LINEAR)?;
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&fbo));
+        gl.viewport(0, 0, w, h);
+        let pass = |prog: &ShaderProgram, out: &WebGlTexture, inputs: &[(&Option<WebGlUniformLocation>, &WebGlTexture)]| {
+            gl.framebuffer_texture_2d(GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0, GL::TEXTURE_2D, Some(out), 0);
+            gl.use_program(Some(&prog.prog));
+            for (unit, (loc, tex)) in inputs.iter().enumerate() {
+                gl.uniform1i(loc.as_ref(), unit as i32);
+                gl.active_texture(GL::TEXTURE0 + unit as u32);
+                gl.bind_texture(GL::TEXTURE_2D, Some(tex));
+            }
+            draw_fullscreen(gl, Some(vbo));
+        };
+
+        let [seed_u, step_u, field_u] = &self.u;
+        pass(&self.seed, &seeds[0], &[(&seed_u.u_image, image)]);
+        let mut cur = 0;
+        for step in jump_flood_steps(w as u32, h as u32) {
+            gl.use_program(Some(&self.step.prog));
+            gl.uniform1i(step_u.u_step.as_ref(), step as i32);
+            pass(&self.step, &seeds[1 - cur], &[(&step_u.u_seeds, &seeds[cur])]);
+            cur = 1 - cur;
+        }
+        gl.use_program(Some(&self.field.prog));
+        gl.uniform1f(field_u.u_range.as_ref(), SDF_RANGE);
+        pass(&self.field, &field, &[(&field_u.u_seeds, &seeds[cur]), (&field_u.u_image, image)]);
+
+        gl.bind_texture(GL::TEXTURE_2D, None);
+        gl.active_texture(GL::TEXTURE0);
+        gl.bind_texture(GL::TEXTURE_2D, None);
+        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+        drop(scratch);
+        Ok(field)
+    }
+}
@@ -0,0 +1,97 @@
+#![cfg(target_arch = "wasm32")]
+
+//! Settings kept in `localStorage`, and preset files saved and opened through
+//! the browser.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{window, Blob, BlobPropertyBag, HtmlAnchorElement, HtmlInputElement, Storage, Url};
+
+use super::error::VizError;
+use super::options::Options;
+use crate::settings::Settings;
+
+/// `localStorage` key used by the standalone page.
+pub const PAGE_KEY: &str = "viz-settings";
+
+fn storage() -> Option<Storage> {
+    window()?.local_storage().ok().flatten()
+}
+
+/// Settings stored under `key`. Defaults when there are none, storage is
+/// unavailable (some private modes) or the stored JSON does not parse.
+pub fn load(key: &str) -> Settings {
+    let Some(json) = storage().and_then(|s| s.get_item(key).ok().flatten()) else {
+        return Settings::default();
+    };
+    Settings::from_json(&json).unwrap_or_else(|e| {
+        web_sys::console::warn_1(&format!("{e}; starting from defaults").into());
+        Settings::default()
+    })
+}
+
+/// Best effort: a full or disabled storage only costs persistence.
+pub fn save(key: &str, settings: &Settings) {
+    if let Some(s) = storage() {
+        let _ = s.set_item(key, &settings.to_json());
+    }
+}
+
+/// Start `opts` from the settings stored under `key` and keep saving there.
+pub fn restore(opts: &mut Options, key: &str) {
+    let settings = load(key);
+    opts.restore(&settings);
+    opts.storage = Some(key.to_string());
+}
+
+/// Offer `text` as a JSON file download called `filename`.
+pub fn download(filename: &str, text: &str) -> Result<(), VizError> {
+    let document = window()
+        .and_then(|w| w.document())
+        .ok_or_else(|| VizError::MissingElement("document".into()))?;
+    let props = BlobPropertyBag::new();
+    props.set_type("application/json");
+    let blob = Blob::new_with_str_sequence_and_options(&js_sys::Array::of1(&JsValue::from_str(text)), &props)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+    let a: HtmlAnchorElement = document
+        .create_element("a")?
+        .dyn_into()
+        .map_err(|_| VizError::MissingElement("anchor element".into()))?;
+    a.set_href(&url);
+    a.set_download(filename);
+    a.click();
+    Url::revoke_object_url(&url)?;
+    Ok(())
+}
+
+/// Ask the user for a JSON file; its text is pushed to `inbox` once read.
+pub fn pick_file(inbox: Rc<RefCell<Vec<String>>>) -> Result<(), VizError> {
+    let document = window()
+        .and_then(|w| w.document())
+        .ok_or_else(|| VizError::MissingElement("document".into()))?;
+    let input: HtmlInputElement = document
+        .create_element("input")?
+        .dyn_into()
+        .map_err(|_| VizError::MissingElement("input element".into()))?;
+    input.set_type("file");
+    input.set_accept("application/json,.json");
+    let picked = input.clone();
+    // Freed when it runs; a cancelled dialog never fires `change` and leaks this
+    // one small closure.
+    let on_change = Closure::once_into_js(move || {
+        let Some(file) = picked.files().and_then(|f| f.get(0)) else {
+            return;
+        };
+        wasm_bindgen_futures::spawn_local(async move {
+            match wasm_bindgen_futures::JsFuture::from(file.text()).await {
+                Ok(text) => inbox.borrow_mut().extend(text.as_string()),
+                Err(e) => web_sys::console::error_1(&e),
+            }
+        });
+    });
+    input.set_onchange(Some(on_change.unchecked_ref()));
+    input.click();
+    Ok(())
+}
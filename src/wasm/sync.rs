@@ -0,0 +1,161 @@
+#![cfg(target_arch = "wasm32")]
+
+//! Network side of [`crate::sync`]: polls the host relay for its clock and,
+//! on followers, for the leader's latest state; posts it on the leader.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{window, RequestInit, Response};
+
+use super::error::VizError;
+use super::shaders::fetch_text;
+use crate::show::ShowState;
+use crate::sync::{same_show, ClockSync, Role, SyncState, TimeReply, SYNC_PORT};
+
+const POLL_MS: i32 = 250;
+/// Clock samples are taken every tick until [`ClockSync::KEEP`] are in, then
+/// every `TIME_EVERY` ticks to follow drift.
+const TIME_EVERY: u32 = 8;
+/// The leader reposts an unchanged show this often, for late joiners and to
+/// refresh the followers' time anchor.
+const HEARTBEAT_MS: f64 = 5000.0;
+
+fn local_ms() -> f64 {
+    window().and_then(|w| w.performance()).map(|p| p.now()).unwrap_or_else(js_sys::Date::now)
+}
+
+/// The page's host on [`SYNC_PORT`].
+pub fn default_url() -> String {
+    let loc = window().map(|w| w.location());
+    let protocol = loc.as_ref().and_then(|l| l.protocol().ok()).unwrap_or_else(|| "http:".into());
+    let host = loc.and_then(|l| l.hostname().ok()).filter(|h| !h.is_empty()).unwrap_or_else(|| "localhost".into());
+    format!("{protocol}//{host}:{SYNC_PORT}")
+}
+
+/// Polling of the relay at `url`; stops when dropped.
+pub struct SyncLink {
+    role: Role,
+    url: String,
+    clock: Rc<RefCell<ClockSync>>,
+    // Newest leader state not yet taken (followers)
+    inbox: Rc<RefCell<Option<SyncState>>>,
+    // Last show posted and when (leader, local ms)
+    published: Option<(ShowState, f64)>,
+    posting: Rc<Cell<bool>>,
+    interval: i32,
+    _tick: Closure<dyn FnMut()>,
+}
+
+impl SyncLink {
+    pub fn start(role: Role, url: &str) -> Result<Self, VizError> {
+        let url = url.trim_end_matches('/').to_string();
+        let clock = Rc::new(RefCell::new(ClockSync::default()));
+        let inbox: Rc<RefCell<Option<SyncState>>> = Rc::new(RefCell::new(None));
+        let seq = Rc::new(Cell::new(0u64));
+        let busy = Rc::new(Cell::new(false));
+        let mut ticks = 0u32;
+
+        let tick = {
+            let (url, clock, inbox) = (url.clone(), clock.clone(), inbox.clone());
+            Closure::wrap(Box::new(move || {
+                if busy.get() {
+                    return;
+                }
+                busy.set(true);
+                let sample = ticks < ClockSync::KEEP as u32 || ticks.is_multiple_of(TIME_EVERY);
+                ticks = ticks.wrapping_add(1);
+                let (url, clock, inbox, seq, busy) = (url.clone(), clock.clone(), inbox.clone(), seq.clone(), busy.clone());
+                wasm_bindgen_futures::spawn_local(async move {
+                    if sample {
+                        let sent = local_ms();
+                        let reply = fetch_text(&format!("{url}/time")).await;
+                        let received = local_ms();
+                        if let Some(t) = reply.and_then(|r| serde_json::from_str::<TimeReply>(&r).ok()) {
+                            clock.borrow_mut().sample(sent, t.host_ms, received);
+                        }
+                    }
+                    if role == Role::Follower {
+                        // 204 when nothing is newer, which `fetch_text` reads as empty
+                        let text = fetch_text(&format!("{url}/state?after={}", seq.get())).await;
+                        match text.filter(|t| !t.is_empty()).map(|t| serde_json::from_str::<SyncState>(&t)) {
+                            Some(Ok(state)) => {
+                                seq.set(state.seq);
+                                *inbox.borrow_mut() = Some(state);
+                            }
+                            Some(Err(e)) => web_sys::console::warn_1(&format!("sync state: {e}").into()),
+                            None => {}
+                        }
+                    }
+                    busy.set(false);
+                });
+            }) as Box<dyn FnMut()>)
+        };
+
+        let win = window().ok_or_else(|| VizError::MissingElement("window".into()))?;
+        let f: &js_sys::Function = tick.as_ref().unchecked_ref();
+        f.call0(&JsValue::NULL)?;
+        let interval = win.set_interval_with_callback_and_timeout_and_arguments_0(f, POLL_MS)?;
+        Ok(Self { role, url, clock, inbox, published: None, posting: Rc::new(Cell::new(false)), interval, _tick: tick })
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    /// Host time at local time `local_ms`, once the relay has answered.
+    pub fn host_now(&self, local_ms: f64) -> Option<f64> {
+        self.clock.borrow().host_now(local_ms)
+    }
+
+    /// Leader state received since the last call.
+    pub fn take(&self) -> Option<SyncState> {
+        self.inbox.borrow_mut().take()
+    }
+
+    /// Post `show`, as of local time `local_ms`, when it differs from what was
+    /// last posted or the heartbeat is due. Skipped until the clock is known
+    /// and while a post is in flight.
+    pub fn publish(&mut self, show: ShowState, local_ms: f64) {
+        let Some(host_ms) = self.host_now(local_ms) else {
+            return;
+        };
+        let due = match &self.published {
+            Some((last, at)) => !same_show(last, &show) || local_ms - at >= HEARTBEAT_MS,
+            None => true,
+        };
+        if !due || self.posting.get() {
+            return;
+        }
+        let body = serde_json::to_string(&SyncState { seq: 0, host_ms, show: show.clone() }).unwrap_or_default();
+        self.published = Some((show, local_ms));
+        self.posting.set(true);
+        let (url, posting) = (format!("{}/state", self.url), self.posting.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(e) = post(&url, &body).await {
+                web_sys::console::warn_1(&e);
+            }
+            posting.set(false);
+        });
+    }
+}
+
+impl Drop for SyncLink {
+    fn drop(&mut self) {
+        if let Some(win) = window() {
+            win.clear_interval_with_handle(self.interval);
+        }
+    }
+}
+
+// A string body is sent as `text/plain`, which needs no CORS preflight.
+async fn post(url: &str, body: &str) -> Result<(), JsValue> {
+    let win = window().ok_or_else(|| JsValue::from_str("no window"))?;
+    let init = RequestInit::new();
+    init.set_method("POST");
+    init.set_body(&JsValue::from_str(body));
+    let resp: Response = JsFuture::from(win.fetch_with_str_and_init(url, &init)).await?.dyn_into()?;
+    if resp.ok() { Ok(()) } else { Err(format!("sync post: HTTP {}", resp.status()).into()) }
+}
@@ -0,0 +1,175 @@
+#![cfg(target_arch = "wasm32")]
+
+//! Scene spelling out a message in the embedded SDF font. The layout is
+//! rebuilt only when the text changes, i.e. once a second while a countdown
+//! runs.
+
+use web_sys::{WebGl2RenderingContext as GL, WebGlBuffer, WebGlTexture};
+
+use super::error::VizError;
+use super::glutil::{delete, track, uniforms};
+use super::scenes::{set_pose, ParamCache, SdfUniforms, Visualizer};
+use super::shaders::{ShaderLibrary, ShaderProgram};
+use crate::anim::Pose;
+use crate::font::Atlas;
+use crate::text::{embedded_atlas, layout, TextConfig};
+
+uniforms! { struct TextUniforms { u_atlas, u_sdf_scale } }
+
+// x, y, u, v per vertex
+const STRIDE: i32 = 4 * 4;
+
+pub struct TextScene {
+    config: TextConfig,
+    atlas: Option<Atlas>,
+    prog: Option<ShaderProgram>,
+    pose_u: Option<SdfUniforms>,
+    u: Option<TextUniforms>,
+    params: ParamCache,
+    texture: Option<WebGlTexture>,
+    vbo: Option<WebGlBuffer>,
+    // Message the buffer holds, its vertex count and scene units per atlas pixel
+    shown: Option<String>,
+    vertices: i32,
+    px: f32,
+}
+
+impl TextScene {
+    pub fn new(config: TextConfig) -> Self {
+        Self {
+            config,
+            atlas: None,
+            prog: None,
+            pose_u: None,
+            u: None,
+            params: Vec::new(),
+            texture: None,
+            vbo: None,
+            shown: None,
+            vertices: 0,
+            px: 0.0,
+        }
+    }
+
+    // Lay `message` out into the vertex buffer.
+    fn relayout(&mut self, gl: &GL, message: String) {
+        let Some(atlas) = self.atlas.as_ref() else { return };
+        let l = layout(atlas, &message);
+        let verts: Vec<f32> = l
+            .quads
+            .iter()
+            .flat_map(|q| {
+                let ([x0, y0, x1, y1], [u0, v0, u1, v1]) = (q.pos, q.uv);
+                [x0, y0, u0, v0, x1, y0, u1, v0, x1, y1, u1, v1, x0, y0, u0, v0, x1, y1, u1, v1, x0, y1, u0, v1]
+            })
+            .collect();
+        gl.bind_buffer(GL::ARRAY_BUFFER, self.vbo.as_ref());
+        unsafe {
+            let fa = js_sys::Float32Array::view(&verts);
+            gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &fa, GL::DYNAMIC_DRAW);
+        }
+        gl.bind_buffer(GL::ARRAY_BUFFER, None);
+        self.vertices = (verts.len() / 4) as i32;
+        self.px = l.px;
+        self.shown = Some(message);
+    }
+}
+
+impl Visualizer for TextScene {
+    fn name(&self) -> &'static str {
+        self.config.scene_name()
+    }
+
+    fn init(&mut self, gl: &GL, lib: &ShaderLibrary) -> Result<(), VizError> {
+        let prog = ShaderProgram::new(gl, lib, "text.vert", "text.frag")?;
+        self.pose_u = Some(SdfUniforms::locate(gl, &prog.prog));
+        self.u = Some(TextUniforms::locate(gl, &prog.prog));
+        self.prog = Some(prog);
+        self.vbo = Some(track(gl.create_buffer()).ok_or(VizError::Resource("vertex buffer"))?);
+
+        let atlas = embedded_atlas();
+        let tex = track(gl.create_texture()).ok_or(VizError::Resource("font atlas texture"))?;
+        gl.bind_texture(GL::TEXTURE_2D, Some(&tex));
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::LINEAR as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::LINEAR as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+        // Rows are 512 bytes, so the default 4-byte unpack alignment holds
+        let uploaded = gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            GL::TEXTURE_2D,
+            0,
+            GL::R8 as i32,
+            atlas.width as i32,
+            atlas.height as i32,
+            0,
+            GL::RED,
+            GL::UNSIGNED_BYTE,
+            Some(&atlas.pixels),
+        );
+        gl.bind_texture(GL::TEXTURE_2D, None);
+        self.texture = Some(tex);
+        uploaded?;
+        self.atlas = Some(atlas);
+        self.shown = None;
+        Ok(())
+    }
+
+    fn render(&mut self, gl: &GL, _t: f32, pose: &Pose) {
+        let message = self.config.message(js_sys::Date::now());
+        if self.shown.as_ref() != Some(&message) {
+            self.relayout(gl, message);
+        }
+        let (Some(prog), Some(pose_u), Some(u)) = (self.prog.as_ref(), self.pose_u.as_ref(), self.u.as_ref()) else {
+            return;
+        };
+        let Some(atlas) = self.atlas.as_ref() else { return };
+        if self.vertices == 0 {
+            return;
+        }
+        gl.use_program(Some(&prog.prog));
+        set_pose(gl, &prog.prog, pose_u, &mut self.params, pose);
+        gl.uniform1i(u.u_atlas.as_ref(), 0);
+        gl.uniform1f(u.u_sdf_scale.as_ref(), 2.0 * atlas.range * self.px);
+        gl.active_texture(GL::TEXTURE0);
+        gl.bind_texture(GL::TEXTURE_2D, self.texture.as_ref());
+        gl.bind_buffer(GL::ARRAY_BUFFER, self.vbo.as_ref());
+        gl.enable_vertex_attrib_array(0);
+        gl.enable_vertex_attrib_array(1);
+        gl.vertex_attrib_pointer_with_i32(0, 2, GL::FLOAT, false, STRIDE, 0);
+        gl.vertex_attrib_pointer_with_i32(1, 2, GL::FLOAT, false, STRIDE, 8);
+        gl.draw_arrays(GL::TRIANGLES, 0, self.vertices);
+        gl.disable_vertex_attrib_array(1);
+        gl.disable_vertex_attrib_array(0);
+        gl.bind_buffer(GL::ARRAY_BUFFER, None);
+        gl.bind_texture(GL::TEXTURE_2D, None);
+    }
+
+    fn reload(&mut self, gl: &GL, lib: &ShaderLibrary, changed: &[String]) -> Result<(), VizError> {
+        if let Some(prog) = self.prog.as_mut() {
+            if prog.reload(gl, lib, changed)? {
+                self.pose_u = Some(SdfUniforms::locate(gl, &prog.prog));
+                self.u = Some(TextUniforms::locate(gl, &prog.prog));
+                self.params.clear();
+            }
+        }
+        Ok(())
+    }
+
+    fn destroy(&mut self, gl: &GL) {
+        if let Some(prog) = self.prog.take() {
+            prog.delete(gl);
+        }
+        if let Some(vbo) = self.vbo.take() {
+            delete(gl, &vbo);
+        }
+        if let Some(tex) = self.texture.take() {
+            delete(gl, &tex);
+        }
+        self.atlas = None;
+        self.shown = None;
+        self.vertices = 0;
+        self.pose_u = None;
+        self.u = None;
+        self.params.clear();
+    }
+}
@@ -0,0 +1,364 @@
+#![cfg(target_arch = "wasm32")]
+
+//! Scene showing a video file or webcam: every new frame is uploaded into a
+//! texture and keyed into the mask by `video.frag` (see [`crate::video`]).
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    window, HtmlVideoElement, MediaStream, MediaStreamConstraints, MediaStreamTrack, WebGl2RenderingContext as GL,
+    WebGlBuffer, WebGlTexture,
+};
+
+use super::error::VizError;
+use super::glutil::{delete, track, uniforms};
+use super::scenes::{draw_fullscreen, fullscreen_vbo, Visualizer};
+use super::shaders::{ShaderLibrary, ShaderProgram};
+use crate::anim::Pose;
+use crate::video::{MaskMode, VideoConfig, VideoSource};
+
+uniforms! {
+    struct VideoUniforms {
+        u_scale, u_rot, u_offset, u_video, u_prev,
+        u_mask_mode, u_threshold, u_key, u_video_aspect, u_mirror,
+    }
+}
+
+// `HTMLMediaElement.HAVE_CURRENT_DATA`: a frame can be drawn.
+const HAVE_CURRENT_DATA: u16 = 2;
+
+pub struct VideoScene {
+    config: VideoConfig,
+    video: Option<HtmlVideoElement>,
+    // Created here (not passed in), so `destroy` stops and releases it
+    owned: bool,
+    // Webcam stream, once granted
+    stream: Rc<RefCell<Option<MediaStream>>>,
+    // Cleared by `destroy`; a webcam granted later is stopped right away
+    alive: Rc<Cell<bool>>,
+    prog: Option<ShaderProgram>,
+    u: Option<VideoUniforms>,
+    vbo: Option<WebGlBuffer>,
+    // Current and previous frame, alternating
+    frames: Vec<WebGlTexture>,
+    current: usize,
+    uploaded: usize,
+    last_time: f64,
+    aspect: f32,
+}
+
+impl VideoScene {
+    /// Plays `config.source` once initialised.
+    pub fn new(config: VideoConfig) -> Self {
+        Self::build(config, None)
+    }
+
+    /// Shows an element the caller set up and keeps playing; `config.source`
+    /// only names the scene.
+    pub fn with_element(config: VideoConfig, video: HtmlVideoElement) -> Self {
+        Self::build(config, Some(video))
+    }
+
+    fn build(config: VideoConfig, video: Option<HtmlVideoElement>) -> Self {
+        Self {
+            config,
+            owned: video.is_none(),
+            video,
+            stream: Rc::new(RefCell::new(None)),
+            alive: Rc::new(Cell::new(true)),
+            prog: None,
+            u: None,
+            vbo: None,
+            frames: Vec::new(),
+            current: 0,
+            uploaded: 0,
+            last_time: -1.0,
+            aspect: 1.0,
+        }
+    }
+
+    // A muted, looping, inline element for `source`; webcams attach when granted.
+    fn open(&self) -> Result<HtmlVideoElement, VizError> {
+        let document = window()
+            .and_then(|w| w.document())
+            .ok_or_else(|| VizError::MissingElement("document".into()))?;
+        let video: HtmlVideoElement = document
+            .create_element("video")?
+            .dyn_into()
+            .map_err(|_| VizError::MissingElement("video element".into()))?;
+        video.set_muted(true);
+        video.set_loop(true);
+        video.set_autoplay(true);
+        // iOS only plays muted video inline with this set
+        video.set_attribute("playsinline", "")?;
+        match &self.config.source {
+            VideoSource::Url(url) => {
+                // Lets frames of other origins be uploaded when they send CORS headers
+                video.set_cross_origin(Some("anonymous"));
+                video.set_src(url);
+                play(&video);
+            }
+            VideoSource::Webcam => {
+                let devices = window()
+                    .ok_or_else(|| VizError::MissingElement("window".into()))?
+                    .navigator()
+                    .media_devices()?;
+                let constraints = MediaStreamConstraints::new();
+                constraints.set_video(&JsValue::TRUE);
+                let request = devices.get_user_media_with_constraints(&constraints)?;
+                let (target, stream, alive) = (video.clone(), self.stream.clone(), self.alive.clone());
+                wasm_bindgen_futures::spawn_local(async move {
+                    match JsFuture::from(request).await.map(MediaStream::unchecked_from_js) {
+                        Ok(s) if alive.get() => {
+                            target.set_src_object(Some(&s));
+                            play(&target);
+                            *stream.borrow_mut() = Some(s);
+                        }
+                        Ok(s) => stop(&s),
+                        Err(e) => web_sys::console::error_2(&"webcam unavailable:".into(), &e),
+                    }
+                });
+            }
+        }
+        Ok(video)
+    }
+
+    // Upload the element's frame when it shows a new one.
+    fn upload(&mut self, gl: &GL) {
+        let Some(video) = &self.video else { return };
+        if video.ready_state() < HAVE_CURRENT_DATA || video.video_width() == 0 {
+            return;
+        }
+        let t = video.current_time();
+        if t == self.last_time {
+            return;
+        }
+        let next = (self.current + 1) % self.frames.len();
+        gl.bind_texture(GL::TEXTURE_2D, Some(&self.frames[next]));
+        let ok = gl
+            .tex_image_2d_with_u32_and_u32_and_html_video_element(
+                GL::TEXTURE_2D,
+                0,
+                GL::RGBA as i32,
+                GL::RGBA,
+                GL::UNSIGNED_BYTE,
+                video,
+            )
+            .is_ok();
+        gl.bind_texture(GL::TEXTURE_2D, None);
+        // Cross-origin video without CORS taints the context; nothing to show
+        if ok {
+            self.last_time = t;
+            self.current = next;
+            self.uploaded += 1;
+            self.aspect = video.video_width() as f32 / video.video_height().max(1) as f32;
+        }
+    }
+}
+
+// Autoplay may be refused until the user interacts; muted video rarely is.
+fn play(video: &HtmlVideoElement) {
+    if let Ok(p) = video.play() {
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(e) = JsFuture::from(p).await {
+                web_sys::console::warn_2(&"video did not start:".into(), &e);
+            }
+        });
+    }
+}
+
+fn stop(stream: &MediaStream) {
+    for track in stream.get_tracks().iter() {
+        track.unchecked_into::<MediaStreamTrack>().stop();
+    }
+}
+
+impl Visualizer for VideoScene {
+    fn name(&self) -> &'static str {
+        self.config.scene_name()
+    }
+
+    fn init(&mut self, gl: &GL, lib: &ShaderLibrary) -> Result<(), VizError> {
+        let prog = ShaderProgram::new(gl, lib, "fullscreen.vert", "video.frag")?;
+        self.u = Some(VideoUniforms::locate(gl, &prog.prog));
+        self.prog = Some(prog);
+        self.vbo = Some(fullscreen_vbo(gl)?);
+        for _ in 0..2 {
+            let tex = track(gl.create_texture()).ok_or(VizError::Resource("video texture"))?;
+            gl.bind_texture(GL::TEXTURE_2D, Some(&tex));
+            gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::LINEAR as i32);
+            gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::LINEAR as i32);
+            gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+            gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+            self.frames.push(tex);
+        }
+        gl.bind_texture(GL::TEXTURE_2D, None);
+        if self.video.is_none() {
+            self.video = Some(self.open()?);
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, gl: &GL, _t: f32, pose: &Pose) {
+        if self.frames.is_empty() {
+            return;
+        }
+        self.upload(gl);
+        let (Some(prog), Some(u)) = (self.prog.as_ref(), self.u.as_ref()) else { return };
+        // Nothing to key before the first frame; the cleared targets stay black
+        if self.uploaded == 0 {
+            return;
+        }
+        // Until there are two frames the previous one is the current one
+        let prev = if self.uploaded > 1 { (self.current + 1) % self.frames.len() } else { self.current };
+        let mask = &self.config.mask;
+        let key = match *mask {
+            MaskMode::ChromaKey { key, .. } => key,
+            _ => [0.0; 3],
+        };
+        gl.use_program(Some(&prog.prog));
+        gl.uniform1f(u.u_scale.as_ref(), pose.scale);
+        gl.uniform1f(u.u_rot.as_ref(), pose.rotation);
+        gl.uniform2f(u.u_offset.as_ref(), pose.offset[0], pose.offset[1]);
+        gl.uniform1i(u.u_mask_mode.as_ref(), mask.id());
+        gl.uniform1f(u.u_threshold.as_ref(), mask.threshold());
+        gl.uniform3f(u.u_key.as_ref(), key[0], key[1], key[2]);
+        gl.uniform1f(u.u_video_aspect.as_ref(), self.aspect);
+        gl.uniform1f(u.u_mirror.as_ref(), if self.config.mirror { 1.0 } else { 0.0 });
+        gl.uniform1i(u.u_video.as_ref(), 0);
+        gl.uniform1i(u.u_prev.as_ref(), 1);
+        gl.active_texture(GL::TEXTURE0);
+        gl.bind_texture(GL::TEXTURE_2D, Some(&self.frames[self.current]));
+        gl.active_texture(GL::TEXTURE1);
+        gl.bind_texture(GL::TEXTURE_2D, Some(&self.frames[prev]));
+        draw_fullscreen(gl, self.vbo.as_ref());
+        gl.bind_texture(GL::TEXTURE_2D, None);
+        gl.active_texture(GL::TEXTURE0);
+        gl.bind_texture(GL::TEXTURE_2D, None);
+    }
+
+    fn reload(&mut self, gl: &GL, lib: &ShaderLibrary, changed: &[String]) -> Result<(), VizError> {
+        if let Some(prog) = self.prog.as_mut() {
+            if prog.reload(gl, lib, changed)? {
+                self.u = Some(VideoUniforms::locate(gl, &prog.prog));
+            }
+        }
+        Ok(())
+    }
+
+    fn destroy(&mut self, gl: &GL) {
+        self.alive.set(false);
+        if let Some(prog) = self.prog.take() {
+            prog.delete(gl);
+        }
+        if let Some(vbo) = self.vbo.take() {
+            delete(gl, &vbo);
+        }
+        for tex in self.frames.drain(..) {
+            delete(gl, &tex);
+        }
+        self.u = None;
+        self.uploaded = 0;
+        self.last_time = -1.0;
+        if let Some(stream) = self.stream.borrow_mut().take() {
+            stop(&stream);
+        }
+        if self.owned {
+            if let Some(video) = self.video.take() {
+                let _ = video.pause();
+                video.set_src_object(None);
+                video.remove_attribute("src").ok();
+                video.load();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wasm::glutil::{Globals, GlobalsUbo};
+    use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+    use wasm_bindgen_test::*;
+
+    const SIZE: i32 = 32;
+
+    fn canvas(size: u32) -> HtmlCanvasElement {
+        let document = window().unwrap().document().unwrap();
+        let canvas: HtmlCanvasElement = document.create_element("canvas").unwrap().dyn_into().unwrap();
+        canvas.set_width(size);
+        canvas.set_height(size);
+        canvas
+    }
+
+    async fn sleep(ms: i32) {
+        let p = js_sys::Promise::new(&mut |resolve, _| {
+            window().unwrap().set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, ms).unwrap();
+        });
+        JsFuture::from(p).await.unwrap();
+    }
+
+    // Fixture: a white square in the middle of a black frame, drawn on a 2D
+    // canvas and played back as a live stream, so no camera or network is needed.
+    async fn fixture_video() -> HtmlVideoElement {
+        let src = canvas(64);
+        let ctx: CanvasRenderingContext2d = src.get_context("2d").unwrap().unwrap().dyn_into().unwrap();
+        ctx.set_fill_style_str("#000");
+        ctx.fill_rect(0.0, 0.0, 64.0, 64.0);
+        ctx.set_fill_style_str("#fff");
+        ctx.fill_rect(16.0, 16.0, 32.0, 32.0);
+        let video: HtmlVideoElement = window().unwrap().document().unwrap().create_element("video").unwrap().dyn_into().unwrap();
+        video.set_muted(true);
+        video.set_src_object(Some(&src.capture_stream().unwrap()));
+        JsFuture::from(video.play().unwrap()).await.unwrap();
+        for _ in 0..100 {
+            if video.ready_state() >= HAVE_CURRENT_DATA && video.video_width() > 0 {
+                break;
+            }
+            sleep(20).await;
+        }
+        video
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn luminance_key_masks_the_bright_square() {
+        let video = fixture_video().await;
+        let gl: GL = canvas(SIZE as u32).get_context("webgl2").unwrap().unwrap().dyn_into().unwrap();
+        let lib = ShaderLibrary::embedded();
+        let globals = GlobalsUbo::new(&gl).unwrap();
+        globals.upload(&gl, &Globals { resolution: [SIZE as f32; 2], ..Globals::default() });
+
+        // Scene color and mask targets, as `Post` sets them up
+        let mut targets = Vec::new();
+        let fbo = gl.create_framebuffer().unwrap();
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&fbo));
+        for att in [GL::COLOR_ATTACHMENT0, GL::COLOR_ATTACHMENT1] {
+            let tex = gl.create_texture().unwrap();
+            gl.bind_texture(GL::TEXTURE_2D, Some(&tex));
+            gl.tex_storage_2d(GL::TEXTURE_2D, 1, GL::RGBA8, SIZE, SIZE);
+            gl.framebuffer_texture_2d(GL::FRAMEBUFFER, att, GL::TEXTURE_2D, Some(&tex), 0);
+            targets.push(tex);
+        }
+        gl.draw_buffers(&js_sys::Array::of2(&GL::COLOR_ATTACHMENT0.into(), &GL::COLOR_ATTACHMENT1.into()));
+        gl.viewport(0, 0, SIZE, SIZE);
+
+        let config = VideoConfig { source: VideoSource::Url("fixture".into()), mask: MaskMode::default(), mirror: false };
+        let mut scene = VideoScene::with_element(config, video);
+        scene.init(&gl, &lib).expect("init");
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&fbo));
+        scene.render(&gl, 0.0, &Pose::default());
+
+        gl.read_buffer(GL::COLOR_ATTACHMENT1);
+        let mut px = vec![0u8; (SIZE * SIZE * 4) as usize];
+        gl.read_pixels_with_opt_u8_array(0, 0, SIZE, SIZE, GL::RGBA, GL::UNSIGNED_BYTE, Some(&mut px)).unwrap();
+        let at = |x: i32, y: i32| px[((y * SIZE + x) * 4) as usize];
+        assert_eq!(at(SIZE / 2, SIZE / 2), 255, "centre of the square is masked");
+        assert_eq!(at(1, 1), 0, "black corner is not");
+
+        scene.destroy(&gl);
+        globals.delete(&gl);
+    }
+}
@@ -0,0 +1,8 @@
+vec3 hsv2rgb(vec3 c){
+    vec3 p = abs(fract(c.xxx + vec3(0.0, 2.0/6.0, 4.0/6.0)) * 6.0 - 3.0);
+    vec3 rgb = c.z * mix(vec3(1.0), clamp(p - 1.0, 0.0, 1.0), c.y);
+    return rgb;
+}
+
+// Rec. 709 luminance
+float luma(vec3 c){ return dot(c, vec3(0.2126,0.7152,0.0722)); }
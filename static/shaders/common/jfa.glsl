@@ -0,0 +1,11 @@
+// Jump flood (see src/silhouette.rs): seed coordinates are packed into RGBA8 as
+// two 16-bit values, so no float render targets are needed. All ones = no seed.
+const vec4 NO_SEED = vec4(1.0);
+
+vec4 pack_seed(ivec2 p){ return vec4(float(p.x / 256), float(p.x % 256), float(p.y / 256), float(p.y % 256)) / 255.0; }
+ivec2 unpack_seed(vec4 c){ ivec4 b = ivec4(round(c * 255.0)); return ivec2(b.r * 256 + b.g, b.b * 256 + b.a); }
+bool has_seed(vec4 c){ return any(lessThan(c, vec4(1.0))); }
+
+bool in_bounds(ivec2 p, ivec2 size){ return all(greaterThanEqual(p, ivec2(0))) && all(lessThan(p, size)); }
+// Inside the shape: alpha of at least one half. Outside the picture counts as outside.
+bool inside(sampler2D image, ivec2 p){ return in_bounds(p, textureSize(image, 0)) && texelFetch(image, p, 0).a >= 0.5; }
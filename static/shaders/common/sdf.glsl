@@ -0,0 +1,3 @@
+// 2D signed distance functions; negative inside.
+float sdCircle(vec2 p, float r){ return length(p)-r; }
+float sdBox(vec2 p, vec2 b){ vec2 d=abs(p)-b; return length(max(d,0.0))+min(max(d.x,d.y),0.0); }
@@ -0,0 +1,16 @@
+#include "common/color.glsl"
+
+// Sobel gradient of the luminance of `tex` around `uv`; `px` is one texel.
+vec2 sobel(sampler2D tex, vec2 uv, vec2 px){
+    float l00 = luma(texture(tex, uv + px*vec2(-1.0,-1.0)).rgb);
+    float l10 = luma(texture(tex, uv + px*vec2( 0.0,-1.0)).rgb);
+    float l20 = luma(texture(tex, uv + px*vec2( 1.0,-1.0)).rgb);
+    float l01 = luma(texture(tex, uv + px*vec2(-1.0, 0.0)).rgb);
+    float l21 = luma(texture(tex, uv + px*vec2( 1.0, 0.0)).rgb);
+    float l02 = luma(texture(tex, uv + px*vec2(-1.0, 1.0)).rgb);
+    float l12 = luma(texture(tex, uv + px*vec2( 0.0, 1.0)).rgb);
+    float l22 = luma(texture(tex, uv + px*vec2( 1.0, 1.0)).rgb);
+    float gx = (l20 + 2.0*l21 + l22) - (l00 + 2.0*l01 + l02);
+    float gy = (l02 + 2.0*l12 + l22) - (l00 + 2.0*l10 + l20);
+    return vec2(gx, gy);
+}
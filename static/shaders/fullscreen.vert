@@ -0,0 +1,4 @@
+#version 300 es
+// Oversized triangle covering the viewport; shared by every fullscreen pass.
+layout(location=0) in vec2 a_pos;
+void main(){ gl_Position = vec4(a_pos, 0.0, 1.0); }
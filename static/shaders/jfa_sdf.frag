@@ -0,0 +1,20 @@
+#version 300 es
+// Jump flood, last pass: signed distance to the nearest seed, negative inside,
+// stored as 0.5 + 0.5 * d / u_range.
+precision highp float;
+precision highp int;
+out vec4 o;
+uniform sampler2D u_seeds;
+uniform sampler2D u_image;
+uniform float u_range; // texels
+
+#include "common/jfa.glsl"
+
+void main(){
+    ivec2 p = ivec2(gl_FragCoord.xy);
+    vec4 s = texelFetch(u_seeds, p, 0);
+    // The outline runs half a texel beyond the seed pixels
+    float d = has_seed(s) ? min(distance(vec2(unpack_seed(s)), vec2(p)) + 0.5, u_range) : u_range;
+    if (inside(u_image, p)) d = -d;
+    o = vec4(vec3(0.5 + 0.5 * d / u_range), 1.0);
+}
@@ -0,0 +1,16 @@
+#version 300 es
+// Jump flood, first pass: pixels on the shape's outline are their own seed.
+precision highp float;
+precision highp int;
+out vec4 o;
+uniform sampler2D u_image;
+
+#include "common/jfa.glsl"
+
+void main(){
+    ivec2 p = ivec2(gl_FragCoord.xy);
+    bool here = inside(u_image, p);
+    bool edge = inside(u_image, p + ivec2(1, 0)) != here || inside(u_image, p + ivec2(-1, 0)) != here
+        || inside(u_image, p + ivec2(0, 1)) != here || inside(u_image, p + ivec2(0, -1)) != here;
+    o = edge ? pack_seed(p) : NO_SEED;
+}
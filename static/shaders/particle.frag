@@ -0,0 +1,25 @@
+#version 300 es
+// Particles scene, drawn twice: soft additive sprites into the colour target,
+// then hard discs into the mask target (drawBuffers switches the other output
+// off each time). highp so the transform uniforms match particle.vert's.
+precision highp float;
+#include "common/scene.glsl"
+
+uniform int u_pass;          // 0 colour, 1 mask
+in float v_heat;
+
+void main(){
+    vec2 c = gl_PointCoord * 2.0 - 1.0;
+    float r2 = dot(c, c);
+    if (u_pass == 0) {
+        if (r2 > 1.0) discard;
+        float g = exp(-4.0 * r2) * (1.0 - r2);
+        o = vec4(mix(vec3(0.55, 0.75, 1.0), vec3(1.0, 0.95, 0.8), v_heat) * g, g);
+        o_mask = vec4(0.0);
+    } else {
+        // Core of 0.6 of the sprite's radius
+        if (r2 > 0.36) discard;
+        o = vec4(0.0);
+        o_mask = vec4(1.0);
+    }
+}
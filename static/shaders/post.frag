@@ -0,0 +1,113 @@
+#version 300 es
+// Final pass: pattern fill inside the mask, flame edges, displacement and vignette.
+precision mediump float;
+out vec4 o;
+uniform sampler2D u_src;
+uniform sampler2D u_mask;
+uniform vec2 u_texel; // 1/size of the offscreen targets
+uniform float u_stripe_theta0;
+uniform float u_stripe_theta_speed;
+uniform float u_stripe_density;
+uniform float u_stripe_thickness;
+uniform vec2  u_stripe_drift_speed;
+uniform float u_color_speed;
+// Polka dot uniforms
+uniform float u_fill_mode; // 0 = stripes, 1 = polka
+uniform float u_dot_theta0;
+uniform float u_dot_theta_speed;
+uniform vec2  u_dot_drift_speed;
+uniform float u_dot_density;       // average dots per unit
+uniform float u_dot_radius_min;    // min radius in UV units
+uniform float u_dot_radius_max;    // max radius in UV units
+uniform float u_contrast;          // 1 = unchanged; lowered by the safety limiter
+uniform float u_motion;            // 1 = full; 0 = reduced motion: no displacement or flicker
+
+#include "common/color.glsl"
+#include "common/hash.glsl"
+#include "common/sobel.glsl"
+
+vec3 sample_src(vec2 uv){
+    vec2 c = uv - 0.5; float r = length(c); float ca = 0.002 * r;
+    vec3 col; col.r = texture(u_src, uv + ca * normalize(c)).r; col.g = texture(u_src, uv).g; col.b = texture(u_src, uv - ca * normalize(c)).b; return col;
+}
+
+void main(){
+    vec2 res = u_resolution;
+    // Compute a centered, square-normalized coordinate uv in [0,1]^2
+    float side = min(res.x, res.y);
+    vec2 origin = 0.5*(res - vec2(side));
+    vec2 uv = (gl_FragCoord.xy - origin) / side;
+    // Outside the centered square: black bars
+    if (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0)))) {
+        o = vec4(0.0,0.0,0.0,1.0);
+        return;
+    }
+    // Aspect-correct square space where effects stay consistent across viewport sizes
+    // uv is already normalized to the centered square; use it directly
+    vec2 a = vec2(min(res.x, res.y)) / res; // components <= 1
+    vec2 uv_sq = uv;
+
+    // Build displacement in square space
+    vec2 disp = vec2(0.0);
+    float wave = sin(uv_sq.y*12.0 + u_time*1.5) * 0.003; wave += sin((uv_sq.x+uv_sq.y)*10.0 - u_time*1.2) * 0.002; disp += vec2(wave, 0.0);
+    vec2 s1 = vec2(0.3+0.2*sin(u_time*0.4), 0.4+0.2*cos(u_time*0.35));
+    vec2 s2 = vec2(0.7+0.2*cos(u_time*0.37), 0.6+0.2*sin(u_time*0.31));
+    for(int i=0;i<2;i++){ vec2 c = (i==0)? s1 : s2; vec2 d = uv_sq - c; float r = length(d)+1e-4; float ang = 0.15 * sin(u_time*0.8 + r*25.0); mat2 rot = mat2(cos(ang),-sin(ang),sin(ang),cos(ang)); disp += (rot * d - d) * smoothstep(0.25, 0.0, r); }
+    for(int i=0; i<3; ++i){ vec2 seed = vec2(fract(sin(float(i)*12.9898+78.233)*43758.5453), fract(sin(float(i)*19.123+11.73)*24634.6345)); seed = 0.2 + 0.6*seed + 0.05*vec2(sin(u_time*(1.0+float(i)*0.3)+float(i)), cos(u_time*(1.2+float(i)*0.17)+float(i))); vec2 d = uv_sq - seed; float r = length(d); float r0 = 0.18 + 0.05*sin(u_time*1.7+float(i)); float amp = 0.008 * sin((r-r0)*40.0 - u_time*3.0); disp += normalize(d) * amp * smoothstep(r0, 0.0, r); }
+
+    // Apply displacement in square space, convert back to texture space for sampling
+    disp *= u_motion;
+    vec2 suv_sq = clamp(uv_sq + disp, 0.0, 1.0);
+    // Map square UVs back into the inscribed square band of the rectangular textures
+    vec2 suv = (suv_sq - 0.5) / a + 0.5;
+
+    vec3 base = sample_src(suv);
+    float mask = texture(u_mask, suv).r;
+
+    // Diagonal zebra stripes (aspect-invariant)
+    float t = u_time;
+    float theta = u_stripe_theta0 + u_stripe_theta_speed * t;
+    mat2 R = mat2(cos(theta), -sin(theta), sin(theta), cos(theta));
+    vec2 q = R * (suv_sq - 0.5) + u_stripe_drift_speed * t;
+    float s = fract(q.y * u_stripe_density);
+    float stripeMask = step(s, clamp(u_stripe_thickness, 0.02, 0.98));
+    float hue = fract(q.x * (u_stripe_density*0.5) + t * u_color_speed);
+    vec3 rainbow = hsv2rgb(vec3(hue, 0.9, 1.0));
+    vec3 stripes = stripeMask * rainbow;
+
+    // Polka dots pattern (aspect-invariant)
+    float theta_d = u_dot_theta0 + u_dot_theta_speed * t;
+    mat2 RD = mat2(cos(theta_d), -sin(theta_d), sin(theta_d), cos(theta_d));
+    vec2 pd = RD * (suv_sq - 0.5) + u_dot_drift_speed * t + 0.5;
+    // Grid cell and local coords
+    float dens = max(2.0, u_dot_density);
+    vec2 g = pd * dens;
+    vec2 cell = floor(g);
+    vec2 f = fract(g);
+    // Random center jitter within cell
+    vec2 j = (hash22(cell) - 0.5) * 0.8; // up to 40% of cell size
+    vec2 center = 0.5 + j;
+    float rmin = max(0.005, u_dot_radius_min);
+    float rmax = max(rmin+0.002, u_dot_radius_max);
+    float r = mix(rmin, rmax, hash12(cell+13.17));
+    float d = length(f - center);
+    float dotMask = step(d, r);
+    float hue_d = fract((cell.x + cell.y*1.37) * 0.15 + t * u_color_speed);
+    vec3 dotColor = hsv2rgb(vec3(hue_d, 0.9, 1.0));
+    vec3 polka = dotMask * dotColor;
+
+    // Pick pattern: u_fill_mode 0 -> stripes, 1 -> polka
+    vec3 pattern = mix(stripes, polka, clamp(u_fill_mode, 0.0, 1.0));
+
+    // Flaming edges from source
+    float edge = clamp(length(sobel(u_src, suv, u_texel))*1.5, 0.0, 1.0);
+    float flicker = 0.6 + 0.4*u_motion*sin(u_time*min(15.0, 6.2831853*u_max_flash_hz) + suv.x*30.0 + suv.y*25.0);
+    vec3 flame = vec3(1.0, 0.5, 0.05) * pow(edge, 0.8) * flicker;
+
+    vec3 col = mix(vec3(0.0), pattern, mask);
+    col += flame * 0.6;
+    float v = smoothstep(0.95, 0.4, length(uv_sq-0.5));
+    col *= v;
+    col = mix(vec3(0.25), col, u_contrast);
+    o = vec4(col, 1.0);
+}
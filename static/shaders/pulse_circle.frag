@@ -0,0 +1,15 @@
+#version 300 es
+precision mediump float;
+#include "common/scene.glsl"
+#include "common/sdf.glsl"
+
+void main(){
+    vec2 uv=gl_FragCoord.xy/u_resolution; vec2 p=toP(uv);
+    float d=sdCircle(p,0.7);
+    float clip=1.0 - smoothstep(0.85, 1.0, length(p));
+    float a=smoothstep(0.0,-0.005,d)*clip;
+    float m=step(d,0.0)*clip;
+    float bright=0.5+0.5*sin(u_scene_time);
+    o=vec4(vec3(bright), a);
+    o_mask=vec4(m,m,m,1.0);
+}
@@ -0,0 +1,41 @@
+#version 300 es
+// Video or webcam frame keyed into the mask; the frame itself is the color pass
+// the post pass traces flame edges on. Keying matches `MaskMode::mask` in src/video.rs.
+precision mediump float;
+#include "common/scene.glsl"
+#include "common/color.glsl"
+
+uniform sampler2D u_video;     // current frame
+uniform sampler2D u_prev;      // previous frame, for difference keying
+uniform int u_mask_mode;       // 0 = luminance, 1 = chroma key, 2 = difference
+uniform float u_threshold;     // threshold, or chroma-key tolerance
+uniform vec3 u_key;            // chroma-key colour
+uniform float u_video_aspect;  // frame width / height
+uniform float u_mirror;        // 1 = flip horizontally
+
+const float SOFTNESS = 0.05;
+
+vec2 chroma(vec3 c){ float y = luma(c); return vec2((c.b - y) / 1.8556, (c.r - y) / 1.5748); }
+
+void main(){
+    vec2 p = toP(gl_FragCoord.xy / u_resolution);
+    // Cover the centered square, cropping the longer side of the frame
+    vec2 uv = 0.5 + 0.5 * p * vec2(min(1.0, 1.0 / u_video_aspect), min(1.0, u_video_aspect));
+    if (u_mirror > 0.5) uv.x = 1.0 - uv.x;
+    // Frames are uploaded top row first
+    uv.y = 1.0 - uv.y;
+    float inside = step(0.0, uv.x) * step(uv.x, 1.0) * step(0.0, uv.y) * step(uv.y, 1.0);
+    vec3 col = texture(u_video, uv).rgb;
+    float m;
+    if (u_mask_mode == 1) {
+        m = smoothstep(u_threshold, u_threshold + SOFTNESS, distance(chroma(col), chroma(u_key)));
+    } else if (u_mask_mode == 2) {
+        float d = abs(luma(col) - luma(texture(u_prev, uv).rgb));
+        m = smoothstep(u_threshold - SOFTNESS, u_threshold + SOFTNESS, d);
+    } else {
+        m = smoothstep(u_threshold - SOFTNESS, u_threshold + SOFTNESS, luma(col));
+    }
+    m *= inside;
+    o = vec4(col * inside, m);
+    o_mask = vec4(vec3(step(0.5, m)), 1.0);
+}
@@ -0,0 +1,66 @@
+use viz_wasm::anim::{Animation, Curve, Ease, Key, Target, Wave};
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+#[test]
+fn easings_start_at_zero_and_end_at_one() {
+    for ease in [Ease::Linear, Ease::In, Ease::Out, Ease::InOut, Ease::Spring] {
+        assert!(close(ease.apply(0.0), 0.0), "{ease:?}");
+        assert!(close(ease.apply(1.0), 1.0), "{ease:?}");
+    }
+    assert_eq!(Ease::Step.apply(0.99), 0.0);
+    // The spring overshoots on the way
+    assert!((0..100).any(|i| Ease::Spring.apply(i as f32 / 100.0) > 1.0));
+}
+
+#[test]
+fn keyframes_interpolate_hold_and_loop() {
+    let keys = vec![
+        Key { t: 2.0, value: 10.0, ease: Ease::Linear },
+        Key { t: 0.0, value: 0.0, ease: Ease::Linear },
+        Key { t: 4.0, value: 10.0, ease: Ease::Step },
+    ];
+    let held = Curve::keyframes(keys.clone(), false);
+    assert_eq!(held.sample(-1.0), 0.0);
+    assert!(close(held.sample(1.0), 5.0));
+    assert_eq!(held.sample(3.0), 10.0);
+    assert_eq!(held.sample(9.0), 10.0);
+
+    let looped = Curve::keyframes(keys, true);
+    assert!(close(looped.sample(5.0), 5.0));
+}
+
+#[test]
+fn lfos_stay_within_amplitude() {
+    for wave in [Wave::Sine, Wave::Triangle, Wave::Random] {
+        let c = Curve::Lfo { wave, hz: 0.7, amp: 0.25, offset: 1.0, phase: 0.0 };
+        for i in 0..500 {
+            let v = c.sample(i as f32 * 0.037);
+            assert!((0.75..=1.25).contains(&v), "{wave:?} gave {v}");
+        }
+    }
+    let tri = Curve::Lfo { wave: Wave::Triangle, hz: 1.0, amp: 1.0, offset: 0.0, phase: 0.0 };
+    assert!(close(tri.sample(0.0), 0.0));
+    assert!(close(tri.sample(0.25), 1.0));
+    assert!(close(tri.sample(0.75), -1.0));
+}
+
+#[test]
+fn entry_tracks_override_scene_defaults() {
+    let scene = Animation::default().with(Target::Rotation, Curve::Ramp(0.5));
+    let entry = Animation::default()
+        .with(Target::Scale, Curve::Const(2.0))
+        .with(Target::Param("u_glow".into()), Curve::Const(0.3));
+    let pose = entry.over(&scene).sample(2.0);
+    assert_eq!(pose.rotation, 1.0);
+    assert_eq!(pose.scale, 2.0);
+    assert_eq!(pose.offset, [0.0, 0.0]);
+    assert_eq!(pose.params, vec![("u_glow".to_string(), 0.3)]);
+
+    let spin = Animation::default().with(Target::Rotation, Curve::Const(0.0)).over(&scene);
+    assert_eq!(spin.sample(2.0).rotation, 0.0);
+    assert!("u_glow".parse::<Target>().is_ok());
+    assert!("glow".parse::<Target>().is_err());
+}
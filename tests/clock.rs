@@ -0,0 +1,46 @@
+use viz_wasm::clock::{Clock, MAX_SPEED, STEP_MS};
+
+#[test]
+fn advances_with_wall_time_scaled_by_speed() {
+    let mut c = Clock::default();
+    assert_eq!(c.tick(5000.0), 0.0);
+    assert_eq!(c.tick(5100.0), 100.0);
+    c.set_speed(0.5);
+    assert_eq!(c.tick(5300.0), 200.0);
+    c.set_speed(100.0);
+    assert_eq!(c.speed(), MAX_SPEED);
+    c.set_speed(f64::NAN);
+    assert_eq!(c.speed(), MAX_SPEED);
+}
+
+#[test]
+fn pause_freezes_time_and_resume_skips_the_gap() {
+    let mut c = Clock::default();
+    c.tick(0.0);
+    c.tick(1000.0);
+    c.set_paused(true);
+    assert_eq!(c.tick(4000.0), 1000.0);
+    c.set_paused(false);
+    assert_eq!(c.tick(4016.0), 1016.0);
+}
+
+#[test]
+fn step_pauses_and_moves_by_whole_frames() {
+    let mut c = Clock::default();
+    c.tick(0.0);
+    c.step(3);
+    assert!(c.paused());
+    assert!((c.now() - 3.0 * STEP_MS).abs() < 1e-9);
+    c.step(-1);
+    assert!((c.now() - 2.0 * STEP_MS).abs() < 1e-9);
+    assert_eq!(c.tick(10_000.0), c.now());
+}
+
+#[test]
+fn seek_keeps_running_state() {
+    let mut c = Clock::default();
+    c.tick(0.0);
+    c.seek(2500.0);
+    assert_eq!(c.tick(100.0), 2600.0);
+    assert!(!c.paused());
+}
@@ -0,0 +1,69 @@
+use viz_wasm::font::{winding_inside, Atlas, Font, CHARSET, EM_PX, RANGE_PX};
+
+fn font_data() -> Vec<u8> {
+    std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/fonts/DejaVuSans-Bold.ttf")).unwrap()
+}
+
+#[test]
+fn reads_metrics_and_outlines() {
+    let data = font_data();
+    let font = Font::parse(&data).unwrap();
+    assert_eq!(font.units_per_em, 2048.0);
+    assert!(font.ascender > 0.0 && font.descender < 0.0);
+
+    let o = font.glyph_index('O').unwrap();
+    assert!(font.advance(o) > 1000.0);
+    let outline = font.outline(o);
+    assert_eq!(outline.len(), 2);
+    assert!(outline.iter().all(|c| c.first() == c.last()));
+    // The counter of the O is a hole, its stroke is filled
+    let (x0, x1) = outline.iter().flatten().fold((f32::MAX, f32::MIN), |(a, b), p| (a.min(p[0]), b.max(p[0])));
+    let (y0, y1) = outline.iter().flatten().fold((f32::MAX, f32::MIN), |(a, b), p| (a.min(p[1]), b.max(p[1])));
+    let y_mid = (y0 + y1) / 2.0;
+    assert!(!winding_inside(&outline, [(x0 + x1) / 2.0, y_mid]));
+    assert!(winding_inside(&outline, [x0 + 40.0, y_mid]));
+
+    assert!(font.outline(font.glyph_index(' ').unwrap()).is_empty());
+    assert_eq!(font.glyph_index('\u{e000}'), None);
+    assert!(Font::parse(b"not a font").is_err());
+}
+
+#[test]
+fn composite_glyphs_are_assembled() {
+    let data = font_data();
+    let font = Font::parse(&data).unwrap();
+    // é is e plus an accent above it
+    let e = font.outline(font.glyph_index('e').unwrap());
+    let e_acute = font.outline(font.glyph_index('é').unwrap());
+    assert!(e_acute.len() > e.len());
+    let top = |c: &[Vec<[f32; 2]>]| c.iter().flatten().map(|p| p[1]).fold(f32::MIN, f32::max);
+    assert!(top(&e_acute) > top(&e));
+}
+
+#[test]
+fn atlas_holds_every_glyph_apart() {
+    let data = font_data();
+    let atlas = Atlas::build(&Font::parse(&data).unwrap(), CHARSET);
+    assert_eq!(atlas.glyphs.len(), CHARSET.chars().count());
+    assert_eq!(atlas.pixels.len(), (atlas.width * atlas.height) as usize);
+    let rects: Vec<[u32; 4]> = atlas.glyphs.iter().map(|g| g.rect).filter(|r| r[2] > 0).collect();
+    for (i, a) in rects.iter().enumerate() {
+        assert!(a[0] + a[2] <= atlas.width && a[1] + a[3] <= atlas.height);
+        for b in &rects[i + 1..] {
+            let apart = a[0] + a[2] <= b[0] || b[0] + b[2] <= a[0] || a[1] + a[3] <= b[1] || b[1] + b[3] <= a[1];
+            assert!(apart, "{a:?} overlaps {b:?}");
+        }
+    }
+
+    // The field of "I" is inside at its center and saturated at its padded corner
+    let i = atlas.glyph('I').unwrap();
+    let [x, y, w, h] = i.rect;
+    let at = |px: u32, py: u32| atlas.pixels[(py * atlas.width + px) as usize];
+    assert!(at(x + w / 2, y + h / 2) < 100);
+    assert_eq!(at(x, y), 255);
+    // Padding is the field range on every side
+    let [l, b, r, t] = i.bounds;
+    assert!(((r - l) * EM_PX - w as f32).abs() < 1e-3 && ((t - b) * EM_PX - h as f32).abs() < 1e-3);
+    assert!(l < 0.0 && (l * EM_PX + RANGE_PX) > 0.0);
+    assert_eq!(atlas.glyph(' ').unwrap().rect, [0; 4]);
+}
@@ -0,0 +1,63 @@
+use viz_wasm::gesture::{Gesture, GestureRecognizer, LONG_PRESS_MS};
+
+#[test]
+fn horizontal_flick_is_a_swipe() {
+    let mut g = GestureRecognizer::default();
+    g.down(1, 300.0, 200.0, 0.0);
+    g.moved(1, 200.0, 210.0);
+    assert_eq!(g.up(1, 150.0, 215.0, 200.0), Some(Gesture::SwipeLeft));
+
+    g.down(2, 100.0, 200.0, 1000.0);
+    assert_eq!(g.up(2, 250.0, 190.0, 1200.0), Some(Gesture::SwipeRight));
+}
+
+#[test]
+fn slow_short_or_vertical_drags_are_not_swipes() {
+    let mut g = GestureRecognizer::default();
+    g.down(1, 300.0, 200.0, 0.0);
+    assert_eq!(g.up(1, 150.0, 200.0, 2000.0), None);
+    g.down(1, 300.0, 200.0, 3000.0);
+    assert_eq!(g.up(1, 280.0, 200.0, 3100.0), None);
+    g.down(1, 300.0, 200.0, 4000.0);
+    assert_eq!(g.up(1, 200.0, 400.0, 4100.0), None);
+}
+
+#[test]
+fn two_finger_tap() {
+    let mut g = GestureRecognizer::default();
+    g.down(1, 100.0, 100.0, 0.0);
+    g.down(2, 200.0, 100.0, 20.0);
+    assert_eq!(g.up(1, 101.0, 100.0, 120.0), None);
+    assert_eq!(g.up(2, 200.0, 101.0, 140.0), Some(Gesture::TwoFingerTap));
+}
+
+#[test]
+fn long_press_fires_once_and_suppresses_swipe() {
+    let mut g = GestureRecognizer::default();
+    g.down(1, 100.0, 100.0, 0.0);
+    assert_eq!(g.poll(LONG_PRESS_MS - 1.0), None);
+    assert_eq!(g.poll(LONG_PRESS_MS), Some(Gesture::LongPress));
+    assert_eq!(g.poll(LONG_PRESS_MS + 100.0), None);
+    assert_eq!(g.up(1, 100.0, 100.0, LONG_PRESS_MS + 200.0), None);
+}
+
+#[test]
+fn pinch_and_rotate_report_relative_changes() {
+    let mut g = GestureRecognizer::default();
+    g.down(1, 100.0, 100.0, 0.0);
+    g.down(2, 200.0, 100.0, 0.0);
+    let Some(Gesture::Transform { scale, rotate }) = g.moved(2, 300.0, 100.0) else {
+        panic!("expected a transform");
+    };
+    assert!((scale - 2.0).abs() < 1e-5);
+    assert!(rotate.abs() < 1e-5);
+    // Second finger moves from straight right to straight up (screen y down)
+    let Some(Gesture::Transform { scale, rotate }) = g.moved(2, 100.0, -100.0) else {
+        panic!("expected a transform");
+    };
+    assert!((scale - 1.0).abs() < 1e-5);
+    assert!((rotate - std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+    // A pinch is neither a tap nor a swipe
+    g.up(1, 100.0, 100.0, 500.0);
+    assert_eq!(g.up(2, 100.0, -100.0, 500.0), None);
+}
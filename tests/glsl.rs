@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use viz_wasm::glsl::{preprocess, Preprocessed, DEFINES_FILE, PRELUDE_FILE};
+
+fn run(files: &[(&str, &str)], root: &str, defines: &[(&str, &str)], prelude: &str) -> Result<Preprocessed, String> {
+    let files: HashMap<String, String> = files.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    let defines: Vec<(String, String)> = defines.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    preprocess(root, &defines, prelude, |f| files.get(f).cloned())
+}
+
+#[test]
+fn includes_are_expanded_once_and_tracked() {
+    let files = [
+        ("main.frag", "#version 300 es\n#include \"common/a.glsl\"\n#include \"common/b.glsl\"\nvoid main(){}\n"),
+        ("common/a.glsl", "float a(){ return 1.0; }\n"),
+        ("common/b.glsl", "#include \"common/a.glsl\"\nfloat b(){ return a(); }\n"),
+    ];
+    let out = run(&files, "main.frag", &[], "").unwrap();
+    assert_eq!(out.source, "#version 300 es\nfloat a(){ return 1.0; }\nfloat b(){ return a(); }\nvoid main(){}\n");
+    assert_eq!(out.files(), ["main.frag", "common/a.glsl", "common/b.glsl"]);
+}
+
+#[test]
+fn defines_and_prelude_follow_version_line() {
+    let files = [("s.frag", "#version 300 es\nvoid main(){}\n")];
+    let out = run(&files, "s.frag", &[("N", "4")], "uniform float t;\n").unwrap();
+    assert_eq!(out.source, "#version 300 es\n#define N 4\nuniform float t;\nvoid main(){}\n");
+    assert_eq!(out.origin(2), Some((DEFINES_FILE, 1)));
+    assert_eq!(out.origin(3), Some((PRELUDE_FILE, 1)));
+    assert_eq!(out.origin(4), Some(("s.frag", 2)));
+}
+
+#[test]
+fn compile_log_points_at_snippet() {
+    let files = [
+        ("s.frag", "#version 300 es\n#include \"common/x.glsl\"\nvoid main(){}\n"),
+        ("common/x.glsl", "// helper\nfloat x(){ return y; }\n"),
+    ];
+    let out = run(&files, "s.frag", &[("A", "1")], "").unwrap();
+    let log = "ERROR: 0:4: 'y' : undeclared identifier\nERROR: 0:40: unknown\n";
+    assert_eq!(
+        out.map_log(log),
+        "ERROR: common/x.glsl:2: 'y' : undeclared identifier\nERROR: 0:40: unknown\n"
+    );
+}
+
+#[test]
+fn missing_or_cyclic_includes_are_errors() {
+    let missing = [("s.frag", "void main(){}\n#include \"nope.glsl\"\n")];
+    let err = run(&missing, "s.frag", &[], "").unwrap_err();
+    assert_eq!(err, "s.frag:2: cannot find include \"nope.glsl\"");
+
+    let cyclic = [("s.frag", "#include \"a.glsl\"\n"), ("a.glsl", "#include \"s.frag\"\n")];
+    assert!(run(&cyclic, "s.frag", &[], "").unwrap_err().contains("include cycle"));
+
+    let malformed = [("s.frag", "#include <a.glsl>\n")];
+    assert!(run(&malformed, "s.frag", &[], "").unwrap_err().starts_with("s.frag:1:"));
+}
@@ -0,0 +1,47 @@
+use viz_wasm::keymap::{Command, Keymap};
+
+#[test]
+fn default_bindings() {
+    let km = Keymap::default();
+    assert_eq!(km.lookup(" "), Some(Command::Next));
+    assert_eq!(km.lookup("3"), Some(Command::Scene(3)));
+    assert_eq!(km.lookup("?"), Some(Command::Help));
+    // Letters ignore case
+    assert_eq!(km.lookup("B"), Some(Command::Blackout));
+    assert_eq!(km.lookup("Shift"), None);
+    assert_eq!(km.lookup("F2"), Some(Command::Preset(2)));
+    assert_eq!(km.lookup("Shift+F2"), Some(Command::SavePreset(2)));
+}
+
+#[test]
+fn bind_replaces_and_unbind_removes() {
+    let mut km = Keymap::default();
+    km.bind("n", Command::Next);
+    km.bind("B", Command::Prev);
+    assert_eq!(km.lookup("n"), Some(Command::Next));
+    assert_eq!(km.lookup("b"), Some(Command::Prev));
+    km.unbind(" ");
+    assert_eq!(km.lookup(" "), None);
+}
+
+#[test]
+fn command_names_round_trip() {
+    for (_, cmd) in Keymap::default().bindings() {
+        assert_eq!(cmd.to_string().parse::<Command>(), Ok(*cmd));
+    }
+    assert_eq!("scene-12".parse::<Command>(), Ok(Command::Scene(12)));
+    assert!("scene-0".parse::<Command>().is_err());
+    assert_eq!("save-preset-3".parse::<Command>(), Ok(Command::SavePreset(3)));
+    assert!("warp".parse::<Command>().is_err());
+}
+
+#[test]
+fn help_groups_keys_per_command() {
+    let mut km = Keymap::default();
+    km.bind("n", Command::Next);
+    let help = km.help();
+    assert!(help.iter().any(|l| l.starts_with("Space / n") && l.ends_with("next scene")));
+    assert!(help.iter().any(|l| l.starts_with("1-9") && l.ends_with("scene by number")));
+    assert_eq!(help.iter().filter(|l| l.contains("scene by number")).count(), 1);
+    assert!(help.iter().any(|l| l.starts_with("F1-F8") && l.ends_with("recall preset")));
+}
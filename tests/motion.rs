@@ -0,0 +1,24 @@
+use viz_wasm::motion::{calm, MotionPref, CALM_SPEED};
+use viz_wasm::pattern::{PatternParams, Rng};
+
+#[test]
+fn preference_resolves_against_the_system_setting() {
+    assert!(MotionPref::Auto.reduced(true));
+    assert!(!MotionPref::Auto.reduced(false));
+    assert!(MotionPref::Reduce.reduced(false));
+    assert!(!MotionPref::Full.reduced(true));
+    assert_eq!("reduce".parse(), Ok(MotionPref::Reduce));
+    assert!("still".parse::<MotionPref>().is_err());
+}
+
+#[test]
+fn calm_slows_speeds_and_keeps_the_look() {
+    let mut rng = Rng::new(3);
+    let p = PatternParams::random(|| rng.next_f32());
+    let c = calm(&p);
+    assert_eq!(c.theta_speed, p.theta_speed * CALM_SPEED);
+    assert_eq!(c.dot_drift_y, p.dot_drift_y * CALM_SPEED);
+    assert_eq!(c.color_speed, p.color_speed * CALM_SPEED);
+    assert_eq!((c.theta0, c.density, c.thickness, c.mode_polka), (p.theta0, p.density, p.thickness, p.mode_polka));
+    assert_eq!((c.dot_density, c.dot_rmin, c.dot_rmax), (p.dot_density, p.dot_rmin, p.dot_rmax));
+}
@@ -0,0 +1,87 @@
+use viz_wasm::particles::{attraction, born, curl, seed, step, Attractor, ParticleConfig, MAX_EMITTERS};
+
+#[test]
+fn curl_flow_is_divergence_free() {
+    let (scale, time, h) = (1.5, 2.0, 1e-2);
+    let mut largest = 0.0f32;
+    for i in 0..50 {
+        let p = [(i as f32 * 0.37).sin() * 0.9, (i as f32 * 0.61).cos() * 0.9];
+        let v = curl(p, time, scale);
+        largest = largest.max(v[0].hypot(v[1]));
+        let div = (curl([p[0] + h, p[1]], time, scale)[0] - curl([p[0] - h, p[1]], time, scale)[0]
+            + curl([p[0], p[1] + h], time, scale)[1]
+            - curl([p[0], p[1] - h], time, scale)[1])
+            / (2.0 * h);
+        assert!(div.abs() < 0.1, "divergence {div} at {p:?}");
+    }
+    assert!(largest > 0.5, "the flow moves: {largest}");
+}
+
+#[test]
+fn attractors_pull_and_repel() {
+    let pull = attraction([1.0, 0.0], &[Attractor { pos: [0.0, 0.0], strength: 1.0 }]);
+    assert!(pull[0] < 0.0 && pull[1] == 0.0);
+    let push = attraction([1.0, 0.0], &[Attractor { pos: [0.0, 0.0], strength: -1.0 }]);
+    assert_eq!(push, [-pull[0], 0.0]);
+    // Finite on top of the attractor
+    assert_eq!(attraction([0.0, 0.0], &[Attractor { pos: [0.0, 0.0], strength: 5.0 }]), [0.0, 0.0]);
+
+    let config = ParticleConfig { curl: 0.0, drag: 0.0, attractors: vec![Attractor { pos: [0.0, 0.5], strength: 1.0 }], ..ParticleConfig::default() };
+    let mut p = born(&config, 0, 0.0);
+    p.vel = [0.0, 0.0];
+    let start = (p.pos[0].powi(2) + (p.pos[1] - 0.5).powi(2)).sqrt();
+    for k in 0..20 {
+        step(&config, &mut p, 0, k as f32 * 0.01, 0.01);
+    }
+    let end = (p.pos[0].powi(2) + (p.pos[1] - 0.5).powi(2)).sqrt();
+    assert!(end < start, "{end} >= {start}");
+}
+
+#[test]
+fn particles_are_born_at_emitters_and_reborn_after_their_life() {
+    let config = ParticleConfig::default();
+    let near_emitter = |pos: [f32; 2]| {
+        config.emitters.iter().any(|e| ((pos[0] - e.pos[0]).powi(2) + (pos[1] - e.pos[1]).powi(2)).sqrt() <= e.radius + 1e-5)
+    };
+    for i in 0..200 {
+        let p = seed(&config, i);
+        assert!(near_emitter(p.pos));
+        assert!(p.age <= 0.0 && p.age >= -config.life[1]);
+        assert!((config.life[0]..=config.life[1]).contains(&p.life));
+    }
+    // Both emitters get used
+    let left = (0..200).filter(|&i| seed(&config, i).pos[0] < 0.0).count();
+    assert!(left > 50 && left < 150, "{left} of 200 on the left");
+
+    let mut p = seed(&config, 7);
+    let mut t = 0.0;
+    while p.age < 0.0 {
+        step(&config, &mut p, 7, t, 0.05);
+        t += 0.05;
+    }
+    assert_eq!(p.age, p.age.max(0.0));
+    while p.age < p.life {
+        step(&config, &mut p, 7, t, 0.05);
+        t += 0.05;
+    }
+    step(&config, &mut p, 7, t, 0.05);
+    assert_eq!(p.age, 0.0);
+    assert!(near_emitter(p.pos));
+}
+
+#[test]
+fn config_json_takes_defaults_and_is_validated() {
+    let c = ParticleConfig::from_json(r#"{"count": 500, "curlScale": 3, "attractors": []}"#).unwrap();
+    assert_eq!((c.count, c.curl_scale), (500, 3.0));
+    assert!(c.attractors.is_empty());
+    assert_eq!(c.emitters, ParticleConfig::default().emitters);
+
+    let e = r#"{"emitters": [{"pos": [0, 0]}]}"#;
+    assert_eq!(ParticleConfig::from_json(e).unwrap().emitters[0].radius, 0.1);
+    assert!(ParticleConfig::from_json(r#"{"count": 0}"#).is_err());
+    assert!(ParticleConfig::from_json(r#"{"emitters": []}"#).is_err());
+    let many = format!(r#"{{"emitters": [{}]}}"#, vec!["{}"; MAX_EMITTERS as usize + 1].join(","));
+    assert!(ParticleConfig::from_json(&many).is_err());
+    assert!(ParticleConfig::from_json(r#"{"life": [3, 1]}"#).is_err());
+    assert!(ParticleConfig::from_json(r#"{"count": "lots"}"#).is_err());
+}
@@ -0,0 +1,27 @@
+use viz_wasm::pattern::{PatternParams, Rng};
+
+#[test]
+fn same_seed_same_patterns() {
+    let (mut a, mut b) = (Rng::new(7), Rng::new(7));
+    for _ in 0..3 {
+        assert_eq!(PatternParams::random(|| a.next_f32()), PatternParams::random(|| b.next_f32()));
+    }
+    let (mut a, mut c) = (Rng::new(7), Rng::new(8));
+    assert_ne!(PatternParams::random(|| a.next_f32()), PatternParams::random(|| c.next_f32()));
+}
+
+#[test]
+fn rng_stays_in_unit_interval() {
+    let mut r = Rng::new(0);
+    assert!((0..10_000).map(|_| r.next_f32()).all(|x| (0.0..1.0).contains(&x)));
+}
+
+#[test]
+fn random_params_respect_ranges() {
+    let mut r = Rng::new(99);
+    for _ in 0..100 {
+        let p = PatternParams::random(|| r.next_f32());
+        assert!((8.0..32.0).contains(&p.density));
+        assert!(p.dot_rmin < p.dot_rmax);
+    }
+}
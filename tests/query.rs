@@ -0,0 +1,81 @@
+use viz_wasm::motion::MotionPref;
+use viz_wasm::query::{parse, DateTime, Fill, Query};
+use viz_wasm::silhouette::Fit;
+use viz_wasm::sync::Role;
+use viz_wasm::video::{MaskMode, VideoSource};
+
+#[test]
+fn parses_every_parameter() {
+    let q = parse("?scene=Pulsing%20Plus&seed=42&duration=7.5&fill=polka&overlay=off&autoplay=1&hud=on&safety=on&motion=reduce&sync=follower&relay=http%3A%2F%2F10.0.0.2%3A8001&video=webcam&mask=difference&image=logo.svg&fit=cover&text=OPEN%5CnAIR&countdown=2026-12-31T23%3A00&playlist=shows/a.json")
+        .unwrap();
+    assert_eq!(
+        q,
+        Query {
+            scene: Some("Pulsing Plus".into()),
+            seed: Some(42),
+            duration_s: Some(7.5),
+            fill: Some(Fill::Polka),
+            overlay: Some(false),
+            autoplay: Some(true),
+            hud: Some(true),
+            safety: Some(true),
+            motion: Some(MotionPref::Reduce),
+            sync: Some(Role::Follower),
+            relay: Some("http://10.0.0.2:8001".into()),
+            video: Some(VideoSource::Webcam),
+            mask: Some("difference".parse::<MaskMode>().unwrap()),
+            image: Some("logo.svg".into()),
+            fit: Some(Fit::Cover),
+            text: Some("OPEN\nAIR".into()),
+            countdown: Some("2026-12-31T23:00".parse().unwrap()),
+            playlist_url: Some("shows/a.json".into()),
+        }
+    );
+    assert_eq!(parse("").unwrap(), Query::default());
+    assert_eq!(parse("scene=Rotating+Square").unwrap().scene.as_deref(), Some("Rotating Square"));
+}
+
+#[test]
+fn reports_every_bad_parameter() {
+    let errors = parse("?seed=abc&fill=zigzag&colour=red&duration=-3&hud=").unwrap_err();
+    assert_eq!(errors.len(), 5, "{errors:?}");
+    assert!(errors.iter().any(|e| e.contains("unknown parameter \"colour\"")));
+    assert!(errors.iter().any(|e| e.starts_with("fill=zigzag")));
+    assert!(errors.iter().any(|e| e == "hud: missing value"));
+}
+
+#[test]
+fn countdown_dates_are_checked() {
+    let d: DateTime = "2026-12-31T23:00".parse().unwrap();
+    assert_eq!((d.year, d.month, d.day, d.hour, d.minute, d.second), (2026, 12, 31, 23, 0, 0));
+    // Local time: only the page knows the zone
+    assert_eq!(d.utc_offset_min, None);
+    assert_eq!(d.utc_ms(), None);
+
+    let ms = |s: &str| s.parse::<DateTime>().unwrap().utc_ms().unwrap();
+    assert_eq!(ms("1970-01-01T00:00Z"), 0.0);
+    assert_eq!(ms("2026-12-31T23:00:30Z"), 1_798_758_030_000.0);
+    assert_eq!(ms("2027-01-01T00:00+01:00"), ms("2026-12-31T23:00Z"));
+    assert_eq!(ms("2024-02-29T12:00-05:30"), ms("2024-02-29T17:30Z"));
+    assert_eq!("2026-12-31".parse::<DateTime>().unwrap().hour, 0);
+
+    for bad in ["tomorrow", "2026-13-01", "2025-02-29", "2026-12-31T24:00", "2026-12-31T23", "2026-12-31T23:00+1", "31.12.2026", "2026-12-31T23:00Zulu"] {
+        assert!(bad.parse::<DateTime>().is_err(), "{bad}");
+    }
+    let errors = parse("?text=Soon&countdown=next%20friday").unwrap_err();
+    assert_eq!(errors, ["countdown=next friday: expected a date like 2026-12-31 or 2026-12-31T23:00"]);
+}
+
+#[test]
+fn mask_needs_a_video() {
+    assert!(parse("?video=webcam&mask=chroma").is_ok());
+    assert_eq!(parse("?mask=chroma").unwrap_err(), ["mask: needs video= as well"]);
+    // The bad source is reported on its own
+    assert_eq!(parse("?video=&mask=chroma").unwrap_err(), ["video: missing value"]);
+}
+
+#[test]
+fn bad_escapes_are_kept_literally() {
+    assert_eq!(parse("scene=100%").unwrap().scene.as_deref(), Some("100%"));
+    assert_eq!(parse("scene=%zz%C3%A9").unwrap().scene.as_deref(), Some("%zzé"));
+}
@@ -0,0 +1,57 @@
+use viz_wasm::perf::{AdaptiveScale, FrameMonitor, ScaleConfig};
+
+// Feed `secs` worth of frames at a fixed frame time and return the final scale.
+fn run(scaler: &mut AdaptiveScale, cfg: &ScaleConfig, frame_ms: f64, secs: f64) -> f32 {
+    let mut mon = FrameMonitor::default();
+    let mut now = 0.0;
+    let mut scale = scaler.scale();
+    while now < secs * 1000.0 {
+        now += frame_ms;
+        if let Some(dt) = mon.tick(now) {
+            scale = scaler.update(cfg, mon.avg_ms(), dt);
+        }
+    }
+    scale
+}
+
+#[test]
+fn scale_drops_when_over_budget_and_respects_min() {
+    let cfg = ScaleConfig::new(0.5, 1.0, 60.0);
+    let mut s = AdaptiveScale::default();
+    let dropped = run(&mut s, &cfg, 33.0, 2.0);
+    assert!(dropped < 1.0, "scale should drop, got {dropped}");
+    let floor = run(&mut s, &cfg, 33.0, 20.0);
+    assert_eq!(floor, 0.5);
+}
+
+#[test]
+fn scale_recovers_when_within_budget() {
+    let cfg = ScaleConfig::new(0.5, 1.0, 60.0);
+    let mut s = AdaptiveScale::default();
+    run(&mut s, &cfg, 40.0, 10.0);
+    assert_eq!(s.scale(), 0.5);
+    let back = run(&mut s, &cfg, 16.6, 60.0);
+    assert_eq!(back, 1.0);
+}
+
+#[test]
+fn config_is_ordered_and_clamped() {
+    let cfg = ScaleConfig::new(1.5, 0.01, 1000.0);
+    assert_eq!(cfg.min_scale, 0.1);
+    assert_eq!(cfg.max_scale, 1.5);
+    assert_eq!(cfg.target_fps, 240.0);
+}
+
+#[test]
+fn bad_config_never_panics() {
+    let cfg = ScaleConfig::new(f32::NAN, f32::INFINITY, f32::NAN);
+    assert_eq!(cfg, ScaleConfig::default());
+
+    let inverted = ScaleConfig { min_scale: 1.0, max_scale: 0.5, target_fps: 60.0 };
+    assert_eq!(inverted.validated(), ScaleConfig::new(0.5, 1.0, 60.0));
+    let nan = ScaleConfig { min_scale: f32::NAN, max_scale: f32::NAN, target_fps: f32::NAN };
+    for cfg in [inverted, nan] {
+        let mut s = AdaptiveScale::default();
+        assert!(run(&mut s, &cfg, 40.0, 2.0).is_finite());
+    }
+}
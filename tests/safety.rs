@@ -0,0 +1,93 @@
+use viz_wasm::safety::{relative_luminance, FlashAnalyzer, SafetyLimiter, MAX_FLASHES_PER_S};
+
+const W: usize = 16;
+const FRAME_MS: f64 = 1000.0 / 60.0;
+
+fn solid(rgb: [u8; 3]) -> Vec<u8> {
+    (0..W * W).flat_map(|_| [rgb[0], rgb[1], rgb[2], 255]).collect()
+}
+
+// Two seconds at 60 fps of `a`/`b` alternating every `period` frames.
+fn run(a: &[u8], b: &[u8], period: usize) -> viz_wasm::safety::FlashReport {
+    let mut fa = FlashAnalyzer::default();
+    let mut report = Default::default();
+    for i in 0..120 {
+        let frame = if (i / period).is_multiple_of(2) { a } else { b };
+        report = fa.push(i as f64 * FRAME_MS, frame, W, W);
+    }
+    report
+}
+
+#[test]
+fn luminance_matches_wcag() {
+    assert_eq!(relative_luminance(0, 0, 0), 0.0);
+    assert!((relative_luminance(255, 255, 255) - 1.0).abs() < 1e-5);
+    assert!((relative_luminance(255, 0, 0) - 0.2126).abs() < 1e-4);
+}
+
+#[test]
+fn steady_and_slow_changes_are_safe() {
+    let (black, white) = (solid([0; 3]), solid([255; 3]));
+    assert_eq!(run(&black, &black, 1).flashes_per_s, 0.0);
+    // One full cycle per second: one flash
+    let slow = run(&black, &white, 30);
+    assert_eq!(slow.flashes_per_s, 1.0);
+    assert!(slow.is_safe());
+}
+
+#[test]
+fn fast_black_white_flashing_is_unsafe() {
+    // Toggling every 3 frames: 20 transitions, 10 flashes per second
+    let report = run(&solid([0; 3]), &solid([255; 3]), 3);
+    assert!(report.flashes_per_s >= 9.5, "{report:?}");
+    assert!(!report.is_safe());
+}
+
+#[test]
+fn flashing_between_bright_states_does_not_count() {
+    // Both states above the 0.8 dark limit
+    let report = run(&solid([235; 3]), &solid([255; 3]), 3);
+    assert_eq!(report.flashes_per_s, 0.0);
+}
+
+#[test]
+fn saturated_red_flashes_at_constant_luminance() {
+    let (red, grey) = (solid([255, 0, 0]), solid([127, 127, 127]));
+    assert!((relative_luminance(255, 0, 0) - relative_luminance(127, 127, 127)).abs() < 0.01);
+    let report = run(&red, &grey, 3);
+    assert_eq!(report.flashes_per_s, 0.0);
+    assert!(report.red_flashes_per_s > MAX_FLASHES_PER_S, "{report:?}");
+}
+
+#[test]
+fn a_flashing_corner_is_enough() {
+    let black = solid([0; 3]);
+    let mut corner = black.clone();
+    for y in 0..W / 4 {
+        for x in 0..W / 4 {
+            corner[(y * W + x) * 4..][..3].copy_from_slice(&[255; 3]);
+        }
+    }
+    assert!(!run(&black, &corner, 3).is_safe());
+}
+
+#[test]
+fn limiter_tightens_fast_and_relaxes_slowly() {
+    let (black, white) = (solid([0; 3]), solid([255; 3]));
+    let mut fa = FlashAnalyzer::default();
+    let mut limiter = SafetyLimiter::default();
+    let loose = limiter.limits();
+    assert!(loose.max_flash_hz < MAX_FLASHES_PER_S);
+    for i in 0..60usize {
+        let frame = if (i / 3).is_multiple_of(2) { &black } else { &white };
+        limiter.update(&fa.push(i as f64 * FRAME_MS, frame, W, W));
+    }
+    let tight = limiter.limits();
+    assert_eq!(limiter.level(), SafetyLimiter::MIN_LEVEL);
+    assert!(tight.max_flash_hz < loose.max_flash_hz && tight.contrast < loose.contrast);
+    // A few calm seconds later it has only partly recovered
+    for i in 60..300 {
+        limiter.update(&fa.push(i as f64 * FRAME_MS, &black, W, W));
+    }
+    assert!(limiter.level() > SafetyLimiter::MIN_LEVEL && limiter.level() < 1.0);
+}
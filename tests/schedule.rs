@@ -0,0 +1,35 @@
+use viz_wasm::schedule::{Entry, Playlist};
+
+#[test]
+fn next_and_prev_wrap_around() {
+    let mut p = Playlist::sequential(3, 1000.0);
+    assert_eq!(p.current().map(|e| e.scene), Some(0));
+    assert_eq!(p.prev_entry().map(|e| e.scene), Some(2));
+    assert_eq!(p.next_entry().map(|e| e.scene), Some(0));
+    p.next_entry();
+    p.next_entry();
+    assert_eq!(p.next_entry().map(|e| e.scene), Some(0));
+}
+
+#[test]
+fn goto_and_find_scene() {
+    let mut p = Playlist::new(vec![
+        Entry::new(2, 5000.0),
+        Entry::new(0, 8000.0),
+        Entry::new(2, 1000.0),
+    ]);
+    assert_eq!(p.find_scene(2), Some(0));
+    assert_eq!(p.find_scene(1), None);
+    assert_eq!(p.goto(1).map(|e| e.duration_ms), Some(8000.0));
+    // Out of range: cursor stays put
+    assert_eq!(p.goto(7).map(|e| e.scene), Some(0));
+    assert_eq!(p.position(), 1);
+}
+
+#[test]
+fn empty_playlist_has_no_current_entry() {
+    let mut p = Playlist::sequential(0, 1000.0);
+    assert!(p.is_empty());
+    assert!(p.next_entry().is_none());
+    assert!(p.prev_entry().is_none());
+}
@@ -0,0 +1,99 @@
+use viz_wasm::motion::MotionPref;
+use viz_wasm::pattern::{PatternParams, Rng};
+use viz_wasm::query::Fill;
+use viz_wasm::settings::{Preset, Presets, Settings, MAX_SLOTS};
+
+fn preset(name: &str, scene: &str) -> Preset {
+    Preset { name: name.into(), scene: Some(scene.into()), ..Preset::default() }
+}
+
+#[test]
+fn settings_round_trip_through_json() {
+    let mut rng = Rng::new(7);
+    let mut s = Settings {
+        scene: Some("Pulsing Plus".into()),
+        locked_params: Some(PatternParams::random(|| rng.next_f32())),
+        hud: true,
+        ..Settings::default()
+    };
+    s.calibration.zoom = 1.5;
+    s.presets.save(preset("Party", "Rotating Square"));
+    assert_eq!(Settings::from_json(&s.to_json()), Ok(s));
+}
+
+#[test]
+fn missing_fields_take_defaults() {
+    let s = Settings::from_json(r#"{"scene":"Pulsing Plus","lockedParams":{"density":4}}"#).unwrap();
+    assert_eq!(s.scene.as_deref(), Some("Pulsing Plus"));
+    assert!(s.overlay);
+    assert_eq!(s.calibration.zoom, 1.0);
+    let params = s.locked_params.unwrap();
+    assert_eq!(params.density, 4.0);
+    assert_eq!(params.thickness, PatternParams::default().thickness);
+    assert!(Settings::from_json("{\"scene\":").is_err());
+}
+
+#[test]
+fn presets_are_unique_by_name() {
+    let mut p = Presets::default();
+    p.save(preset("Lobby calm", "Pulsing Plus"));
+    p.save(preset("Party", "Rotating Square"));
+    p.save(Preset { speed: 2.0, ..preset("Lobby calm", "Rotating Square") });
+    assert_eq!(p.names().collect::<Vec<_>>(), ["Lobby calm", "Party"]);
+    assert_eq!(p.get("Lobby calm").map(|p| p.speed), Some(2.0));
+    assert!(p.remove("Party"));
+    assert!(!p.remove("Party"));
+}
+
+#[test]
+fn slots_keep_names_and_leave_gaps() {
+    let mut p = Presets::default();
+    p.save(preset("Lobby calm", "Pulsing Plus"));
+    assert!(p.save_slot(1, preset("", "Rotating Square")));
+    assert_eq!(p.slot(1).map(|p| (p.name.as_str(), p.scene.as_deref())), Some(("Lobby calm", Some("Rotating Square"))));
+    assert!(p.save_slot(4, preset("", "Pulsing Plus")));
+    assert_eq!(p.slot(4).map(|p| p.name.as_str()), Some("Preset 4"));
+    assert_eq!(p.slots().collect::<Vec<_>>(), [Some("Lobby calm"), None, None, Some("Preset 4")]);
+    assert_eq!(p.len(), 2);
+    // A new name takes the first gap; removing one leaves the others in place
+    p.save(preset("Party", "Rotating Square"));
+    assert_eq!(p.slot(2).map(|p| p.name.as_str()), Some("Party"));
+    assert!(p.remove("Party"));
+    assert_eq!(p.slot(4).map(|p| p.name.as_str()), Some("Preset 4"));
+    assert_eq!(Presets::from_json(&p.to_json()), Ok(p.clone()));
+
+    assert!(!p.save_slot(0, preset("", "Pulsing Plus")));
+    assert!(!p.save_slot(MAX_SLOTS + 1, preset("", "Pulsing Plus")));
+    assert!(p.slot(0).is_none());
+}
+
+#[test]
+fn presets_keep_the_whole_look() {
+    let look = Preset {
+        fill: Some(Fill::Polka),
+        zoom: 2.0,
+        rotation: 0.5,
+        safety: true,
+        motion: MotionPref::Reduce,
+        ..preset("Calm", "Pulsing Plus")
+    };
+    let mut p = Presets::default();
+    p.save(look.clone());
+    assert_eq!(Presets::from_json(&p.to_json()).unwrap().get("Calm"), Some(&look));
+    // Saved before these fields existed
+    let old = Presets::from_json(r#"[{"name":"Old","scene":"Pulsing Plus","speed":2}]"#).unwrap();
+    assert_eq!(old.slot(1), Some(&Preset { speed: 2.0, ..preset("Old", "Pulsing Plus") }));
+}
+
+#[test]
+fn import_merges_exported_file() {
+    let mut ours = Presets::default();
+    ours.save(preset("Lobby calm", "Pulsing Plus"));
+    let mut theirs = Presets::default();
+    theirs.save(preset("Party", "Rotating Square"));
+    theirs.save(Preset { blackout: true, ..preset("Lobby calm", "Pulsing Plus") });
+    ours.merge(Presets::from_json(&theirs.to_json()).unwrap());
+    assert_eq!(ours.len(), 2);
+    assert!(ours.get("Lobby calm").unwrap().blackout);
+    assert!(Presets::from_json("{}").is_err());
+}
@@ -0,0 +1,46 @@
+use viz_wasm::pattern::{PatternParams, Rng};
+use viz_wasm::query::Fill;
+use viz_wasm::show::{ClockState, Effects, ShowState, BINARY_VERSION};
+
+fn state() -> ShowState {
+    let mut rng = Rng::new(42);
+    let params = PatternParams::random(|| rng.next_f32());
+    ShowState {
+        scene: Some("Pulsing Plus".into()),
+        entry: 3,
+        clock: ClockState { time_ms: 81_234.5, scene_ms: 1_234.5, speed: 0.5, paused: true },
+        seed: Some(rng.state()),
+        fill: Some(Fill::Polka),
+        params,
+        params_locked: true,
+        effects: Effects { blackout: false, frozen: true, zoom: 1.25, rotation: -0.5 },
+    }
+}
+
+#[test]
+fn json_and_binary_round_trip() {
+    let s = state();
+    assert_eq!(ShowState::from_json(&s.to_json()), Ok(s.clone()));
+    assert_eq!(ShowState::from_bytes(&s.to_bytes()), Ok(s.clone()));
+    // The binary form is the compact one
+    assert!(s.to_bytes().len() < s.to_json().len() / 2);
+    assert!(s.to_json().contains("\"fill\":\"polka\""));
+}
+
+#[test]
+fn rng_state_continues_the_sequence() {
+    let mut a = Rng::new(9);
+    a.next_f32();
+    let mut b = Rng::new(a.state());
+    assert_eq!(a.next_f32(), b.next_f32());
+}
+
+#[test]
+fn rejects_bad_binary() {
+    let mut bytes = state().to_bytes();
+    assert_eq!(bytes[0], BINARY_VERSION);
+    bytes[0] = BINARY_VERSION + 1;
+    assert!(ShowState::from_bytes(&bytes).unwrap_err().contains("version"));
+    assert!(ShowState::from_bytes(&[]).is_err());
+    assert!(ShowState::from_bytes(&[BINARY_VERSION, 1, 2]).is_err());
+}
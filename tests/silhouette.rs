@@ -0,0 +1,60 @@
+use viz_wasm::silhouette::{jump_flood, jump_flood_steps, raster_size, Fit, SDF_RANGE};
+
+#[test]
+fn fit_scales_picture_into_the_square() {
+    // A picture twice as wide as tall
+    assert_eq!(Fit::Fill.scale(2.0), [1.0, 1.0]);
+    assert_eq!(Fit::Contain.scale(2.0), [1.0, 2.0]);
+    assert_eq!(Fit::Cover.scale(2.0), [0.5, 1.0]);
+    assert_eq!(Fit::Contain.scale(0.5), [2.0, 1.0]);
+    assert_eq!(Fit::Cover.scale(1.0), [1.0, 1.0]);
+    assert_eq!("cover".parse(), Ok(Fit::Cover));
+    assert!("stretch".parse::<Fit>().is_err());
+}
+
+#[test]
+fn rasterizes_the_long_side_at_raster_size() {
+    assert_eq!(raster_size(1024.0, 256.0), (512, 128));
+    assert_eq!(raster_size(30.0, 60.0), (256, 512));
+    assert_eq!(raster_size(0.0, 0.0), (512, 512));
+    assert_eq!(raster_size(f32::NAN, 10.0), (512, 512));
+    assert_eq!(jump_flood_steps(512, 128), vec![256, 128, 64, 32, 16, 8, 4, 2, 1]);
+    assert_eq!(jump_flood_steps(5, 3), vec![4, 2, 1]);
+    assert_eq!(jump_flood_steps(1, 1), vec![1]);
+}
+
+#[test]
+fn jump_flood_matches_brute_force() {
+    // A ring and a dot, so some pixels have their nearest outline far away
+    let (w, h) = (48usize, 40usize);
+    let inside: Vec<bool> = (0..w * h)
+        .map(|i| {
+            let (x, y) = ((i % w) as f32, (i / w) as f32);
+            let r = ((x - 20.0).powi(2) + (y - 18.0).powi(2)).sqrt();
+            (6.0..12.0).contains(&r) || ((x - 40.0).powi(2) + (y - 33.0).powi(2)) < 4.0
+        })
+        .collect();
+    let at = |x: i64, y: i64| x >= 0 && y >= 0 && x < w as i64 && y < h as i64 && inside[y as usize * w + x as usize];
+    let outline: Vec<(i64, i64)> = (0..w * h)
+        .map(|i| ((i % w) as i64, (i / w) as i64))
+        .filter(|&(x, y)| [(1, 0), (-1, 0), (0, 1), (0, -1)].iter().any(|(dx, dy)| at(x + dx, y + dy) != at(x, y)))
+        .collect();
+
+    let field = jump_flood(&inside, w, h);
+    for (i, d) in field.iter().enumerate() {
+        let (x, y) = ((i % w) as i64, (i / w) as i64);
+        assert_eq!(*d < 0.0, inside[i], "sign at {x},{y}");
+        let exact = outline
+            .iter()
+            .map(|(sx, sy)| (((sx - x).pow(2) + (sy - y).pow(2)) as f32).sqrt())
+            .fold(f32::MAX, f32::min);
+        let exact = (exact + 0.5).min(SDF_RANGE);
+        assert!((d.abs() - exact).abs() <= 1.0, "{x},{y}: {d} vs {exact}");
+    }
+}
+
+#[test]
+fn empty_picture_saturates() {
+    let field = jump_flood(&[false; 16], 4, 4);
+    assert!(field.iter().all(|d| *d == SDF_RANGE));
+}
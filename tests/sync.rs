@@ -0,0 +1,80 @@
+use viz_wasm::pattern::PatternParams;
+use viz_wasm::show::{ClockState, Effects, ShowState};
+use viz_wasm::sync::{same_show, ClockSync, Relay, Role, SyncState, TimeReply};
+
+fn show(time_ms: f64, speed: f64, paused: bool) -> ShowState {
+    ShowState {
+        scene: Some("Pulsing Plus".into()),
+        entry: 1,
+        clock: ClockState { time_ms, scene_ms: 500.0, speed, paused },
+        seed: Some(7),
+        fill: None,
+        params: PatternParams::default(),
+        params_locked: false,
+        effects: Effects { blackout: false, frozen: false, zoom: 1.0, rotation: 0.0 },
+    }
+}
+
+#[test]
+fn clock_offset_uses_the_fastest_round_trip() {
+    let mut c = ClockSync::default();
+    assert_eq!(c.host_now(0.0), None);
+    // Host is 1000 ms ahead; a slow reply that spent its delay on the way back
+    c.sample(100.0, 1110.0, 300.0);
+    c.sample(400.0, 1410.0, 420.0);
+    assert_eq!(c.rtt(), Some(20.0));
+    assert_eq!(c.offset(), Some(1000.0));
+    assert_eq!(c.host_now(50.0), Some(1050.0));
+    // Clock going backwards is not a sample
+    c.sample(500.0, 0.0, 499.0);
+    assert_eq!(c.rtt(), Some(20.0));
+    // Only the latest samples count
+    for i in 0..ClockSync::KEEP {
+        let t = 1000.0 + i as f64 * 100.0;
+        c.sample(t, t + 2040.0, t + 80.0);
+    }
+    assert_eq!(c.offset(), Some(2000.0));
+}
+
+#[test]
+fn followers_advance_the_leader_clock() {
+    let s = SyncState { seq: 1, host_ms: 10_000.0, show: show(3000.0, 2.0, false) };
+    assert_eq!(s.playback_ms(10_500.0), 4000.0);
+    let later = s.at(10_500.0);
+    assert_eq!((later.clock.time_ms, later.clock.scene_ms), (4000.0, 1500.0));
+    assert!(same_show(&later, &s.show));
+    // Never runs backwards, and stands still while paused
+    assert_eq!(s.playback_ms(9000.0), 3000.0);
+    let paused = SyncState { show: show(3000.0, 2.0, true), ..s.clone() };
+    assert_eq!(paused.playback_ms(20_000.0), 3000.0);
+    assert!(!same_show(&paused.show, &s.show));
+}
+
+#[test]
+fn relay_numbers_states_and_serves_newer_ones() {
+    let mut relay = Relay::default();
+    let time = relay.handle("GET", "/time", "", 1234.5);
+    assert_eq!(serde_json::from_str::<TimeReply>(&time.body).unwrap().host_ms, 1234.5);
+    assert_eq!(relay.handle("GET", "/state?after=0", "", 0.0).status, 204);
+
+    let post = SyncState { seq: 99, host_ms: 50.0, show: show(0.0, 1.0, false) };
+    let body = serde_json::to_string(&post).unwrap();
+    assert_eq!(relay.handle("POST", "/state", &body, 0.0).status, 200);
+    assert_eq!(relay.handle("POST", "/state", &body, 0.0).body, r#"{"seq":2}"#);
+
+    let got = relay.handle("GET", "/state?after=1", "", 0.0);
+    let state: SyncState = serde_json::from_str(&got.body).unwrap();
+    assert_eq!(state, SyncState { seq: 2, ..post });
+    assert_eq!(relay.handle("GET", "/state?after=2", "", 0.0).status, 204);
+    assert_eq!(relay.handle("GET", "/state", "", 0.0).status, 200);
+
+    assert_eq!(relay.handle("POST", "/state", "{", 0.0).status, 400);
+    assert_eq!(relay.handle("DELETE", "/state", "", 0.0).status, 404);
+}
+
+#[test]
+fn parses_roles() {
+    assert_eq!("leader".parse(), Ok(Role::Leader));
+    assert_eq!("follower".parse(), Ok(Role::Follower));
+    assert!("boss".parse::<Role>().is_err());
+}
@@ -0,0 +1,39 @@
+use viz_wasm::text::{embedded_atlas, format_countdown, layout, TextConfig, MARGIN};
+
+#[test]
+fn counts_down_to_the_target() {
+    assert_eq!(format_countdown(65_000.0), "01:05");
+    assert_eq!(format_countdown(64_001.0), "01:05");
+    assert_eq!(format_countdown(3_600_000.0), "1:00:00");
+    assert_eq!(format_countdown(90_061_000.0), "1d 01:01:01");
+    assert_eq!(format_countdown(-5_000.0), "00:00");
+
+    let mut c = TextConfig::new("NEON NIGHTS\n{countdown}\n@club");
+    assert_eq!(c.message(0.0), "NEON NIGHTS\n00:00\n@club");
+    c.countdown_to = Some(10_000.0);
+    assert_eq!(c.message(1_000.0), "NEON NIGHTS\n00:09\n@club");
+    assert_eq!(TextConfig::new("plain").message(1.0), "plain");
+}
+
+#[test]
+fn centers_lines_inside_the_margin() {
+    let atlas = embedded_atlas();
+    let l = layout(&atlas, "HELLO\nhi");
+    // Blank glyphs make no quads; unknown characters fall back to "?"
+    assert_eq!(l.quads.len(), 7);
+    assert_eq!(layout(&atlas, "a b").quads.len(), 2);
+    assert_eq!(layout(&atlas, "\u{2603}").quads.len(), 1);
+    assert!(layout(&atlas, "").quads.is_empty());
+    assert!(l.px > 0.0);
+
+    // Ink stays inside the margin (the quads carry the field padding)
+    let pad = atlas.range * l.px;
+    let (x0, x1) = l.quads.iter().fold((f32::MAX, f32::MIN), |(a, b), q| (a.min(q.pos[0]), b.max(q.pos[2])));
+    assert!(x0 + pad >= -MARGIN - 1e-3 && x1 - pad <= MARGIN + 1e-3);
+    assert!((x0 + x1).abs() < 0.05, "widest line centered: {x0} {x1}");
+    // First line above the second
+    assert!(l.quads[0].pos[1] > l.quads[5].pos[3] - 2.0 * pad);
+    for q in &l.quads {
+        assert!(q.uv.iter().all(|v| (0.0..=1.0).contains(v)));
+    }
+}
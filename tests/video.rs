@@ -0,0 +1,50 @@
+use viz_wasm::video::{parse_hex_color, MaskMode, VideoConfig, VideoSource};
+
+const BLACK: [f32; 3] = [0.0; 3];
+const WHITE: [f32; 3] = [1.0; 3];
+const GREEN: [f32; 3] = [0.0, 1.0, 0.0];
+
+#[test]
+fn luminance_keys_bright_pixels() {
+    let m = MaskMode::default();
+    assert_eq!(m.mask(WHITE, WHITE), 1.0);
+    assert_eq!(m.mask(BLACK, BLACK), 0.0);
+    // Soft around the threshold
+    let mid = m.mask([0.5; 3], BLACK);
+    assert!(mid > 0.4 && mid < 0.6, "{mid}");
+}
+
+#[test]
+fn chroma_key_drops_the_key_colour_only() {
+    let m: MaskMode = "chroma".parse().unwrap();
+    assert_eq!(m.mask(GREEN, GREEN), 0.0);
+    assert_eq!(m.mask([0.1, 0.8, 0.1], BLACK), 0.0, "near green is keyed too");
+    assert_eq!(m.mask([0.9, 0.2, 0.6], BLACK), 1.0);
+    assert_eq!(m.mask([0.8, 0.6, 0.5], BLACK), 1.0, "skin tones stay");
+    // Grey has no chroma and stays at any brightness
+    assert_eq!(m.mask(WHITE, BLACK), 1.0);
+}
+
+#[test]
+fn difference_keys_changed_pixels() {
+    let m: MaskMode = "difference".parse().unwrap();
+    assert_eq!(m.mask(WHITE, BLACK), 1.0);
+    assert_eq!(m.mask(WHITE, WHITE), 0.0);
+    assert_eq!(m.mask([0.52; 3], [0.5; 3]), 0.0, "sensor noise is ignored");
+    assert_eq!(m.with_threshold(0.3).threshold(), 0.3);
+    assert_eq!(m.id(), 2);
+}
+
+#[test]
+fn parses_sources_modes_and_colours() {
+    assert_eq!("webcam".parse(), Ok(VideoSource::Webcam));
+    assert_eq!("clips/loop.mp4".parse(), Ok(VideoSource::Url("clips/loop.mp4".into())));
+    assert!("".parse::<VideoSource>().is_err());
+    assert!("sepia".parse::<MaskMode>().is_err());
+    assert_eq!(parse_hex_color("#00ff00"), Some(GREEN));
+    assert_eq!(parse_hex_color("ffffff"), Some(WHITE));
+    assert_eq!(parse_hex_color("#0f0"), None);
+    // Webcams are mirrored by default, files are not
+    assert!(VideoConfig::new(VideoSource::Webcam).mirror);
+    assert_eq!(VideoConfig::new(VideoSource::Url("a.webm".into())).scene_name(), "Video");
+}